@@ -0,0 +1,268 @@
+use std::io::{BufReader, Read, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use dap::{
+  base_message::{BaseMessage, Sendable},
+  errors::ServerError,
+  events::Event,
+  framing::{read_message, write_message, DEFAULT_MAX_CONTENT_LENGTH},
+  requests::{Command, Request, RequestEnvelope},
+  responses::{Response, ResponseMessage},
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::display;
+
+/// What the REPL remembers about the debuggee, so commands can default to the current thread
+/// and frame.
+#[derive(Debug, Default)]
+pub struct Context {
+  pub thread_id: Option<i64>,
+  pub frame_id: Option<i64>,
+}
+
+/// The writing half of the connection, shared with the thread that answers reverse requests.
+struct Output {
+  writer: Box<dyn Write + Send>,
+  seq: i64,
+}
+
+impl Output {
+  fn request(&mut self, command: Command) -> Result<i64, ServerError> {
+    self.seq += 1;
+    let request = Request {
+      seq: self.seq,
+      command,
+    };
+    write_message(&mut self.writer, &request)?;
+    Ok(self.seq)
+  }
+
+  fn respond(&mut self, response: Response) -> Result<(), ServerError> {
+    self.seq += 1;
+    let message = BaseMessage {
+      seq: self.seq,
+      message: Sendable::Response(response),
+    };
+    write_message(&mut self.writer, &message)
+  }
+}
+
+/// The connection to the adapter.
+///
+/// Events are printed by a background thread as soon as they arrive; responses are handed to
+/// the REPL, which waits for them.
+pub struct Client {
+  output: Arc<Mutex<Output>>,
+  responses: Receiver<Response>,
+  pub context: Arc<Mutex<Context>>,
+}
+
+impl Client {
+  pub fn start<R: Read + Send + 'static>(input: R, writer: Box<dyn Write + Send>) -> Self {
+    let context = Arc::new(Mutex::new(Context::default()));
+    let output = Arc::new(Mutex::new(Output { writer, seq: 0 }));
+    let (sender, responses) = channel();
+
+    let reader = Reader {
+      sender,
+      output: output.clone(),
+      context: context.clone(),
+    };
+    thread::spawn(move || reader.run(input));
+
+    Self {
+      output,
+      responses,
+      context,
+    }
+  }
+
+  /// Send a request and wait for its response.
+  pub fn request(&mut self, command: Command, timeout: Duration) -> Result<Response, String> {
+    let seq = self
+      .output
+      .lock()
+      .map_err(|_| "the connection is broken".to_string())?
+      .request(command)
+      .map_err(|e| e.to_string())?;
+
+    loop {
+      match self.responses.recv_timeout(timeout) {
+        Ok(response) if response.request_seq == seq => return Ok(response),
+        // A late response to a request that timed out earlier.
+        Ok(response) => println!("{}", display::response(&response)),
+        Err(RecvTimeoutError::Timeout) => return Err("timed out waiting for a response".into()),
+        Err(RecvTimeoutError::Disconnected) => return Err("the adapter is gone".into()),
+      }
+    }
+  }
+}
+
+/// Reads the adapter's messages on a background thread.
+struct Reader {
+  sender: Sender<Response>,
+  output: Arc<Mutex<Output>>,
+  context: Arc<Mutex<Context>>,
+}
+
+impl Reader {
+  fn run<R: Read>(self, input: R) {
+    let mut input = BufReader::new(input);
+    loop {
+      match read_message::<_, Value>(&mut input, DEFAULT_MAX_CONTENT_LENGTH) {
+        Ok(Some(message)) => {
+          if !self.dispatch(message) {
+            return;
+          }
+        }
+        Ok(None) => {
+          println!("<adapter closed the connection>");
+          return;
+        }
+        Err(e) => {
+          println!("<could not read from the adapter: {}>", e);
+          return;
+        }
+      }
+    }
+  }
+
+  /// Handle a single message. Returns `false` once the REPL has gone away.
+  fn dispatch(&self, message: Value) -> bool {
+    match BaseMessage::deserialize(&message) {
+      Ok(BaseMessage {
+        message: Sendable::Response(response),
+        ..
+      }) => return self.sender.send(response).is_ok(),
+      Ok(BaseMessage {
+        message: Sendable::Event(event),
+        ..
+      }) => {
+        if let Event::Stopped(body) = &event {
+          if let Ok(mut context) = self.context.lock() {
+            context.thread_id = body.thread_id.or(context.thread_id);
+            context.frame_id = None;
+          }
+        }
+        println!("{}", display::event(&event));
+      }
+      Ok(BaseMessage {
+        seq,
+        message: Sendable::ReverseRequest(request),
+      }) => self.decline(seq, request.command.name()),
+      // Reverse requests this crate doesn't know still have to be answered.
+      Err(_) if message["type"] == "request" => match RequestEnvelope::deserialize(&message) {
+        Ok(envelope) => self.decline(envelope.seq, &envelope.command),
+        Err(_) => println!("{}", display::json(&message)),
+      },
+      Err(_) => println!("{}", display::json(&message)),
+    }
+    true
+  }
+
+  /// Answer a reverse request with an error, so the adapter doesn't wait for it forever.
+  fn decline(&self, request_seq: i64, command: &str) {
+    println!("* declined '{}' request", command);
+    let response = Response {
+      request_seq,
+      success: false,
+      message: Some(ResponseMessage::Error(
+        "not supported by dap-cli".to_string(),
+      )),
+      command: Some(command.to_string()),
+      ..Default::default()
+    };
+    let sent = match self.output.lock() {
+      Ok(mut output) => output.respond(response).map_err(|e| e.to_string()),
+      Err(_) => Err("the connection is broken".to_string()),
+    };
+    if let Err(e) = sent {
+      println!("<could not answer '{}': {}>", command, e);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  /// Collects what the client writes.
+  #[derive(Clone, Default)]
+  struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  fn frames(messages: &[Value]) -> Vec<u8> {
+    let mut input = Vec::new();
+    for message in messages {
+      write_message(&mut input, message).unwrap();
+    }
+    input
+  }
+
+  #[test]
+  fn test_reverse_requests_are_declined() {
+    let input = frames(&[
+      json!({"seq": 1, "type": "request", "command": "runInTerminal",
+        "arguments": {"cwd": "/", "args": ["ls"]}}),
+      json!({"seq": 2, "type": "request", "command": "somethingNew"}),
+    ]);
+    let buffer = Buffer::default();
+    let output = Arc::new(Mutex::new(Output {
+      writer: Box::new(buffer.clone()),
+      seq: 0,
+    }));
+    let reader = Reader {
+      sender: channel().0,
+      output,
+      context: Default::default(),
+    };
+    reader.run(input.as_slice());
+
+    let written = buffer.0.lock().unwrap().clone();
+    let mut written = written.as_slice();
+    for (seq, command) in [(1, "runInTerminal"), (2, "somethingNew")] {
+      let response: Value = read_message(&mut written, DEFAULT_MAX_CONTENT_LENGTH)
+        .unwrap()
+        .unwrap();
+      assert_eq!(response["type"], "response");
+      assert_eq!(response["seq"], seq);
+      assert_eq!(response["request_seq"], seq);
+      assert_eq!(response["command"], command);
+      assert_eq!(response["success"], false);
+    }
+  }
+
+  #[test]
+  fn test_requests_are_typed() {
+    let buffer = Buffer::default();
+    let mut output = Output {
+      writer: Box::new(buffer.clone()),
+      seq: 0,
+    };
+    assert_eq!(output.request(Command::Threads).unwrap(), 1);
+    let written = buffer.0.lock().unwrap().clone();
+    let request: Value = read_message(&mut written.as_slice(), DEFAULT_MAX_CONTENT_LENGTH)
+      .unwrap()
+      .unwrap();
+    assert_eq!(
+      request,
+      json!({"seq": 1, "type": "request", "command": "threads"})
+    );
+  }
+}
@@ -0,0 +1,185 @@
+use dap::{
+  events::Event,
+  responses::{Response, ResponseBody},
+};
+use serde_json::Value;
+
+pub fn json(value: &Value) -> String {
+  serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// The string form of a protocol enum, e.g. `breakpoint` for a stop reason.
+fn name<T: serde::Serialize>(value: &T) -> String {
+  match serde_json::to_value(value) {
+    Ok(Value::String(name)) => name,
+    Ok(other) => other.to_string(),
+    Err(_) => "?".to_string(),
+  }
+}
+
+pub fn event(event: &Event) -> String {
+  match event.clone() {
+    Event::Initialized => "* initialized".to_string(),
+    Event::Stopped(body) => {
+      let mut line = format!("* stopped ({})", name(&body.reason));
+      if let Some(thread_id) = body.thread_id {
+        line += &format!(" in thread {}", thread_id);
+      }
+      if let Some(description) = body.description.or(body.text) {
+        line += &format!(": {}", description);
+      }
+      line
+    }
+    Event::Continued(body) => format!("* continued thread {}", body.thread_id),
+    Event::Output(body) => {
+      let category = body
+        .category
+        .map(|c| name(&c))
+        .unwrap_or_else(|| "console".to_string());
+      format!("[{}] {}", category, body.output.trim_end())
+    }
+    Event::Thread(body) => format!("* thread {} {}", body.thread_id, name(&body.reason)),
+    Event::Exited(body) => format!("* exited with code {}", body.exit_code),
+    Event::Terminated(_) => "* terminated".to_string(),
+    other => {
+      let message = serde_json::to_value(&other).unwrap_or_default();
+      format!(
+        "* {} {}",
+        message["event"].as_str().unwrap_or("event"),
+        json(&message["body"])
+      )
+    }
+  }
+}
+
+pub fn response(response: &Response) -> String {
+  if !response.success {
+    let reason = match (&response.error, &response.message) {
+      (Some(error), _) => error.format.clone(),
+      (None, Some(message)) => name(message),
+      (None, None) => "request failed".to_string(),
+    };
+    return format!("error: {}", reason);
+  }
+
+  match &response.body {
+    Some(ResponseBody::Threads(body)) => body
+      .threads
+      .iter()
+      .map(|t| format!("  {:>4}  {}", t.id, t.name))
+      .collect::<Vec<_>>()
+      .join("\n"),
+    Some(ResponseBody::StackTrace(body)) => body
+      .stack_frames
+      .iter()
+      .enumerate()
+      .map(|(i, frame)| {
+        let location = frame
+          .source
+          .as_ref()
+          .and_then(|s| s.path.clone().or_else(|| s.name.clone()))
+          .unwrap_or_else(|| "?".to_string());
+        format!(
+          "  #{:<2} [{}] {} at {}:{}",
+          i, frame.id, frame.name, location, frame.line
+        )
+      })
+      .collect::<Vec<_>>()
+      .join("\n"),
+    Some(ResponseBody::Scopes(body)) => body
+      .scopes
+      .iter()
+      .map(|s| format!("  {} (ref {})", s.name, s.variables_reference))
+      .collect::<Vec<_>>()
+      .join("\n"),
+    Some(ResponseBody::Variables(body)) => body
+      .variables
+      .iter()
+      .map(|v| {
+        let mut line = format!("  {}", v.name);
+        if let Some(type_field) = &v.type_field {
+          line += &format!(": {}", type_field);
+        }
+        line += &format!(" = {}", v.value);
+        if v.variables_reference > 0 {
+          line += &format!(" (ref {})", v.variables_reference);
+        }
+        line
+      })
+      .collect::<Vec<_>>()
+      .join("\n"),
+    Some(ResponseBody::Evaluate(body)) => {
+      if body.variables_reference > 0 {
+        format!("{} (ref {})", body.result, body.variables_reference)
+      } else {
+        body.result.clone()
+      }
+    }
+    Some(ResponseBody::SetBreakpoints(body)) => body
+      .breakpoints
+      .iter()
+      .map(|bp| {
+        let line = bp.line.map(|l| l.to_string()).unwrap_or_else(|| "?".into());
+        let state = if bp.verified { "verified" } else { "pending" };
+        match &bp.message {
+          Some(message) => format!("  line {}: {} ({})", line, state, message),
+          None => format!("  line {}: {}", line, state),
+        }
+      })
+      .collect::<Vec<_>>()
+      .join("\n"),
+    None => "ok".to_string(),
+    Some(body) => {
+      let message = serde_json::to_value(body).unwrap_or_default();
+      if message["body"].is_null() {
+        "ok".to_string()
+      } else {
+        json(&message["body"])
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_stack_trace() {
+    let message = json!({
+      "seq": 4, "type": "response", "request_seq": 3, "success": true, "command": "stackTrace",
+      "body": {"stackFrames": [
+        {"id": 1000, "name": "main", "line": 12, "column": 1, "source": {"path": "src/main.rs"}}
+      ]}
+    });
+    assert_eq!(
+      response(&serde_json::from_value(message).unwrap()),
+      "  #0  [1000] main at src/main.rs:12"
+    );
+  }
+
+  #[test]
+  fn test_error_response() {
+    let message = json!({
+      "seq": 4, "type": "response", "request_seq": 3, "success": false, "command": "evaluate",
+      "message": "notStopped"
+    });
+    assert_eq!(
+      response(&serde_json::from_value(message).unwrap()),
+      "error: notStopped"
+    );
+  }
+
+  #[test]
+  fn test_stopped_event() {
+    let message = json!({
+      "seq": 5, "type": "event", "event": "stopped",
+      "body": {"reason": "breakpoint", "threadId": 1}
+    });
+    assert_eq!(
+      event(&serde_json::from_value(message).unwrap()),
+      "* stopped (breakpoint) in thread 1"
+    );
+  }
+}
@@ -0,0 +1,122 @@
+//! An interactive command-line client for debug adapters.
+//!
+//! ```text
+//! dap-cli <adapter> [args...]      launch the adapter and talk to it over stdio
+//! dap-cli --connect <transport>    connect to a running adapter, e.g. tcp://127.0.0.1:4711
+//! ```
+mod client;
+mod display;
+mod repl;
+
+use std::io::{self, BufRead, Write};
+use std::process::{self, Command};
+use std::time::Duration;
+
+use dap::{
+  proxy::{Upstream, UpstreamConnection},
+  responses::{Response, ResponseBody},
+  transport::Transport,
+};
+
+use client::Client;
+use repl::Action;
+
+const USAGE: &str = "\
+usage: dap-cli <adapter> [args...]
+       dap-cli --connect <transport>
+
+The transport is tcp://<host>:<port> or unix://<path>.";
+
+/// How long to wait for the response to a request.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn parse_args(args: &[String]) -> Result<Upstream, String> {
+  match args {
+    [] => Err("missing adapter".to_string()),
+    [flag, spec] if flag == "--connect" => spec
+      .parse::<Transport>()
+      .map(Upstream::Connect)
+      .map_err(|e| e.to_string()),
+    [flag, ..] if flag == "--help" || flag == "-h" => Err(String::new()),
+    [program, args @ ..] => {
+      let mut command = Command::new(program);
+      command.args(args);
+      Ok(Upstream::Spawn(command))
+    }
+  }
+}
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let upstream = match parse_args(&args) {
+    Ok(upstream) => upstream,
+    Err(message) => {
+      if !message.is_empty() {
+        eprintln!("error: {}\n", message);
+      }
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    }
+  };
+  let UpstreamConnection {
+    reader,
+    writer,
+    child,
+  } = match upstream.open() {
+    Ok(connection) => connection,
+    Err(e) => {
+      eprintln!("error: could not start the adapter: {}", e);
+      process::exit(1);
+    }
+  };
+
+  let mut client = Client::start(reader, writer);
+  println!("Connected. Type 'help' for a list of commands.");
+
+  let stdin = io::stdin();
+  loop {
+    print!("(dap) ");
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    match stdin.lock().read_line(&mut line) {
+      Ok(0) | Err(_) => break,
+      Ok(_) => {}
+    }
+
+    let action = {
+      let context = client.context.lock().expect("context lock is poisoned");
+      repl::parse(&line, &context)
+    };
+    match action {
+      Ok(Action::Send(command)) => match client.request(*command, TIMEOUT) {
+        Ok(response) => {
+          remember_frame(&client, &response);
+          println!("{}", display::response(&response));
+        }
+        Err(e) => println!("error: {}", e),
+      },
+      Ok(Action::Help) => println!("{}", repl::HELP),
+      Ok(Action::Quit) => break,
+      Ok(Action::Nothing) => {}
+      Err(e) => println!("{}", e),
+    }
+  }
+
+  if let Some(mut child) = child {
+    let _ = child.kill();
+    let _ = child.wait();
+  }
+}
+
+/// Make the top frame of a stack trace the current frame.
+fn remember_frame(client: &Client, response: &Response) {
+  let frame = match &response.body {
+    Some(ResponseBody::StackTrace(body)) => body.stack_frames.first(),
+    _ => None,
+  };
+  if let Some(frame) = frame {
+    if let Ok(mut context) = client.context.lock() {
+      context.frame_id = Some(frame.id);
+    }
+  }
+}
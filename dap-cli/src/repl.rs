@@ -0,0 +1,228 @@
+use dap::{
+  requests::{
+    AttachRequestArguments, Command, ContinueArguments, DisconnectArguments, EvaluateArguments,
+    InitializeArguments, LaunchRequestArguments, NextArguments, PauseArguments, ScopesArguments,
+    SetBreakpointsArguments, StackTraceArguments, StepInArguments, StepOutArguments,
+    VariablesArguments,
+  },
+  types::{EvaluateArgumentsContext, Source, SourceBreakpoint},
+};
+use serde_json::Value;
+
+use crate::client::Context;
+
+pub const HELP: &str = "\
+Commands:
+  init [adapterID]             send the initialize request
+  launch <json>                launch with the given configuration
+  attach <json>                attach with the given configuration
+  break <file>:<line>[,<line>] set the breakpoints of a file
+  done                         send configurationDone
+  threads                      list threads
+  continue|c [thread]          resume execution
+  next|n [thread]              step over
+  step|s [thread]              step into
+  out [thread]                 step out
+  pause [thread]               suspend execution
+  stack|bt [thread]            show the call stack
+  scopes [frame]               show the scopes of a frame
+  vars <ref>                   show the variables of a scope or variable
+  eval <expr>                  evaluate an expression in the current frame
+  disconnect [terminate]       end the session
+  help                         show this help
+  quit                         exit";
+
+/// What to do with a line of input.
+#[derive(Debug)]
+pub enum Action {
+  Send(Box<Command>),
+  Help,
+  Quit,
+  Nothing,
+}
+
+pub fn parse(line: &str, context: &Context) -> Result<Action, String> {
+  let line = line.trim();
+  let (word, rest) = match line.split_once(char::is_whitespace) {
+    Some((word, rest)) => (word, rest.trim()),
+    None => (line, ""),
+  };
+
+  let command = match word {
+    "" => return Ok(Action::Nothing),
+    "help" | "?" => return Ok(Action::Help),
+    "quit" | "exit" | "q" => return Ok(Action::Quit),
+    "init" => Command::Initialize(InitializeArguments {
+      client_id: Some("dap-cli".to_string()),
+      client_name: Some("dap-cli".to_string()),
+      adapter_id: if rest.is_empty() { "dap-cli" } else { rest }.to_string(),
+      lines_start_at1: Some(true),
+      columns_start_at1: Some(true),
+      supports_variable_type: Some(true),
+      // Output is printed to the terminal as is, so escape sequences are rendered.
+      supports_ansi_styling: Some(true),
+      ..Default::default()
+    }),
+    "launch" => Command::Launch(LaunchRequestArguments {
+      additional_data: configuration(rest)?,
+      ..Default::default()
+    }),
+    "attach" => Command::Attach(AttachRequestArguments {
+      additional_data: configuration(rest)?,
+      ..Default::default()
+    }),
+    "break" | "b" => {
+      let (path, lines) = rest
+        .rsplit_once(':')
+        .ok_or("usage: break <file>:<line>[,<line>...]")?;
+      let breakpoints = lines
+        .split(',')
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+          l.trim()
+            .parse()
+            .map(|line| SourceBreakpoint {
+              line,
+              ..Default::default()
+            })
+            .map_err(|_| format!("invalid line number '{}'", l))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+      Command::SetBreakpoints(SetBreakpointsArguments {
+        source: Source {
+          path: Some(path.to_string()),
+          ..Default::default()
+        },
+        breakpoints: Some(breakpoints),
+        ..Default::default()
+      })
+    }
+    "done" | "configdone" => Command::ConfigurationDone,
+    "threads" => Command::Threads,
+    "continue" | "c" => Command::Continue(ContinueArguments {
+      thread_id: thread(rest, context)?,
+      ..Default::default()
+    }),
+    "next" | "n" => Command::Next(NextArguments {
+      thread_id: thread(rest, context)?,
+      ..Default::default()
+    }),
+    "step" | "s" => Command::StepIn(StepInArguments {
+      thread_id: thread(rest, context)?,
+      ..Default::default()
+    }),
+    "out" => Command::StepOut(StepOutArguments {
+      thread_id: thread(rest, context)?,
+      ..Default::default()
+    }),
+    "pause" => Command::Pause(PauseArguments {
+      thread_id: thread(rest, context)?,
+    }),
+    "stack" | "bt" => Command::StackTrace(StackTraceArguments {
+      thread_id: thread(rest, context)?,
+      ..Default::default()
+    }),
+    "scopes" => Command::Scopes(ScopesArguments {
+      frame_id: number(rest, context.frame_id, "frame")?,
+    }),
+    "vars" | "v" => Command::Variables(VariablesArguments {
+      variables_reference: number(rest, None, "variables reference")?,
+      ..Default::default()
+    }),
+    "eval" | "p" => {
+      if rest.is_empty() {
+        return Err("usage: eval <expression>".to_string());
+      }
+      Command::Evaluate(EvaluateArguments {
+        expression: rest.to_string(),
+        frame_id: context.frame_id,
+        context: Some(EvaluateArgumentsContext::Repl),
+        ..Default::default()
+      })
+    }
+    "disconnect" => Command::Disconnect(DisconnectArguments {
+      terminate_debuggee: Some(rest == "terminate"),
+      ..Default::default()
+    }),
+    other => return Err(format!("unknown command '{}', try 'help'", other)),
+  };
+  Ok(Action::Send(Box::new(command)))
+}
+
+fn configuration(json: &str) -> Result<Option<Value>, String> {
+  if json.is_empty() {
+    return Ok(None);
+  }
+  serde_json::from_str(json)
+    .map(Some)
+    .map_err(|e| format!("invalid configuration: {}", e))
+}
+
+fn thread(argument: &str, context: &Context) -> Result<i64, String> {
+  number(argument, context.thread_id, "thread")
+}
+
+fn number(argument: &str, default: Option<i64>, what: &str) -> Result<i64, String> {
+  if argument.is_empty() {
+    return default.ok_or_else(|| format!("no current {}, please specify one", what));
+  }
+  argument
+    .parse()
+    .map_err(|_| format!("invalid {} '{}'", what, argument))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn command(line: &str, context: &Context) -> Command {
+    match parse(line, context).unwrap() {
+      Action::Send(command) => *command,
+      other => panic!("unexpected action {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_break() {
+    match command("break src/main.rs:3,7", &Context::default()) {
+      Command::SetBreakpoints(args) => {
+        assert_eq!(args.source.path.as_deref(), Some("src/main.rs"));
+        let lines: Vec<i64> = args.breakpoints.unwrap().iter().map(|bp| bp.line).collect();
+        assert_eq!(lines, vec![3, 7]);
+      }
+      other => panic!("unexpected command {:?}", other),
+    }
+    assert!(parse("break main.rs", &Context::default()).is_err());
+  }
+
+  #[test]
+  fn test_defaults_from_context() {
+    let context = Context {
+      thread_id: Some(4),
+      frame_id: Some(1000),
+    };
+    match command("continue", &context) {
+      Command::Continue(args) => assert_eq!(args.thread_id, 4),
+      other => panic!("unexpected command {:?}", other),
+    }
+    match command("eval x + 1", &context) {
+      Command::Evaluate(args) => {
+        assert_eq!(args.expression, "x + 1");
+        assert_eq!(args.frame_id, Some(1000));
+      }
+      other => panic!("unexpected command {:?}", other),
+    }
+    assert!(parse("continue", &Context::default()).is_err());
+  }
+
+  #[test]
+  fn test_launch() {
+    match command(r#"launch {"program": "a.out"}"#, &Context::default()) {
+      Command::Launch(args) => {
+        assert_eq!(args.additional_data.unwrap()["program"], "a.out")
+      }
+      other => panic!("unexpected command {:?}", other),
+    }
+    assert!(parse("launch {", &Context::default()).is_err());
+  }
+}
@@ -0,0 +1,76 @@
+use std::process::Command;
+use std::time::Duration;
+
+use dap::{proxy::Upstream, transport::Transport};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Describes the adapter under test and the fixture program it debugs.
+///
+/// ```json
+/// {
+///   "adapter": {"command": ["my-adapter", "--stdio"]},
+///   "adapterId": "my-adapter",
+///   "launch": {"program": "fixtures/hello"},
+///   "breakpoint": {"path": "fixtures/hello.c", "line": 5}
+/// }
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+  pub adapter: AdapterConfig,
+  #[serde(default = "default_adapter_id")]
+  pub adapter_id: String,
+  /// Arguments of the `launch` request.
+  pub launch: Value,
+  /// A line of the fixture program that is executed and followed by another line of the same
+  /// function, so that stepping over it stops again.
+  pub breakpoint: BreakpointConfig,
+  /// How long to wait for each response or event.
+  #[serde(default = "default_timeout_ms")]
+  pub timeout_ms: u64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum AdapterConfig {
+  /// Spawn the adapter and talk to it over stdio.
+  Command(Vec<String>),
+  /// Connect to a running adapter, e.g. `tcp://127.0.0.1:4711`.
+  Connect(String),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BreakpointConfig {
+  pub path: String,
+  pub line: i64,
+}
+
+fn default_adapter_id() -> String {
+  "dap-conformance".to_string()
+}
+
+fn default_timeout_ms() -> u64 {
+  5000
+}
+
+impl Config {
+  pub fn timeout(&self) -> Duration {
+    Duration::from_millis(self.timeout_ms)
+  }
+
+  pub fn upstream(&self) -> Result<Upstream, String> {
+    match &self.adapter {
+      AdapterConfig::Command(args) => {
+        let (program, args) = args.split_first().ok_or("the adapter command is empty")?;
+        let mut command = Command::new(program);
+        command.args(args);
+        Ok(Upstream::Spawn(command))
+      }
+      AdapterConfig::Connect(spec) => spec
+        .parse::<Transport>()
+        .map(Upstream::Connect)
+        .map_err(|e| e.to_string()),
+    }
+  }
+}
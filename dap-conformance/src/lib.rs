@@ -0,0 +1,261 @@
+//! Runs a debug adapter through a standard battery of protocol scenarios.
+//!
+//! The scenarios cover the initialize handshake, the ordering of `configurationDone`, hitting a
+//! breakpoint in a fixture program, stepping, paging variables, terminating and disconnecting.
+//! Every message the adapter sends is validated against the bundled DAP JSON schema and checked
+//! for protocol violations such as missing or duplicate responses and events or reverse requests
+//! the client did not declare support for.
+pub mod config;
+pub mod scenarios;
+pub mod session;
+
+use std::io::{Read, Write};
+
+use dap::proxy::UpstreamConnection;
+
+pub use config::Config;
+pub use scenarios::{Outcome, Report, ScenarioResult};
+use session::Session;
+
+/// Start or connect to the adapter described by the configuration and run all scenarios.
+pub fn run(config: &Config) -> Result<Report, String> {
+  let UpstreamConnection {
+    reader,
+    writer,
+    child,
+  } = config
+    .upstream()?
+    .open()
+    .map_err(|e| format!("could not start the adapter: {}", e))?;
+  let report = run_with(reader, writer, config);
+  if let Some(mut child) = child {
+    let _ = child.kill();
+    let _ = child.wait();
+  }
+  Ok(report)
+}
+
+/// Run all scenarios against an adapter that is already connected.
+pub fn run_with<R, W>(reader: R, writer: W, config: &Config) -> Report
+where
+  R: Read + Send + 'static,
+  W: Write + Send + 'static,
+{
+  let mut session = Session::new(reader, Box::new(writer), config.timeout());
+  scenarios::run_all(&mut session, config)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use dap::{
+    events::{Event, StoppedEventBody},
+    prelude::*,
+    responses::{
+      ScopesResponse, SetBreakpointsResponse, StackTraceResponse, ThreadsResponse,
+      VariablesResponse,
+    },
+    test_support::{duplex, Endpoint},
+    types::{Breakpoint, Capabilities, Scope, StackFrame, StoppedEventReason, Thread, Variable},
+  };
+  use serde_json::json;
+
+  use super::*;
+
+  /// Ways in which the fake adapter breaks the protocol.
+  #[derive(Default, Clone, Copy)]
+  struct Bugs {
+    early_initialized: bool,
+    duplicate_threads_response: bool,
+    no_terminated_event: bool,
+  }
+
+  fn stopped(reason: StoppedEventReason) -> Event {
+    Event::Stopped(StoppedEventBody {
+      reason,
+      description: None,
+      thread_id: Some(1),
+      preserve_focus_hint: None,
+      text: None,
+      all_threads_stopped: Some(true),
+      hit_breakpoint_ids: None,
+    })
+  }
+
+  fn fake_adapter(endpoint: Endpoint, bugs: Bugs) {
+    let mut server = endpoint.into_server();
+    let mut line = 5;
+    while let Ok(Some(req)) = server.poll_request() {
+      let body = match &req.command {
+        Command::Initialize(_) => {
+          if bugs.early_initialized {
+            server.send_event(Event::Initialized).unwrap();
+          }
+          server
+            .respond(req.clone().success(ResponseBody::Initialize(Capabilities {
+              supports_configuration_done_request: Some(true),
+              supports_terminate_request: Some(true),
+              ..Default::default()
+            })))
+            .unwrap();
+          if !bugs.early_initialized {
+            server.send_event(Event::Initialized).unwrap();
+          }
+          continue;
+        }
+        Command::Launch(_) => ResponseBody::Launch,
+        Command::SetBreakpoints(args) => ResponseBody::SetBreakpoints(SetBreakpointsResponse {
+          breakpoints: args
+            .breakpoints
+            .iter()
+            .flatten()
+            .map(|bp| Breakpoint {
+              verified: true,
+              line: Some(bp.line),
+              ..Default::default()
+            })
+            .collect(),
+        }),
+        Command::ConfigurationDone => {
+          server
+            .respond(req.success(ResponseBody::ConfigurationDone))
+            .unwrap();
+          server
+            .send_event(stopped(StoppedEventReason::Breakpoint))
+            .unwrap();
+          continue;
+        }
+        Command::Threads => {
+          let body = ResponseBody::Threads(ThreadsResponse {
+            threads: vec![Thread {
+              id: 1,
+              name: "main".to_string(),
+            }],
+          });
+          if bugs.duplicate_threads_response {
+            server.respond(req.clone().success(body.clone())).unwrap();
+          }
+          body
+        }
+        Command::StackTrace(_) => ResponseBody::StackTrace(StackTraceResponse {
+          stack_frames: vec![StackFrame {
+            id: 1000,
+            name: "main".to_string(),
+            line,
+            column: 1,
+            ..Default::default()
+          }],
+          total_frames: Some(1),
+        }),
+        Command::Next(_) => {
+          line += 1;
+          server.respond(req.success(ResponseBody::Next)).unwrap();
+          server
+            .send_event(stopped(StoppedEventReason::Step))
+            .unwrap();
+          continue;
+        }
+        Command::Scopes(_) => ResponseBody::Scopes(ScopesResponse {
+          scopes: vec![Scope {
+            name: "Locals".to_string(),
+            variables_reference: 1,
+            ..Default::default()
+          }],
+        }),
+        Command::Variables(args) => {
+          let all = ["a", "b", "c"].iter().map(|name| Variable {
+            name: name.to_string(),
+            value: "1".to_string(),
+            ..Default::default()
+          });
+          let start = args.start.unwrap_or_default() as usize;
+          let count = args.count.map_or(usize::MAX, |c| c as usize);
+          ResponseBody::Variables(VariablesResponse {
+            variables: all.skip(start).take(count).collect(),
+          })
+        }
+        Command::Terminate(_) => {
+          server
+            .respond(req.success(ResponseBody::Terminate))
+            .unwrap();
+          if !bugs.no_terminated_event {
+            server.send_event(Event::Terminated(None)).unwrap();
+          }
+          continue;
+        }
+        Command::Disconnect(_) => {
+          server
+            .respond(req.success(ResponseBody::Disconnect))
+            .unwrap();
+          return;
+        }
+        _ => {
+          server.respond(req.error("not supported")).unwrap();
+          continue;
+        }
+      };
+      server.respond(req.success(body)).unwrap();
+    }
+  }
+
+  fn run_fake(bugs: Bugs) -> Report {
+    let (client, adapter) = duplex();
+    thread::spawn(move || fake_adapter(adapter, bugs));
+    let config: Config = serde_json::from_value(json!({
+      "adapter": {"connect": "tcp://127.0.0.1:0"},
+      "launch": {"program": "fixture"},
+      "breakpoint": {"path": "fixture.c", "line": 5},
+      "timeoutMs": 2000,
+    }))
+    .unwrap();
+    run_with(client.reader, client.writer, &config)
+  }
+
+  #[test]
+  fn test_conforming_adapter() {
+    let report = run_fake(Bugs::default());
+    assert!(report.is_success(), "{}", report);
+    assert_eq!(report.results.len(), 7);
+    assert!(report.results.iter().all(|r| r.outcome == Outcome::Passed));
+  }
+
+  #[test]
+  fn test_violations() {
+    let report = run_fake(Bugs {
+      early_initialized: true,
+      duplicate_threads_response: true,
+      ..Default::default()
+    });
+    assert!(!report.is_success());
+
+    let violations: Vec<(&str, &str)> = report
+      .results
+      .iter()
+      .flat_map(|r| r.violations.iter().map(move |v| (r.name, v.as_str())))
+      .collect();
+    assert!(violations.contains(&(
+      "initialize handshake",
+      "'initialized' event sent before the 'initialize' response"
+    )));
+    assert!(violations
+      .iter()
+      .any(|(name, v)| *name == "stepping" && v.starts_with("duplicate response to request")));
+  }
+
+  #[test]
+  fn test_missing_terminated_event() {
+    let report = run_fake(Bugs {
+      no_terminated_event: true,
+      ..Default::default()
+    });
+    let terminate = report
+      .results
+      .iter()
+      .find(|r| r.name == "terminate")
+      .unwrap();
+    assert!(
+      matches!(&terminate.outcome, Outcome::Failed(reason) if reason.starts_with("no 'terminated' event"))
+    );
+  }
+}
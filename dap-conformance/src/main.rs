@@ -0,0 +1,40 @@
+//! Runs the conformance scenarios against the adapter described by a configuration file.
+//!
+//! ```text
+//! dap-conformance <config.json>
+//! ```
+use std::fs;
+use std::process;
+
+use dap_conformance::Config;
+
+const USAGE: &str = "usage: dap-conformance <config.json>";
+
+fn load(path: &str) -> Result<Config, String> {
+  let contents =
+    fs::read_to_string(path).map_err(|e| format!("could not read '{}': {}", path, e))?;
+  serde_json::from_str(&contents).map_err(|e| format!("invalid configuration '{}': {}", path, e))
+}
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let path = match args.as_slice() {
+    [path] if path != "--help" && path != "-h" => path,
+    _ => {
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    }
+  };
+
+  let report = match load(path).and_then(|config| dap_conformance::run(&config)) {
+    Ok(report) => report,
+    Err(e) => {
+      eprintln!("error: {}", e);
+      process::exit(2);
+    }
+  };
+  println!("{}", report);
+  if !report.is_success() {
+    process::exit(1);
+  }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::session::Session;
+
+/// How a scenario ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+  Passed,
+  Failed(String),
+  Skipped(String),
+}
+
+/// The outcome of a scenario and the protocol violations detected while it ran.
+#[derive(Debug, Clone)]
+pub struct ScenarioResult {
+  pub name: &'static str,
+  pub outcome: Outcome,
+  pub violations: Vec<String>,
+}
+
+/// The results of all scenarios, in the order they ran.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+  pub results: Vec<ScenarioResult>,
+}
+
+impl Report {
+  /// Whether no scenario failed and no violation was detected.
+  pub fn is_success(&self) -> bool {
+    self
+      .results
+      .iter()
+      .all(|r| !matches!(r.outcome, Outcome::Failed(_)) && r.violations.is_empty())
+  }
+}
+
+impl fmt::Display for Report {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for result in &self.results {
+      match &result.outcome {
+        Outcome::Passed if result.violations.is_empty() => writeln!(f, "PASS {}", result.name)?,
+        Outcome::Passed => writeln!(f, "FAIL {}", result.name)?,
+        Outcome::Failed(reason) => writeln!(f, "FAIL {}: {}", result.name, reason)?,
+        Outcome::Skipped(reason) => writeln!(f, "SKIP {}: {}", result.name, reason)?,
+      }
+      for violation in &result.violations {
+        writeln!(f, "     violation: {}", violation)?;
+      }
+    }
+    let failed = self
+      .results
+      .iter()
+      .filter(|r| matches!(r.outcome, Outcome::Failed(_)) || !r.violations.is_empty())
+      .count();
+    write!(f, "{} scenarios, {} failed", self.results.len(), failed)
+  }
+}
+
+/// What the scenarios learn about the debug session and pass on to the next ones.
+#[derive(Default)]
+struct State {
+  capabilities: Value,
+  thread_id: Option<i64>,
+  frame_id: Option<i64>,
+}
+
+type Scenario = fn(&mut Session, &Config, &mut State) -> Result<Outcome, String>;
+
+/// The standard battery. Each scenario builds on the state left behind by the previous one.
+const SCENARIOS: &[(&str, Scenario)] = &[
+  ("initialize handshake", initialize),
+  ("configurationDone ordering", configuration_done),
+  ("breakpoints", breakpoints),
+  ("stepping", stepping),
+  ("variables paging", variables_paging),
+  ("terminate", terminate),
+  ("disconnect", disconnect),
+];
+
+/// Run all scenarios in order. Once a scenario fails, the remaining ones are skipped.
+pub fn run_all(session: &mut Session, config: &Config) -> Report {
+  let mut state = State::default();
+  let mut report = Report::default();
+  let mut failed = false;
+  for (name, scenario) in SCENARIOS {
+    let outcome = if failed {
+      Outcome::Skipped("an earlier scenario failed".to_string())
+    } else {
+      scenario(session, config, &mut state).unwrap_or_else(Outcome::Failed)
+    };
+    session.drain();
+    failed |= matches!(outcome, Outcome::Failed(_));
+    report.results.push(ScenarioResult {
+      name,
+      outcome,
+      violations: session.take_violations(),
+    });
+  }
+  report
+}
+
+fn initialize(
+  session: &mut Session,
+  config: &Config,
+  state: &mut State,
+) -> Result<Outcome, String> {
+  let response = session.request(
+    "initialize",
+    json!({
+      "clientID": "dap-conformance",
+      "clientName": "dap-conformance",
+      "adapterID": config.adapter_id,
+      "linesStartAt1": true,
+      "columnsStartAt1": true,
+      "pathFormat": "path",
+      "supportsVariableType": true,
+      "supportsVariablePaging": true,
+    }),
+  )?;
+  state.capabilities = match &response["body"] {
+    Value::Null => json!({}),
+    body => body.clone(),
+  };
+  Ok(Outcome::Passed)
+}
+
+fn configuration_done(
+  session: &mut Session,
+  config: &Config,
+  state: &mut State,
+) -> Result<Outcome, String> {
+  // Clients typically launch right away and configure once the adapter is initialized; the
+  // adapter may only answer `launch` after it has been configured.
+  let launch = session.send("launch", config.launch.clone())?;
+  session.wait_event("initialized")?;
+
+  let response = session.request(
+    "setBreakpoints",
+    json!({
+      "source": {"path": config.breakpoint.path},
+      "breakpoints": [{"line": config.breakpoint.line}],
+    }),
+  )?;
+  let count = response["body"]["breakpoints"]
+    .as_array()
+    .map_or(0, Vec::len);
+  if count != 1 {
+    return Err(format!(
+      "expected 1 breakpoint in the response, got {}",
+      count
+    ));
+  }
+
+  if state.capabilities["supportsConfigurationDoneRequest"] == true {
+    session.drain();
+    if session.has_event("stopped") {
+      return Err("the debuggee stopped before 'configurationDone'".to_string());
+    }
+    session.request("configurationDone", Value::Null)?;
+  }
+
+  let response = session.wait_response(launch)?;
+  if response["success"] != true {
+    return Err(format!(
+      "'launch' failed: {}",
+      response["message"].as_str().unwrap_or("no message")
+    ));
+  }
+  Ok(Outcome::Passed)
+}
+
+fn breakpoints(session: &mut Session, _: &Config, state: &mut State) -> Result<Outcome, String> {
+  let stopped = session.wait_event("stopped")?;
+  let body = &stopped["body"];
+  if body["reason"] != "breakpoint" {
+    return Err(format!(
+      "expected a stop at the breakpoint, got {}",
+      body["reason"]
+    ));
+  }
+  state.thread_id = body["threadId"].as_i64();
+  Ok(Outcome::Passed)
+}
+
+fn stepping(session: &mut Session, config: &Config, state: &mut State) -> Result<Outcome, String> {
+  let response = session.request("threads", Value::Null)?;
+  let threads = response["body"]["threads"]
+    .as_array()
+    .cloned()
+    .unwrap_or_default();
+  let thread_id = match state.thread_id {
+    Some(id) if threads.iter().any(|t| t["id"] == id) => id,
+    Some(id) => return Err(format!("thread {} is not in the 'threads' response", id)),
+    None => threads
+      .first()
+      .and_then(|t| t["id"].as_i64())
+      .ok_or("the 'threads' response is empty")?,
+  };
+  state.thread_id = Some(thread_id);
+
+  let line = top_frame(session, thread_id, state)?;
+  if line != config.breakpoint.line {
+    return Err(format!(
+      "expected to be stopped on line {}, the top frame is on line {}",
+      config.breakpoint.line, line
+    ));
+  }
+
+  session.request("next", json!({ "threadId": thread_id }))?;
+  let stopped = session.wait_event("stopped")?;
+  if stopped["body"]["reason"] != "step" {
+    return Err(format!(
+      "expected a stop after the step, got {}",
+      stopped["body"]["reason"]
+    ));
+  }
+  top_frame(session, thread_id, state)?;
+  Ok(Outcome::Passed)
+}
+
+/// Fetch the top stack frame, remember it and return its line.
+fn top_frame(session: &mut Session, thread_id: i64, state: &mut State) -> Result<i64, String> {
+  let response = session.request("stackTrace", json!({ "threadId": thread_id }))?;
+  let frame = &response["body"]["stackFrames"][0];
+  state.frame_id = Some(frame["id"].as_i64().ok_or("the stack trace is empty")?);
+  Ok(frame["line"].as_i64().unwrap_or_default())
+}
+
+fn variables_paging(
+  session: &mut Session,
+  _: &Config,
+  state: &mut State,
+) -> Result<Outcome, String> {
+  let frame_id = state.frame_id.ok_or("no current frame")?;
+  let response = session.request("scopes", json!({ "frameId": frame_id }))?;
+  let reference = response["body"]["scopes"]
+    .as_array()
+    .and_then(|scopes| {
+      scopes
+        .iter()
+        .filter_map(|s| s["variablesReference"].as_i64())
+        .find(|r| *r > 0)
+    })
+    .ok_or("no scope has variables")?;
+
+  let all = variables(session, json!({ "variablesReference": reference }))?;
+  if all.is_empty() {
+    return Ok(Outcome::Skipped("the scope has no variables".to_string()));
+  }
+  let page = variables(
+    session,
+    json!({ "variablesReference": reference, "start": all.len() - 1, "count": 1 }),
+  )?;
+  if page.len() != 1 {
+    return Err(format!("expected a page of 1 variable, got {}", page.len()));
+  }
+  if page[0]["name"] != all[all.len() - 1]["name"] {
+    return Err(format!(
+      "expected the last variable {} in the page, got {}",
+      all[all.len() - 1]["name"],
+      page[0]["name"]
+    ));
+  }
+  Ok(Outcome::Passed)
+}
+
+fn variables(session: &mut Session, arguments: Value) -> Result<Vec<Value>, String> {
+  let response = session.request("variables", arguments)?;
+  Ok(
+    response["body"]["variables"]
+      .as_array()
+      .cloned()
+      .unwrap_or_default(),
+  )
+}
+
+fn terminate(session: &mut Session, _: &Config, state: &mut State) -> Result<Outcome, String> {
+  if state.capabilities["supportsTerminateRequest"] != true {
+    return Ok(Outcome::Skipped(
+      "the adapter does not support 'terminate'".to_string(),
+    ));
+  }
+  session.request("terminate", json!({}))?;
+  session.wait_event("terminated")?;
+  Ok(Outcome::Passed)
+}
+
+fn disconnect(session: &mut Session, _: &Config, _: &mut State) -> Result<Outcome, String> {
+  session.request("disconnect", json!({ "terminateDebuggee": true }))?;
+  Ok(Outcome::Passed)
+}
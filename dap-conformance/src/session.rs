@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufReader, Read, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dap::{
+  errors::ServerError,
+  framing::{read_message, write_message, DEFAULT_MAX_CONTENT_LENGTH},
+  schema::SchemaValidator,
+};
+use serde_json::{json, Value};
+
+/// Events that an adapter may only send if the client declared support for them in `initialize`.
+const GATED_EVENTS: &[(&str, &str)] = &[
+  ("progressStart", "supportsProgressReporting"),
+  ("progressUpdate", "supportsProgressReporting"),
+  ("progressEnd", "supportsProgressReporting"),
+  ("invalidated", "supportsInvalidatedEvent"),
+  ("memory", "supportsMemoryEvent"),
+];
+
+/// Reverse requests that an adapter may only send if the client declared support for them.
+const GATED_REVERSE_REQUESTS: &[(&str, &str)] = &[
+  ("runInTerminal", "supportsRunInTerminalRequest"),
+  ("startDebugging", "supportsStartDebuggingRequest"),
+];
+
+/// A connection to the adapter under test that checks every message it receives.
+///
+/// Responses and events are buffered so that scenarios can wait for them in any order, while
+/// protocol violations are collected as they are detected and handed out with
+/// [`Session::take_violations`].
+pub struct Session {
+  output: Box<dyn Write + Send>,
+  incoming: Receiver<Result<Value, ServerError>>,
+  timeout: Duration,
+  validator: SchemaValidator,
+  seq: i64,
+  /// The arguments of the `initialize` request, which declare the client's capabilities.
+  client_capabilities: Value,
+  /// Commands of the requests that have not been answered yet, by `seq`.
+  pending: HashMap<i64, String>,
+  answered: HashSet<i64>,
+  initialize_answered: bool,
+  last_adapter_seq: Option<i64>,
+  responses: VecDeque<Value>,
+  events: VecDeque<Value>,
+  closed: bool,
+  violations: Vec<String>,
+}
+
+impl Session {
+  pub fn new<R: Read + Send + 'static>(
+    input: R,
+    output: Box<dyn Write + Send>,
+    timeout: Duration,
+  ) -> Self {
+    let (sender, incoming) = channel();
+    thread::spawn(move || {
+      let mut input = BufReader::new(input);
+      loop {
+        let message = match read_message(&mut input, DEFAULT_MAX_CONTENT_LENGTH) {
+          Ok(Some(message)) => Ok(message),
+          Ok(None) => return,
+          Err(e) => Err(e),
+        };
+        let failed = message.is_err();
+        if sender.send(message).is_err() || failed {
+          return;
+        }
+      }
+    });
+
+    Self {
+      output,
+      incoming,
+      timeout,
+      validator: SchemaValidator::new(),
+      seq: 0,
+      client_capabilities: Value::Null,
+      pending: HashMap::new(),
+      answered: HashSet::new(),
+      initialize_answered: false,
+      last_adapter_seq: None,
+      responses: VecDeque::new(),
+      events: VecDeque::new(),
+      closed: false,
+      violations: Vec::new(),
+    }
+  }
+
+  /// Send a request without waiting for its response. Returns its `seq`.
+  pub fn send(&mut self, command: &str, arguments: Value) -> Result<i64, String> {
+    self.seq += 1;
+    let mut request = json!({"seq": self.seq, "type": "request", "command": command});
+    if command == "initialize" {
+      self.client_capabilities = arguments.clone();
+    }
+    if !arguments.is_null() {
+      request["arguments"] = arguments;
+    }
+    write_message(&mut self.output, &request)
+      .map_err(|e| format!("could not send '{}': {}", command, e))?;
+    self.pending.insert(self.seq, command.to_string());
+    Ok(self.seq)
+  }
+
+  /// Send a request and wait for its response, which must be successful.
+  pub fn request(&mut self, command: &str, arguments: Value) -> Result<Value, String> {
+    let seq = self.send(command, arguments)?;
+    let response = self.wait_response(seq)?;
+    if response["success"] != true {
+      return Err(format!(
+        "'{}' failed: {}",
+        command,
+        response["message"].as_str().unwrap_or("no message")
+      ));
+    }
+    Ok(response)
+  }
+
+  /// Wait for the response to the request with the given `seq`.
+  pub fn wait_response(&mut self, seq: i64) -> Result<Value, String> {
+    let deadline = Instant::now() + self.timeout;
+    loop {
+      if let Some(index) = self.responses.iter().position(|r| r["request_seq"] == seq) {
+        return Ok(self.responses.remove(index).unwrap_or_default());
+      }
+      if let Err(e) = self.receive(deadline) {
+        let command = self.pending.get(&seq).cloned().unwrap_or_default();
+        let error = format!("no response to '{}' request {}: {}", command, seq, e);
+        self.violations.push(error.clone());
+        return Err(error);
+      }
+    }
+  }
+
+  /// Wait for an event with the given name.
+  pub fn wait_event(&mut self, event: &str) -> Result<Value, String> {
+    let deadline = Instant::now() + self.timeout;
+    loop {
+      if let Some(index) = self.events.iter().position(|e| e["event"] == event) {
+        return Ok(self.events.remove(index).unwrap_or_default());
+      }
+      self
+        .receive(deadline)
+        .map_err(|e| format!("no '{}' event: {}", event, e))?;
+    }
+  }
+
+  /// Process the messages that have already arrived, without waiting for more.
+  pub fn drain(&mut self) {
+    while self.receive(Instant::now()).is_ok() {}
+  }
+
+  /// Whether an event with the given name has been received and not consumed yet.
+  pub fn has_event(&self, event: &str) -> bool {
+    self.events.iter().any(|e| e["event"] == event)
+  }
+
+  /// The violations detected since the last call.
+  pub fn take_violations(&mut self) -> Vec<String> {
+    std::mem::take(&mut self.violations)
+  }
+
+  fn receive(&mut self, deadline: Instant) -> Result<(), String> {
+    if self.closed {
+      return Err("the adapter closed the connection".to_string());
+    }
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    match self.incoming.recv_timeout(timeout) {
+      Ok(Ok(message)) => {
+        self.check(message);
+        Ok(())
+      }
+      Ok(Err(e)) => {
+        self.closed = true;
+        self
+          .violations
+          .push(format!("could not read a message: {}", e));
+        Err(e.to_string())
+      }
+      Err(RecvTimeoutError::Timeout) => Err(format!("timed out after {:?}", self.timeout)),
+      Err(RecvTimeoutError::Disconnected) => {
+        self.closed = true;
+        Err("the adapter closed the connection".to_string())
+      }
+    }
+  }
+
+  fn check(&mut self, message: Value) {
+    match self.validator.validate(&message) {
+      Ok(()) => {}
+      Err(ServerError::SchemaViolation { definition, errors }) => {
+        for error in errors {
+          self
+            .violations
+            .push(format!("schema: {} {}", definition, error));
+        }
+      }
+      Err(e) => self.violations.push(format!("schema: {}", e)),
+    }
+
+    if let Some(seq) = message["seq"].as_i64() {
+      if let Some(last) = self.last_adapter_seq {
+        if seq <= last {
+          self.violations.push(format!(
+            "seq {} is not greater than the previous seq {}",
+            seq, last
+          ));
+        }
+      }
+      self.last_adapter_seq = Some(seq);
+    }
+
+    match message["type"].as_str() {
+      Some("response") => self.check_response(message),
+      Some("event") => self.check_event(message),
+      Some("request") => self.check_reverse_request(message),
+      _ => self
+        .violations
+        .push(format!("message of unknown type: {}", message)),
+    }
+  }
+
+  fn check_response(&mut self, response: Value) {
+    let request_seq = response["request_seq"].as_i64().unwrap_or_default();
+    match self.pending.remove(&request_seq) {
+      Some(command) => {
+        if response["command"] != command.as_str() {
+          self.violations.push(format!(
+            "the response to '{}' request {} has command {}",
+            command, request_seq, response["command"]
+          ));
+        }
+        if command == "initialize" {
+          self.initialize_answered = true;
+        }
+        self.answered.insert(request_seq);
+        self.responses.push_back(response);
+      }
+      None if self.answered.contains(&request_seq) => self
+        .violations
+        .push(format!("duplicate response to request {}", request_seq)),
+      None => self
+        .violations
+        .push(format!("response to unknown request {}", request_seq)),
+    }
+  }
+
+  fn check_event(&mut self, event: Value) {
+    let name = event["event"].as_str().unwrap_or_default();
+    if name == "initialized" && !self.initialize_answered {
+      self
+        .violations
+        .push("'initialized' event sent before the 'initialize' response".to_string());
+    }
+    if let Some((_, capability)) = GATED_EVENTS.iter().find(|(e, _)| *e == name) {
+      if self.client_capabilities[capability] != true {
+        self.violations.push(format!(
+          "'{}' event sent although the client did not declare '{}'",
+          name, capability
+        ));
+      }
+    }
+    self.events.push_back(event);
+  }
+
+  fn check_reverse_request(&mut self, request: Value) {
+    let command = request["command"].as_str().unwrap_or_default();
+    if let Some((_, capability)) = GATED_REVERSE_REQUESTS.iter().find(|(c, _)| *c == command) {
+      if self.client_capabilities[capability] != true {
+        self.violations.push(format!(
+          "'{}' request sent although the client did not declare '{}'",
+          command, capability
+        ));
+      }
+    }
+
+    // The runner does not implement any reverse requests.
+    self.seq += 1;
+    let response = json!({
+      "seq": self.seq,
+      "type": "response",
+      "request_seq": request["seq"],
+      "success": false,
+      "command": command,
+      "message": "not supported by dap-conformance",
+    });
+    if let Err(e) = write_message(&mut self.output, &response) {
+      self
+        .violations
+        .push(format!("could not answer '{}': {}", command, e));
+    }
+  }
+}
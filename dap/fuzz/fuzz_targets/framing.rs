@@ -0,0 +1,16 @@
+//! Feeds arbitrary bytes to `Server::poll_request`, which covers the header parsing, reading the
+//! content, UTF-8 decoding and deserialization, and answering requests that fail to deserialize.
+#![no_main]
+
+use std::io::{sink, BufReader, BufWriter};
+
+use dap::server::Server;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let mut server = Server::new(BufReader::new(data), BufWriter::new(sink()));
+  // Keep the allocations of a single input small, so that the fuzzer finds the inputs that
+  // allocate more than they should rather than running out of memory on legitimate ones.
+  server.set_max_content_length(1024 * 1024);
+  while let Ok(Some(_)) = server.poll_request() {}
+});
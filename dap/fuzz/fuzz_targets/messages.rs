@@ -0,0 +1,26 @@
+//! Deserializes arbitrary bytes as each kind of message. Whatever parses has to serialize again,
+//! and a message has to parse again after that.
+#![no_main]
+
+use dap::{
+  base_message::BaseMessage,
+  requests::Request,
+  reverse_requests::{ReverseRequest, ReverseResponse},
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  if let Ok(request) = serde_json::from_slice::<Request>(data) {
+    serde_json::to_vec(&request).unwrap();
+  }
+  if let Ok(message) = serde_json::from_slice::<BaseMessage>(data) {
+    let json = serde_json::to_vec(&message).unwrap();
+    serde_json::from_slice::<BaseMessage>(&json).unwrap();
+  }
+  if let Ok(request) = serde_json::from_slice::<ReverseRequest>(data) {
+    serde_json::to_vec(&request).unwrap();
+  }
+  if let Ok(response) = serde_json::from_slice::<ReverseResponse>(data) {
+    serde_json::to_vec(&response).unwrap();
+  }
+});
@@ -0,0 +1,314 @@
+//! Handling of ANSI escape sequences in text sent to the client.
+//!
+//! Clients that declare `supportsANSIStyling` in their `initialize` arguments render escape
+//! sequences in `OutputEvent.output` and `Variable.value` (provided the adapter declares the
+//! capability too). Other clients show them verbatim, so an adapter that forwards colored output
+//! of a compiler or runtime should pass it through [`for_client`] first.
+use std::borrow::Cow;
+
+use crate::requests::InitializeArguments;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+/// The single character form of `ESC [`.
+const CSI: char = '\u{9b}';
+
+/// What to do with styled text when the client does not render ANSI escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fallback {
+  /// Remove all escape sequences.
+  Strip,
+  /// Remove all escape sequences, but replace bold, italic and underlined text with Markdown-like
+  /// markers (see [`to_markers`]).
+  Markers,
+}
+
+/// Prepares text for a client: it is returned unchanged if the client declared
+/// `supportsANSIStyling`, otherwise the escape sequences are removed as `fallback` says.
+pub fn for_client<'a>(
+  text: &'a str,
+  client: &InitializeArguments,
+  fallback: Fallback,
+) -> Cow<'a, str> {
+  if client.supports_ansi_styling == Some(true) || !has_escapes(text) {
+    return Cow::Borrowed(text);
+  }
+  Cow::Owned(match fallback {
+    Fallback::Strip => strip(text),
+    Fallback::Markers => to_markers(text),
+  })
+}
+
+/// Returns true if the text contains anything that looks like an escape sequence.
+pub fn has_escapes(text: &str) -> bool {
+  text.contains(ESC) || text.contains(CSI)
+}
+
+/// Removes all escape sequences from the text.
+pub fn strip(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for token in Tokens::new(text) {
+    if let Token::Text(text) = token {
+      out.push_str(text);
+    }
+  }
+  out
+}
+
+/// Removes all escape sequences from the text and marks up bold text as `**bold**`, italic text
+/// as `_italic_` and underlined text as `__underlined__`. Colors and all other styling are
+/// dropped. Markers that are still open at the end of the text are closed.
+pub fn to_markers(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut open: Vec<Style> = Vec::new();
+  for token in Tokens::new(text) {
+    match token {
+      Token::Text(text) => out.push_str(text),
+      Token::Sgr(params) => {
+        for change in sgr_changes(params) {
+          match change {
+            Change::Reset => close_all(&mut out, &mut open),
+            Change::On(style) if !open.contains(&style) => {
+              out.push_str(style.marker());
+              open.push(style);
+            }
+            Change::Off(style) => close(&mut out, &mut open, style),
+            Change::On(_) => {}
+          }
+        }
+      }
+      Token::Other => {}
+    }
+  }
+  close_all(&mut out, &mut open);
+  out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+  Bold,
+  Italic,
+  Underline,
+}
+
+impl Style {
+  fn marker(self) -> &'static str {
+    match self {
+      Style::Bold => "**",
+      Style::Italic => "_",
+      Style::Underline => "__",
+    }
+  }
+}
+
+enum Change {
+  Reset,
+  On(Style),
+  Off(Style),
+}
+
+/// Translates the parameters of a "select graphic rendition" sequence, e.g. `1;31`. Parameters
+/// that don't affect bold, italic or underline are ignored; the arguments of extended colors
+/// (`38;5;n`, `48;2;r;g;b`) are skipped so they are not mistaken for styles.
+fn sgr_changes(params: &str) -> Vec<Change> {
+  let mut changes = Vec::new();
+  let mut codes = params.split(|c| c == ';' || c == ':');
+  while let Some(code) = codes.next() {
+    let change = match code {
+      "" | "0" => Change::Reset,
+      "1" => Change::On(Style::Bold),
+      "3" => Change::On(Style::Italic),
+      "4" => Change::On(Style::Underline),
+      "22" => Change::Off(Style::Bold),
+      "23" => Change::Off(Style::Italic),
+      "24" => Change::Off(Style::Underline),
+      "38" | "48" | "58" => {
+        let skip = match codes.next() {
+          Some("5") => 1,
+          Some("2") => 3,
+          _ => 0,
+        };
+        for _ in 0..skip {
+          codes.next();
+        }
+        continue;
+      }
+      _ => continue,
+    };
+    changes.push(change);
+  }
+  changes
+}
+
+/// Closes the markers opened after `style` too, so the markers stay properly nested.
+fn close(out: &mut String, open: &mut Vec<Style>, style: Style) {
+  if let Some(index) = open.iter().position(|s| *s == style) {
+    let reopen = open.split_off(index + 1);
+    open.pop();
+    for s in reopen.iter().rev() {
+      out.push_str(s.marker());
+    }
+    out.push_str(style.marker());
+    for s in &reopen {
+      out.push_str(s.marker());
+    }
+    open.extend(reopen);
+  }
+}
+
+fn close_all(out: &mut String, open: &mut Vec<Style>) {
+  while let Some(style) = open.pop() {
+    out.push_str(style.marker());
+  }
+}
+
+enum Token<'a> {
+  Text(&'a str),
+  /// The parameters of a `CSI ... m` sequence.
+  Sgr(&'a str),
+  /// Any other escape sequence.
+  Other,
+}
+
+/// Splits text into plain text and escape sequences. Incomplete sequences at the end of the text
+/// are dropped.
+struct Tokens<'a> {
+  rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+  fn new(text: &'a str) -> Self {
+    Self { rest: text }
+  }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+  type Item = Token<'a>;
+
+  fn next(&mut self) -> Option<Token<'a>> {
+    if self.rest.is_empty() {
+      return None;
+    }
+    let start = self.rest.find(|c| c == ESC || c == CSI);
+    match start {
+      Some(0) => {}
+      Some(index) => {
+        let (text, rest) = self.rest.split_at(index);
+        self.rest = rest;
+        return Some(Token::Text(text));
+      }
+      None => {
+        let text = self.rest;
+        self.rest = "";
+        return Some(Token::Text(text));
+      }
+    }
+
+    let mut chars = self.rest.char_indices();
+    let (_, first) = chars.next().unwrap();
+    let body = if first == CSI {
+      Some(Introducer::Csi(first.len_utf8()))
+    } else {
+      match chars.next() {
+        Some((i, '[')) => Some(Introducer::Csi(i + 1)),
+        Some((i, ']')) => Some(Introducer::Osc(i + 1)),
+        // Two character sequences such as `ESC 7` or `ESC =`.
+        Some((i, c)) => {
+          self.rest = &self.rest[i + c.len_utf8()..];
+          return Some(Token::Other);
+        }
+        None => None,
+      }
+    };
+
+    let token = match body {
+      Some(Introducer::Csi(offset)) => {
+        // Parameter and intermediate bytes followed by a final byte in `@`..=`~`.
+        let sequence = &self.rest[offset..];
+        match sequence.find(|c| ('@'..='~').contains(&c)) {
+          Some(end) => {
+            let token = if sequence[end..].starts_with('m') {
+              Token::Sgr(&sequence[..end])
+            } else {
+              Token::Other
+            };
+            self.rest = &sequence[end + 1..];
+            token
+          }
+          None => {
+            self.rest = "";
+            Token::Other
+          }
+        }
+      }
+      Some(Introducer::Osc(offset)) => {
+        // Operating system commands (e.g. hyperlinks) end with BEL or `ESC \`.
+        let sequence = &self.rest[offset..];
+        self.rest = match (sequence.find(BEL), sequence.find("\u{1b}\\")) {
+          (Some(bel), Some(st)) if st < bel => &sequence[st + 2..],
+          (Some(bel), _) => &sequence[bel + 1..],
+          (None, Some(st)) => &sequence[st + 2..],
+          (None, None) => "",
+        };
+        Token::Other
+      }
+      None => {
+        self.rest = "";
+        Token::Other
+      }
+    };
+    Some(token)
+  }
+}
+
+enum Introducer {
+  Csi(usize),
+  Osc(usize),
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_strip() {
+    assert_eq!(strip("plain"), "plain");
+    assert_eq!(strip("\u{1b}[1;31merror\u{1b}[0m: oops"), "error: oops");
+    assert_eq!(strip("a\u{1b}[2Kb\u{9b}32mc"), "abc");
+    assert_eq!(
+      strip("\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{1b}\\ done"),
+      "link done"
+    );
+    assert_eq!(strip("\u{1b}7saved\u{1b}8"), "saved");
+    assert_eq!(strip("unterminated \u{1b}[1"), "unterminated ");
+    assert_eq!(strip("ünï\u{1b}[4mcødé\u{1b}[m"), "ünïcødé");
+  }
+
+  #[test]
+  fn test_to_markers() {
+    assert_eq!(to_markers("\u{1b}[1mbold\u{1b}[0m text"), "**bold** text");
+    assert_eq!(
+      to_markers("\u{1b}[3;38;2;1;3;4mitalic\u{1b}[23m, \u{1b}[4munderlined"),
+      "_italic_, __underlined__"
+    );
+    assert_eq!(
+      to_markers("\u{1b}[1mbold \u{1b}[3mboth\u{1b}[22m italic\u{1b}[m"),
+      "**bold _both_**_ italic_"
+    );
+    assert_eq!(to_markers("\u{1b}[31mred\u{1b}[39m"), "red");
+  }
+
+  #[test]
+  fn test_for_client() {
+    let colored = "\u{1b}[1;31merror\u{1b}[0m";
+    let mut client = InitializeArguments::default();
+    assert_eq!(for_client(colored, &client, Fallback::Strip), "error");
+    assert_eq!(for_client(colored, &client, Fallback::Markers), "**error**");
+    assert!(matches!(
+      for_client("plain", &client, Fallback::Strip),
+      Cow::Borrowed(_)
+    ));
+    client.supports_ansi_styling = Some(true);
+    assert_eq!(for_client(colored, &client, Fallback::Strip), colored);
+  }
+}
@@ -1,16 +1,15 @@
-use serde::Serialize;
-
-#[cfg(feature = "client")]
-use serde::Deserialize;
+#[cfg(feature = "integration_testing")]
+use fake::Dummy;
+use serde::{Deserialize, Serialize};
 
 use crate::{events::Event, responses::Response, reverse_requests::ReverseRequest};
 
 /// Represents the base protocol message, in which all other messages are wrapped.
 ///
 /// Specification: [Response](https://microsoft.github.io/debug-adapter-protocol/specification)
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct BaseMessage {
   /// Sequence number of the message. The `seq` for
   /// the first message is 1, and for each message is incremented by 1.
@@ -19,19 +18,26 @@ pub struct BaseMessage {
   pub message: Sendable,
 }
 
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "type")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum Sendable {
   Response(Response),
   Event(Event),
+  /// Reverse requests are requests like any other on the wire.
+  #[serde(rename = "request")]
   ReverseRequest(ReverseRequest),
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::{
+    requests::{Command, Request},
+    reverse_requests::{ReverseCommand, StartDebuggingRequestArguments},
+    types::StartDebuggingRequestKind,
+  };
 
   #[test]
   fn test_message_serialize() {
@@ -44,4 +50,184 @@ mod tests {
     let expected = "{\"seq\":10,\"type\":\"event\",\"event\":\"initialized\"}";
     assert_eq!(json, expected);
   }
+
+  #[test]
+  fn test_terminated_without_body() {
+    let message = BaseMessage {
+      seq: 4,
+      message: Sendable::Event(Event::Terminated(None)),
+    };
+    let json = serde_json::to_string(&message).unwrap();
+    // Used to be sent with `"body":null`, which doesn't match the schema.
+    assert_eq!(json, r#"{"seq":4,"type":"event","event":"terminated"}"#);
+    assert_eq!(serde_json::from_str::<BaseMessage>(&json).unwrap(), message);
+
+    // An empty body is not the same as no body.
+    let message = BaseMessage {
+      seq: 4,
+      message: Sendable::Event(Event::Terminated(Some(Default::default()))),
+    };
+    let json = serde_json::to_string(&message).unwrap();
+    assert_eq!(
+      json,
+      r#"{"seq":4,"type":"event","event":"terminated","body":{}}"#
+    );
+    assert_eq!(serde_json::from_str::<BaseMessage>(&json).unwrap(), message);
+  }
+
+  #[test]
+  fn test_request_serialize() {
+    let request = Request {
+      seq: 2,
+      command: Command::Threads,
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(json, r#"{"type":"request","seq":2,"command":"threads"}"#);
+    assert_eq!(serde_json::from_str::<Request>(&json).unwrap(), request);
+  }
+
+  #[test]
+  fn test_reverse_request_serialize() {
+    let message = BaseMessage {
+      seq: 3,
+      message: Sendable::ReverseRequest(ReverseRequest {
+        command: ReverseCommand::StartDebugging(StartDebuggingRequestArguments {
+          configuration: Default::default(),
+          request: StartDebuggingRequestKind::Attach,
+        }),
+      }),
+    };
+    let json: serde_json::Value = serde_json::to_value(&message).unwrap();
+
+    assert_eq!(json["type"], "request");
+    assert_eq!(json["seq"], 3);
+    assert_eq!(json["command"], "startDebugging");
+    assert_eq!(
+      serde_json::from_value::<BaseMessage>(json).unwrap(),
+      message
+    );
+  }
+
+  #[test]
+  fn test_reverse_request_deserialize() {
+    let json = include_str!("../fuzz/seeds/messages/15-request-runInTerminal.json");
+    let message: BaseMessage = serde_json::from_str(json).unwrap();
+    assert_eq!(message.seq, 6);
+    match &message.message {
+      Sendable::ReverseRequest(ReverseRequest {
+        command: ReverseCommand::RunInTerminal(args),
+      }) => {
+        assert_eq!(args.args, vec!["ls".to_string()]);
+        assert_eq!(args.env.as_ref().unwrap()["B"], None);
+      }
+      other => panic!("unexpected message {:?}", other),
+    }
+    let json = serde_json::to_string(&message).unwrap();
+    assert_eq!(json.matches("\"seq\"").count(), 1);
+  }
+
+  /// Property tests: every message generated by `fake` survives serialization unchanged.
+  #[cfg(feature = "integration_testing")]
+  mod round_trip {
+    use std::fmt::Debug;
+
+    use fake::{Fake, Faker};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+    use crate::{responses::ResponseBody, reverse_requests::ReverseResponse};
+
+    const SAMPLES: usize = 500;
+
+    fn assert_round_trips<T>(value: &T)
+    where
+      T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+      let json = serde_json::to_value(value).unwrap();
+      let parsed: T = serde_json::from_value(json.clone())
+        .unwrap_or_else(|e| panic!("{} failed to parse: {}", json, e));
+      assert_eq!(&parsed, value, "{}", json);
+    }
+
+    fn check<T>(seed: u64)
+    where
+      T: Serialize + DeserializeOwned + PartialEq + Debug + fake::Dummy<Faker>,
+    {
+      let rng = &mut StdRng::seed_from_u64(seed);
+      for _ in 0..SAMPLES {
+        let value: T = Faker.fake_with_rng(rng);
+        assert_round_trips(&value);
+      }
+    }
+
+    #[test]
+    fn test_requests_round_trip() {
+      check::<Request>(1);
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+      check::<Event>(2);
+      let rng = &mut StdRng::seed_from_u64(3);
+      for _ in 0..SAMPLES {
+        assert_round_trips(&BaseMessage {
+          seq: rng.gen(),
+          message: Sendable::Event(Faker.fake_with_rng(rng)),
+        });
+      }
+    }
+
+    #[test]
+    fn test_reverse_requests_round_trip() {
+      check::<ReverseRequest>(4);
+      check::<ReverseResponse>(5);
+      let rng = &mut StdRng::seed_from_u64(7);
+      for _ in 0..SAMPLES {
+        assert_round_trips(&BaseMessage {
+          seq: rng.gen(),
+          message: Sendable::ReverseRequest(Faker.fake_with_rng(rng)),
+        });
+      }
+    }
+
+    /// A response has either a body or the command it answers (plus the error details, if it
+    /// failed), so it is built from generated parts instead of being generated as a whole.
+    #[test]
+    fn test_responses_round_trip() {
+      let rng = &mut StdRng::seed_from_u64(6);
+      for _ in 0..SAMPLES {
+        let success = rng.gen();
+        let message = Faker.fake_with_rng(rng);
+        let response = if rng.gen() {
+          Response {
+            request_seq: rng.gen(),
+            success,
+            message,
+            body: Some(Faker.fake_with_rng::<ResponseBody, _>(rng)),
+            error: None,
+            command: None,
+          }
+        } else {
+          Response {
+            request_seq: rng.gen(),
+            success,
+            message,
+            body: None,
+            error: if success {
+              None
+            } else {
+              Faker.fake_with_rng(rng)
+            },
+            command: Some(Faker.fake_with_rng(rng)),
+          }
+        };
+        assert_round_trips(&response);
+        assert_round_trips(&BaseMessage {
+          seq: rng.gen(),
+          message: Sendable::Response(response),
+        });
+      }
+    }
+  }
 }
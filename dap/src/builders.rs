@@ -0,0 +1,308 @@
+//! Fluent builders for the protocol structs that are tedious to write as struct literals.
+//!
+//! The fields the specification requires are the arguments of the `builder` function, so they
+//! can't be forgotten, and every optional field has a setter. Fields that are added to the
+//! specification later don't break code that uses a builder.
+//!
+//! ```rust
+//! use dap::types::{Capabilities, Source, StackFrame};
+//!
+//! let frame = StackFrame::builder(1, "main", 10, 1)
+//!   .source(Source {
+//!     path: Some("/src/main.rs".to_string()),
+//!     ..Default::default()
+//!   })
+//!   .can_restart(true)
+//!   .build();
+//! assert_eq!(frame.can_restart, Some(true));
+//!
+//! let capabilities = Capabilities::builder()
+//!   .supports_configuration_done_request(true)
+//!   .supports_function_breakpoints(true)
+//!   .build();
+//! assert_eq!(capabilities.supports_step_back, None);
+//! ```
+use serde_json::Value;
+
+use crate::{
+  events::{OutputEventBody, StoppedEventBody},
+  types::{
+    Breakpoint, BreakpointMode, Capabilities, ChecksumAlgorithm, ColumnDescriptor,
+    ExceptionBreakpointsFilter, OutputEventCategory, OutputEventGroup, Source, StackFrame,
+    StackFrameModuleid, StackFramePresentationhint, StoppedEventReason, Variable,
+    VariablePresentationHint,
+  },
+};
+
+/// Defines a builder for `$target` with a setter for each of the listed optional fields.
+macro_rules! builder {
+  (
+    $(#[$doc:meta])*
+    $builder:ident => $target:ident {
+      $($field:ident: $ty:ty,)*
+    }
+  ) => {
+    $(#[$doc])*
+    #[derive(Debug, Clone)]
+    #[must_use]
+    pub struct $builder($target);
+
+    impl $builder {
+      $(
+        #[doc = concat!("Sets `", stringify!($field), "`.")]
+        pub fn $field(mut self, $field: impl Into<$ty>) -> Self {
+          self.0.$field = Some($field.into());
+          self
+        }
+      )*
+
+      pub fn build(self) -> $target {
+        self.0
+      }
+    }
+
+    impl From<$builder> for $target {
+      fn from(builder: $builder) -> Self {
+        builder.build()
+      }
+    }
+  };
+}
+
+builder! {
+  /// Builds [`Capabilities`], which has no required fields. Start with [`Capabilities::builder`].
+  CapabilitiesBuilder => Capabilities {
+    supports_configuration_done_request: bool,
+    supports_function_breakpoints: bool,
+    supports_conditional_breakpoints: bool,
+    supports_hit_conditional_breakpoints: bool,
+    supports_evaluate_for_hovers: bool,
+    exception_breakpoint_filters: Vec<ExceptionBreakpointsFilter>,
+    supports_step_back: bool,
+    supports_set_variable: bool,
+    supports_restart_frame: bool,
+    supports_goto_targets_request: bool,
+    supports_step_in_targets_request: bool,
+    supports_completions_request: bool,
+    completion_trigger_characters: Vec<String>,
+    supports_modules_request: bool,
+    additional_module_columns: Vec<ColumnDescriptor>,
+    supported_checksum_algorithms: Vec<ChecksumAlgorithm>,
+    supports_restart_request: bool,
+    supports_exception_options: bool,
+    supports_value_formatting_options: bool,
+    supports_exception_info_request: bool,
+    support_terminate_debuggee: bool,
+    support_suspend_debuggee: bool,
+    supports_delayed_stack_trace_loading: bool,
+    supports_loaded_sources_request: bool,
+    supports_log_points: bool,
+    supports_terminate_threads_request: bool,
+    supports_set_expression: bool,
+    supports_terminate_request: bool,
+    supports_data_breakpoints: bool,
+    supports_read_memory_request: bool,
+    supports_write_memory_request: bool,
+    supports_disassemble_request: bool,
+    supports_cancel_request: bool,
+    supports_breakpoint_locations_request: bool,
+    supports_clipboard_context: bool,
+    supports_stepping_granularity: bool,
+    supports_instruction_breakpoints: bool,
+    supports_exception_filter_options: bool,
+    supports_single_thread_execution_requests: bool,
+    supports_data_breakpoint_bytes: bool,
+    breakpoint_modes: Vec<BreakpointMode>,
+    supports_ansi_styling: bool,
+  }
+}
+
+impl Capabilities {
+  pub fn builder() -> CapabilitiesBuilder {
+    CapabilitiesBuilder(Capabilities::default())
+  }
+}
+
+builder! {
+  /// Builds a [`StackFrame`]. Start with [`StackFrame::builder`].
+  StackFrameBuilder => StackFrame {
+    source: Source,
+    end_line: i64,
+    end_column: i64,
+    can_restart: bool,
+    instruction_pointer_reference: String,
+    module_id: StackFrameModuleid,
+    presentation_hint: StackFramePresentationhint,
+  }
+}
+
+impl StackFrame {
+  pub fn builder(id: i64, name: impl Into<String>, line: i64, column: i64) -> StackFrameBuilder {
+    StackFrameBuilder(StackFrame {
+      id,
+      name: name.into(),
+      line,
+      column,
+      ..Default::default()
+    })
+  }
+}
+
+builder! {
+  /// Builds a [`Variable`]. Start with [`Variable::builder`].
+  VariableBuilder => Variable {
+    type_field: String,
+    presentation_hint: VariablePresentationHint,
+    evaluate_name: String,
+    named_variables: i64,
+    indexed_variables: i64,
+    memory_reference: String,
+    declaration_location_reference: i64,
+    value_location_reference: i64,
+  }
+}
+
+impl Variable {
+  /// `variables_reference` is the reference of the variable's children, 0 if it has none.
+  pub fn builder(
+    name: impl Into<String>,
+    value: impl Into<String>,
+    variables_reference: i64,
+  ) -> VariableBuilder {
+    VariableBuilder(Variable {
+      name: name.into(),
+      value: value.into(),
+      variables_reference,
+      ..Default::default()
+    })
+  }
+}
+
+builder! {
+  /// Builds a [`Breakpoint`]. Start with [`Breakpoint::builder`].
+  BreakpointBuilder => Breakpoint {
+    id: i64,
+    message: String,
+    source: Source,
+    line: i64,
+    column: i64,
+    end_line: i64,
+    end_column: i64,
+    instruction_reference: String,
+    offset: i64,
+  }
+}
+
+impl Breakpoint {
+  pub fn builder(verified: bool) -> BreakpointBuilder {
+    BreakpointBuilder(Breakpoint {
+      verified,
+      ..Default::default()
+    })
+  }
+}
+
+builder! {
+  /// Builds an [`OutputEventBody`]. Start with [`OutputEventBody::builder`].
+  OutputEventBodyBuilder => OutputEventBody {
+    category: OutputEventCategory,
+    group: OutputEventGroup,
+    variables_reference: i64,
+    source: Source,
+    line: i64,
+    column: i64,
+    data: Value,
+  }
+}
+
+impl OutputEventBody {
+  pub fn builder(output: impl Into<String>) -> OutputEventBodyBuilder {
+    OutputEventBodyBuilder(OutputEventBody {
+      output: output.into(),
+      ..Default::default()
+    })
+  }
+}
+
+builder! {
+  /// Builds a [`StoppedEventBody`]. Start with [`StoppedEventBody::builder`].
+  StoppedEventBodyBuilder => StoppedEventBody {
+    description: String,
+    thread_id: i64,
+    preserve_focus_hint: bool,
+    text: String,
+    all_threads_stopped: bool,
+    hit_breakpoint_ids: Vec<i64>,
+  }
+}
+
+impl StoppedEventBody {
+  pub fn builder(reason: StoppedEventReason) -> StoppedEventBodyBuilder {
+    StoppedEventBodyBuilder(StoppedEventBody {
+      reason,
+      description: None,
+      thread_id: None,
+      preserve_focus_hint: None,
+      text: None,
+      all_threads_stopped: None,
+      hit_breakpoint_ids: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_required_fields() {
+    let variable = Variable::builder("x", "42", 0)
+      .type_field("i32")
+      .evaluate_name("x")
+      .build();
+    assert_eq!(
+      variable,
+      Variable {
+        name: "x".to_string(),
+        value: "42".to_string(),
+        type_field: Some("i32".to_string()),
+        evaluate_name: Some("x".to_string()),
+        ..Default::default()
+      }
+    );
+
+    let breakpoint = Breakpoint::builder(false)
+      .message("No code at this line")
+      .line(12)
+      .build();
+    assert!(!breakpoint.verified);
+    assert_eq!(breakpoint.line, Some(12));
+    assert_eq!(breakpoint.id, None);
+  }
+
+  #[test]
+  fn test_event_bodies() {
+    let stopped = StoppedEventBody::builder(StoppedEventReason::Breakpoint)
+      .thread_id(1)
+      .all_threads_stopped(true)
+      .hit_breakpoint_ids(vec![3])
+      .build();
+    let json = serde_json::to_value(&stopped).unwrap();
+    assert_eq!(
+      json,
+      serde_json::json!({
+        "reason": "breakpoint",
+        "threadId": 1,
+        "allThreadsStopped": true,
+        "hitBreakpointIds": [3],
+      })
+    );
+
+    let output: OutputEventBody = OutputEventBody::builder("hello\n")
+      .category(OutputEventCategory::Stdout)
+      .into();
+    assert_eq!(output.output, "hello\n");
+    assert_eq!(output.category, Some(OutputEventCategory::Stdout));
+    assert_eq!(output.data, None);
+  }
+}
@@ -0,0 +1,343 @@
+//! Bookkeeping for data breakpoints (watchpoints) on memory ranges.
+//!
+//! The `dataBreakpointInfo` request returns an opaque `dataId` that the client later passes back
+//! in `setDataBreakpoints`. [`DataId`] encodes the watched memory range and the access types the
+//! adapter offers for it into that string. [`DataBreakpointRegistry`] remembers the ids it issued,
+//! so that ids the adapter never handed out are rejected, checks the requested access types
+//! against the advertised ones and keeps track of the data breakpoints currently set.
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::{
+  errors::DataBreakpointError,
+  requests::SetDataBreakpointsArguments,
+  responses::{DataBreakpointInfoResponse, SetDataBreakpointsResponse},
+  types::{Breakpoint, DataBreakpointAccessType},
+};
+
+/// The data behind a `dataId`: a memory range and the access types that can be watched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataId {
+  /// Start address of the watched range.
+  pub address: u64,
+  /// Length of the watched range in bytes.
+  pub bytes: u64,
+  /// The access types advertised for the range in the `dataBreakpointInfo` response.
+  pub access_types: Vec<DataBreakpointAccessType>,
+}
+
+impl DataId {
+  /// Encodes the id as the string sent to the client, e.g. `0x7ffd1000/8/read,write`.
+  pub fn encode(&self) -> String {
+    self.to_string()
+  }
+
+  /// Decodes an id produced by [`encode`](Self::encode). Fails for empty ranges and access types
+  /// that are not part of the protocol.
+  pub fn decode(data_id: &str) -> Result<Self, DataBreakpointError> {
+    let invalid = || DataBreakpointError::InvalidDataId {
+      data_id: data_id.to_string(),
+    };
+    let mut parts = data_id.splitn(3, '/');
+    let (address, bytes, access_types) = match (parts.next(), parts.next(), parts.next()) {
+      (Some(address), Some(bytes), Some(access_types)) => (address, bytes, access_types),
+      _ => return Err(invalid()),
+    };
+    let address = address
+      .strip_prefix("0x")
+      .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+      .ok_or_else(invalid)?;
+    let bytes = match bytes.parse() {
+      Ok(0) | Err(_) => return Err(invalid()),
+      Ok(bytes) => bytes,
+    };
+    let access_types = access_types
+      .split(',')
+      .filter(|name| !name.is_empty())
+      .map(|name| match name {
+        "read" => Ok(DataBreakpointAccessType::Read),
+        "write" => Ok(DataBreakpointAccessType::Write),
+        "readWrite" => Ok(DataBreakpointAccessType::ReadWrite),
+        _ => Err(invalid()),
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(Self {
+      address,
+      bytes,
+      access_types,
+    })
+  }
+}
+
+impl Display for DataId {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let access_types: Vec<&str> = self.access_types.iter().map(access_type_name).collect();
+    write!(
+      f,
+      "{:#x}/{}/{}",
+      self.address,
+      self.bytes,
+      access_types.join(",")
+    )
+  }
+}
+
+fn access_type_name(access_type: &DataBreakpointAccessType) -> &str {
+  match access_type {
+    DataBreakpointAccessType::Read => "read",
+    DataBreakpointAccessType::Write => "write",
+    DataBreakpointAccessType::ReadWrite => "readWrite",
+    DataBreakpointAccessType::String(other) => other,
+  }
+}
+
+/// Parses the `name` of a `dataBreakpointInfo` request that has `asAddress` set: a decimal
+/// address, or a hexadecimal one if it is prefixed with `0x`.
+pub fn parse_address(name: &str) -> Result<u64, DataBreakpointError> {
+  let name = name.trim();
+  let parsed = match name.strip_prefix("0x").or_else(|| name.strip_prefix("0X")) {
+    Some(hex) => u64::from_str_radix(hex, 16),
+    None => name.parse(),
+  };
+  parsed.map_err(|_| DataBreakpointError::InvalidAddress {
+    address: name.to_string(),
+  })
+}
+
+/// A data breakpoint that passed validation in `setDataBreakpoints`.
+#[derive(Debug, Clone)]
+pub struct ActiveDataBreakpoint {
+  /// The id reported to the client in the `Breakpoint` of the response.
+  pub id: i64,
+  /// The watched memory range.
+  pub data: DataId,
+  /// The requested access type. If the client didn't request one, this is the first of the
+  /// advertised access types.
+  pub access_type: DataBreakpointAccessType,
+  /// The condition of the breakpoint, if any.
+  pub condition: Option<String>,
+  /// The hit condition of the breakpoint, if any.
+  pub hit_condition: Option<String>,
+}
+
+/// Keeps track of the data breakpoints set by the client, and of the ids offered to it.
+#[derive(Debug)]
+pub struct DataBreakpointRegistry {
+  next_id: i64,
+  /// The ids handed out in `dataBreakpointInfo` responses.
+  issued: HashMap<String, DataId>,
+  active: Vec<ActiveDataBreakpoint>,
+}
+
+impl Default for DataBreakpointRegistry {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl DataBreakpointRegistry {
+  pub fn new() -> Self {
+    Self {
+      next_id: 1,
+      issued: HashMap::new(),
+      active: Vec::new(),
+    }
+  }
+
+  /// A `dataBreakpointInfo` response offering a data breakpoint on a range. The id is remembered,
+  /// so that the client can set the data breakpoint with it later.
+  pub fn info_response(
+    &mut self,
+    data: DataId,
+    description: &str,
+  ) -> Result<DataBreakpointInfoResponse, DataBreakpointError> {
+    let data_id = data.encode();
+    // Rejects the ranges and access types the client couldn't set.
+    DataId::decode(&data_id)?;
+    let response = DataBreakpointInfoResponse {
+      data_id: Some(data_id.clone()),
+      description: description.to_string(),
+      access_types: Some(data.access_types.clone()),
+      ..Default::default()
+    };
+    self.issued.insert(data_id, data);
+    Ok(response)
+  }
+
+  /// Validates a data breakpoint requested by the client. The id must have been issued by
+  /// [`info_response`](Self::info_response).
+  pub fn validate(
+    &self,
+    data_id: &str,
+    access_type: Option<&DataBreakpointAccessType>,
+  ) -> Result<(DataId, DataBreakpointAccessType), DataBreakpointError> {
+    let data =
+      self
+        .issued
+        .get(data_id)
+        .cloned()
+        .ok_or_else(|| DataBreakpointError::UnknownDataId {
+          data_id: data_id.to_string(),
+        })?;
+    let access_type = match access_type {
+      Some(access_type) if data.access_types.contains(access_type) => access_type.clone(),
+      Some(access_type) => {
+        return Err(DataBreakpointError::UnsupportedAccessType {
+          data_id: data_id.to_string(),
+          access_type: access_type_name(access_type).to_string(),
+        })
+      }
+      None => match data.access_types.first() {
+        Some(access_type) => access_type.clone(),
+        None => {
+          return Err(DataBreakpointError::UnsupportedAccessType {
+            data_id: data_id.to_string(),
+            access_type: "any".to_string(),
+          })
+        }
+      },
+    };
+    Ok((data, access_type))
+  }
+
+  /// Replaces the active data breakpoints with the ones in a `setDataBreakpoints` request and
+  /// returns the response body. Breakpoints that fail validation are reported as unverified,
+  /// with the reason as their message.
+  pub fn set(&mut self, args: &SetDataBreakpointsArguments) -> SetDataBreakpointsResponse {
+    self.active.clear();
+    let mut breakpoints = Vec::with_capacity(args.breakpoints.len());
+    for requested in &args.breakpoints {
+      let id = self.next_id;
+      self.next_id += 1;
+      match self.validate(&requested.data_id, requested.access_type.as_ref()) {
+        Ok((data, access_type)) => {
+          self.active.push(ActiveDataBreakpoint {
+            id,
+            data,
+            access_type,
+            condition: requested.condition.clone(),
+            hit_condition: requested.hit_condition.clone(),
+          });
+          breakpoints.push(Breakpoint {
+            id: Some(id),
+            verified: true,
+            ..Default::default()
+          });
+        }
+        Err(e) => breakpoints.push(Breakpoint {
+          id: Some(id),
+          verified: false,
+          message: Some(e.to_string()),
+          ..Default::default()
+        }),
+      }
+    }
+    SetDataBreakpointsResponse { breakpoints }
+  }
+
+  /// The data breakpoints set by the last `setDataBreakpoints` request that passed validation.
+  pub fn active(&self) -> &[ActiveDataBreakpoint] {
+    &self.active
+  }
+
+  /// Returns the active data breakpoints whose range contains the given address.
+  pub fn hits(&self, address: u64) -> impl Iterator<Item = &ActiveDataBreakpoint> {
+    self.active.iter().filter(move |breakpoint| {
+      let start = breakpoint.data.address;
+      address >= start && address - start < breakpoint.data.bytes
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::DataBreakpoint;
+
+  fn watch(address: u64, bytes: u64) -> DataId {
+    DataId {
+      address,
+      bytes,
+      access_types: vec![
+        DataBreakpointAccessType::Write,
+        DataBreakpointAccessType::ReadWrite,
+      ],
+    }
+  }
+
+  #[test]
+  fn test_data_id_round_trip() {
+    let data = watch(0x7ffd_1000, 8);
+    assert_eq!(data.encode(), "0x7ffd1000/8/write,readWrite");
+    assert_eq!(DataId::decode(&data.encode()).unwrap(), data);
+    assert!(DataId::decode("0x10/8").is_err());
+    assert!(DataId::decode("16/8/read").is_err());
+    assert!(DataId::decode("0x10/eight/read").is_err());
+    assert!(DataId::decode("0x10/0/read").is_err());
+    assert!(DataId::decode("0x10/8/execute").is_err());
+
+    assert_eq!(parse_address("0x1F").unwrap(), 31);
+    assert_eq!(parse_address("4096").unwrap(), 4096);
+    assert!(parse_address("&x").is_err());
+  }
+
+  #[test]
+  fn test_set_data_breakpoints() {
+    let mut registry = DataBreakpointRegistry::new();
+    let info = registry.info_response(watch(0x1000, 4), "x").unwrap();
+    let data_id = info.data_id.unwrap();
+    assert_eq!(data_id, "0x1000/4/write,readWrite");
+    let args = SetDataBreakpointsArguments {
+      breakpoints: vec![
+        DataBreakpoint {
+          data_id: data_id.clone(),
+          access_type: Some(DataBreakpointAccessType::ReadWrite),
+          ..Default::default()
+        },
+        DataBreakpoint {
+          data_id: data_id.clone(),
+          access_type: Some(DataBreakpointAccessType::Read),
+          ..Default::default()
+        },
+        DataBreakpoint {
+          data_id: "not an id".to_string(),
+          ..Default::default()
+        },
+        DataBreakpoint {
+          data_id: "0x0/18446744073709551615/write".to_string(),
+          ..Default::default()
+        },
+        DataBreakpoint {
+          data_id,
+          ..Default::default()
+        },
+      ],
+    };
+    let response = registry.set(&args);
+    let verified: Vec<bool> = response.breakpoints.iter().map(|b| b.verified).collect();
+    assert_eq!(verified, vec![true, false, false, false, true]);
+    assert_eq!(
+      response.breakpoints[1].message.as_deref(),
+      Some("Access type 'read' is not available for data breakpoint '0x1000/4/write,readWrite'")
+    );
+    assert_eq!(
+      response.breakpoints[3].message.as_deref(),
+      Some("Data breakpoint id '0x0/18446744073709551615/write' was not issued by this adapter")
+    );
+    assert_eq!(registry.active().len(), 2);
+    assert_eq!(
+      registry.active()[1].access_type,
+      DataBreakpointAccessType::Write
+    );
+    assert_eq!(registry.hits(0x1003).count(), 2);
+    assert_eq!(registry.hits(0x1004).count(), 0);
+
+    registry.set(&SetDataBreakpointsArguments::default());
+    assert!(registry.active().is_empty());
+
+    assert!(registry.info_response(watch(0x1000, 0), "x").is_err());
+    let mut unknown_access = watch(0x1000, 4);
+    unknown_access.access_types = vec![DataBreakpointAccessType::String("execute".to_string())];
+    assert!(registry.info_response(unknown_access, "x").is_err());
+  }
+}
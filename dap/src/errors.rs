@@ -1,6 +1,8 @@
 use std::fmt::Debug;
 use thiserror::Error;
 
+use crate::types::BreakpointModeApplicability;
+
 #[derive(Debug, Error)]
 pub enum DeserializationError {
   #[error("could not parse value '{value}' to enum variant of '{enum_name}'")]
@@ -28,6 +30,9 @@ pub enum ServerError {
   #[error("Protocol error while reading line '{line}', reason: '{reason}'")]
   ProtocolError { reason: String, line: String },
 
+  #[error("Content-Length {length} exceeds the maximum of {max} bytes")]
+  ContentTooLarge { length: usize, max: usize },
+
   #[error("Serialization error")]
   SerializationError(#[from] serde_json::Error),
 
@@ -36,4 +41,69 @@ pub enum ServerError {
 
   #[error("Output lock is poisoned")]
   OutputLockError,
+
+  #[error("Connections lock is poisoned")]
+  ConnectionsLockError,
+
+  #[error("Reverse request '{command}' failed: {message}")]
+  ReverseRequestFailed { command: String, message: String },
+
+  #[error("The client does not support the '{command}' reverse request")]
+  ReverseRequestUnsupported { command: String },
+
+  #[error("Invalid transport '{spec}': {reason}")]
+  InvalidTransport { spec: String, reason: String },
+
+  #[cfg(feature = "websocket")]
+  #[error("WebSocket error")]
+  WebSocketError(Box<tungstenite::Error>),
+
+  #[cfg(feature = "schema_validation")]
+  #[error("Message does not conform to the '{definition}' schema: {}", .errors.join("; "))]
+  SchemaViolation {
+    definition: String,
+    errors: Vec<String>,
+  },
+}
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+  #[error("No pending child session for token '{token}'")]
+  UnknownToken { token: String },
+
+  #[error("Child session '{token}' was expected to start with a '{expected}' request")]
+  RequestKindMismatch { token: String, expected: String },
+
+  #[error("Registry lock is poisoned")]
+  LockError,
+}
+
+#[derive(Debug, Error)]
+pub enum BreakpointModeError {
+  #[error("Unknown breakpoint mode '{mode}'")]
+  UnknownMode { mode: String },
+
+  #[error("Breakpoint mode '{mode}' does not apply to {applicability} breakpoints")]
+  NotApplicable {
+    mode: String,
+    applicability: BreakpointModeApplicability,
+  },
+}
+
+#[derive(Debug, Error)]
+pub enum DataBreakpointError {
+  #[error("Invalid data breakpoint id '{data_id}'")]
+  InvalidDataId { data_id: String },
+
+  #[error("Data breakpoint id '{data_id}' was not issued by this adapter")]
+  UnknownDataId { data_id: String },
+
+  #[error("Invalid address '{address}'")]
+  InvalidAddress { address: String },
+
+  #[error("Access type '{access_type}' is not available for data breakpoint '{data_id}'")]
+  UnsupportedAccessType {
+    data_id: String,
+    access_type: String,
+  },
 }
@@ -1,19 +1,21 @@
-use serde::Serialize;
+#[cfg(feature = "integration_testing")]
+use fake::Dummy;
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 
-#[cfg(feature = "client")]
-use serde::Deserialize;
-
 use crate::types::{
   Breakpoint, BreakpointEventReason, Capabilities, InvalidatedAreas, LoadedSourceEventReason,
   Module, ModuleEventReason, OutputEventCategory, OutputEventGroup, ProcessEventStartMethod,
   Source, StoppedEventReason, ThreadEventReason,
 };
 
+#[cfg(feature = "integration_testing")]
+use crate::types::ValueFaker;
+
 //// Arguments for a Breakpoint event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct BreakpointEventBody {
   /// The reason for the event.
   /// Values: 'changed', 'new', 'removed', etc.
@@ -24,57 +26,61 @@ pub struct BreakpointEventBody {
 }
 
 //// Arguments for a Capabilities event
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct CapabilitiesEventBody {
   pub capabilities: Capabilities,
 }
 
 //// Arguments for a Continued event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ContinuedEventBody {
   /// The thread which was continued.
   pub thread_id: i64,
   /// If `allThreadsContinued` is true, a debug adapter can announce that all threads have
   /// continued.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub all_threads_continued: Option<bool>,
 }
 
 //// Arguments for a Exited event
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExitedEventBody {
   /// The exit code returned from the debuggee.
   pub exit_code: i64,
 }
 
 //// Arguments for a Invalidated event
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct InvalidatedEventBody {
   /// Set of logical areas that got invalidated. This property has a hint
   /// characteristic: a client can only be expected to make a 'best effort' in
   /// honouring the areas but there are no guarantees. If this property is
   /// missing, empty, or if values are not understood, the client should assume
   /// a single value `all`.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub areas: Option<Vec<InvalidatedAreas>>,
   /// If specified, the client only needs to refetch data related to this
   /// thread.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub thread_id: Option<i64>,
   /// If specified, the client only needs to refetch data related to this stack
   /// frame (and the `threadId` is ignored).
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub stack_frame_id: Option<i64>,
 }
 
 //// Arguments for a LoadedSource event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct LoadedSourceEventBody {
   /// The reason for the event.
   /// Values: 'new', 'changed', 'removed'
@@ -84,9 +90,9 @@ pub struct LoadedSourceEventBody {
 }
 
 //// Arguments for a Memory event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct MemoryEventBody {
   /// Memory reference of a memory range that has been updated.
   pub memory_reference: String,
@@ -97,9 +103,9 @@ pub struct MemoryEventBody {
 }
 
 //// Arguments for a Module event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ModuleEventBody {
   /// The reason for the event.
   /// Values: 'new', 'changed', 'removed'
@@ -110,9 +116,9 @@ pub struct ModuleEventBody {
 }
 
 //// Arguments for an Output event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct OutputEventBody {
   /// The output category. If not specified or if the category is not
   /// understood by the client, `console` is assumed.
@@ -131,6 +137,7 @@ pub struct OutputEventBody {
   /// 'telemetry': Send the output to telemetry instead of showing it to the
   /// user.
   /// etc.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub category: Option<OutputEventCategory>,
   /// The output to report.
   pub output: String,
@@ -147,38 +154,47 @@ pub struct OutputEventBody {
   /// output events.
   /// A non-empty `output` attribute is shown as the unindented end of the
   /// group.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub group: Option<OutputEventGroup>,
   /// If an attribute `variablesReference` exists and its value is > 0, the
   /// output contains objects which can be retrieved by passing
   /// `variablesReference` to the `variables` request. The value should be less
   /// than or equal to 2147483647 (2^31-1).
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub variables_reference: Option<i64>,
   /// The source location where the output was produced.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub source: Option<Source>,
   /// The source location's line where the output was produced.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub line: Option<i64>,
   /// The position in `line` where the output was produced. It is measured in
   /// UTF-16 code units and the client capability `columnsStartAt1` determines
   /// whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub column: Option<i64>,
   /// Additional data to report. For the `telemetry` category the data is sent
   /// to telemetry, for the other categories the data is shown in JSON format.
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ValueFaker"))]
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub data: Option<Value>,
 }
 
 //// Arguments for an Process event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ProcessEventBody {
   /// The logical name of the process. This is usually the full path to
   /// process's executable file. Example: /home/example/myproj/program.js.
   pub name: String,
   /// The system process id of the debugged process. This property is missing
   /// for non-system processes.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub system_process_id: Option<i64>,
   /// If true, the process is running on the same computer as the debug
   /// adapter.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub is_local_process: Option<bool>,
   /// Describes how the debug engine started debugging this process.
   /// Values:
@@ -186,28 +202,31 @@ pub struct ProcessEventBody {
   /// 'attach': Debugger attached to an existing process.
   /// 'attachForSuspendedLaunch': A project launcher component has launched a
   /// new process in a suspended state and then asked the debugger to attach.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub start_method: Option<ProcessEventStartMethod>,
   /// The size of a pointer or address for this process, in bits. This value
   /// may be used by clients when formatting addresses for display.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub pointer_size: Option<i64>,
 }
 
 //// Arguments for a ProgressEnd event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ProgressEndEventBody {
   /// The ID that was introduced in the initial `ProgressStartEvent`.
   pub progress_id: String,
   /// More detailed progress message. If omitted, the previous message (if any)
   /// is used.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub message: Option<String>,
 }
 
 //// Arguments for a ProgressStart event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ProgressStartEventBody {
   /// An ID that can be used in subsequent `progressUpdate` and `progressEnd`
   /// events to make them refer to the same progress reporting.
@@ -221,6 +240,7 @@ pub struct ProgressStartEventBody {
   /// request until the request has been either completed or cancelled.
   /// If the request ID is omitted, the progress report is assumed to be
   /// related to some general activity of the debug adapter.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub request_id: Option<i64>,
   /// If true, the request that reports progress may be cancelled with a
   /// `cancel` request.
@@ -228,33 +248,38 @@ pub struct ProgressStartEventBody {
   /// supports cancellation.
   /// Clients that don't support cancellation are allowed to ignore the
   /// setting.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub cancellable: Option<bool>,
   /// More detailed progress message.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub message: Option<String>,
   /// Progress percentage to display (value range: 0 to 100). If omitted no
   /// percentage is shown.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub percentage: Option<i64>,
 }
 
 //// Arguments for a ProgressUpdate event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ProgressUpdateEventBody {
   /// The ID that was introduced in the initial `progressStart` event.
   pub progress_id: String,
   /// More detailed progress message. If omitted, the previous message (if any)
   /// is used.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub message: Option<String>,
   /// Progress percentage to display (value range: 0 to 100). If omitted no
   /// percentage is shown.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub percentage: Option<i64>,
 }
 
 //// Arguments for a Stopped event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StoppedEventBody {
   /// The reason for the event.
   /// For backward compatibility this String is shown in the UI if the
@@ -264,14 +289,18 @@ pub struct StoppedEventBody {
   pub reason: StoppedEventReason,
   /// The full reason for the event, e.g. 'Paused on exception'. This String is
   /// shown in the UI as is and can be translated.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub description: Option<String>,
   /// The thread which was stopped.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub thread_id: Option<i64>,
   /// A value of true hints to the client that this event should not change the
   /// focus.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub preserve_focus_hint: Option<bool>,
   /// Additional information. E.g. if reason is `exception`, text contains the
   /// exception name. This String is shown in the UI.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub text: Option<String>,
   /// If `allThreadsStopped` is true, a debug adapter can announce that all
   /// threads have stopped.
@@ -279,6 +308,7 @@ pub struct StoppedEventBody {
   /// be expanded to access their stacktraces.
   /// - If the attribute is missing or false, only the thread with the given
   /// `threadId` can be expanded.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub all_threads_stopped: Option<bool>,
   /// Ids of the breakpoints that triggered the event. In most cases there is
   /// only a single breakpoint but here are some examples for multiple
@@ -288,25 +318,28 @@ pub struct StoppedEventBody {
   /// the compiler/runtime.
   /// - Multiple function breakpoints with different function names map to the
   /// same location.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub hit_breakpoint_ids: Option<Vec<i64>>,
 }
 
 //// Arguments for a Terminated event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct TerminatedEventBody {
   /// A debug adapter may set `restart` to true (or to an arbitrary object) to
   /// request that the client restarts the session.
   /// The value is not interpreted by the client and passed unmodified as an
   /// attribute `__restart` to the `launch` and `attach` requests.
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ValueFaker"))]
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub restart: Option<Value>,
 }
 
 //// Arguments for a Thread event.
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ThreadEventBody {
   /// The reason for the event.
   /// Values: 'started', 'exited', etc.
@@ -315,9 +348,16 @@ pub struct ThreadEventBody {
   pub thread_id: i64,
 }
 
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
-#[serde(tag = "event", content = "body", rename_all = "camelCase")]
+// `remote = "Self"` turns the derived implementations into inherent functions, so that the trait
+// implementations below can special-case a terminated event without a body.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(
+  remote = "Self",
+  tag = "event",
+  content = "body",
+  rename_all = "camelCase"
+)]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum Event {
   /// This event indicates that the debug adapter is ready to accept configuration requests (e.g.
   /// `setBreakpoints`, `setExceptionBreakpoints`).
@@ -425,3 +465,24 @@ pub enum Event {
   /// Specification: [Thread event](https://microsoft.github.io/debug-adapter-protocol/specification#Events_Thread)
   Thread(ThreadEventBody),
 }
+
+impl Serialize for Event {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self {
+      // The specification doesn't allow a `null` body, so a terminated event without a body is
+      // sent without one.
+      Event::Terminated(None) => {
+        let mut event = serializer.serialize_struct("Event", 1)?;
+        event.serialize_field("event", "terminated")?;
+        event.end()
+      }
+      event => Event::serialize(event, serializer),
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for Event {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    Event::deserialize(deserializer)
+  }
+}
@@ -0,0 +1,219 @@
+//! Reading and writing messages framed by a `Content-Length` header, the base protocol of DAP.
+//!
+//! Each message is a header followed by a JSON document. The header consists of a
+//! `Content-Length` field that gives the length of the document in bytes, and is terminated by an
+//! empty line:
+//!
+//! ```text
+//! Content-Length: 52\r\n
+//! \r\n
+//! {"seq": 1, "type": "request", "command": "threads"}
+//! ```
+//!
+//! The length comes from the other side of the connection, so it is checked against a limit and
+//! memory is only allocated for the content as it arrives.
+//!
+//! ```rust
+//! use dap::framing::{read_message, write_message, DEFAULT_MAX_CONTENT_LENGTH};
+//! use serde_json::{json, Value};
+//!
+//! let mut buffer = Vec::new();
+//! write_message(&mut buffer, &json!({"seq": 1, "type": "event", "event": "initialized"})).unwrap();
+//!
+//! let mut input = buffer.as_slice();
+//! let message: Value = read_message(&mut input, DEFAULT_MAX_CONTENT_LENGTH).unwrap().unwrap();
+//! assert_eq!(message["event"], "initialized");
+//! assert!(read_message::<_, Value>(&mut input, DEFAULT_MAX_CONTENT_LENGTH).unwrap().is_none());
+//! ```
+use std::io::{BufRead, Read, Write};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::errors::{DeserializationError, ServerError};
+
+/// The default limit for the `Content-Length` of incoming messages.
+pub const DEFAULT_MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Header lines are short; anything longer than this is not a DAP peer talking.
+const MAX_HEADER_LINE_LENGTH: u64 = 1024;
+
+/// Read the content of a single message. Returns `None` if the input ends before the next
+/// message.
+///
+/// Empty lines before the header, such as a line break after the content of the previous
+/// message, are skipped. Fails with [`ServerError::ContentTooLarge`] if the header announces
+/// more than `max_content_length` bytes.
+pub fn read_frame<R: BufRead>(
+  input: &mut R,
+  max_content_length: usize,
+) -> Result<Option<Vec<u8>>, ServerError> {
+  let mut content_length = None;
+  let mut line = String::new();
+  loop {
+    line.clear();
+    if read_header_line(input, &mut line).map_err(ServerError::IoError)? == 0 {
+      if content_length.is_none() {
+        return Ok(None);
+      }
+      return Err(ServerError::IoError(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "the input ended in the header of a message",
+      )));
+    }
+    let header = line.trim_end();
+    if header.is_empty() {
+      if content_length.is_some() {
+        break;
+      }
+      continue;
+    }
+    match header.split_once(':') {
+      Some(("Content-Length", length)) => {
+        let length = length
+          .trim()
+          .parse::<usize>()
+          .map_err(|_| ServerError::HeaderParseError { line: line.clone() })?;
+        if length > max_content_length {
+          return Err(ServerError::ContentTooLarge {
+            length,
+            max: max_content_length,
+          });
+        }
+        content_length = Some(length);
+      }
+      Some((other, _)) => {
+        return Err(ServerError::UnknownHeader {
+          header: other.to_string(),
+        })
+      }
+      None => return Err(ServerError::HeaderParseError { line }),
+    }
+  }
+
+  read_content(input, content_length.unwrap_or_default()).map(Some)
+}
+
+/// Read a single message and deserialize it. Returns `None` if the input ends before the next
+/// message.
+pub fn read_message<R: BufRead, T: DeserializeOwned>(
+  input: &mut R,
+  max_content_length: usize,
+) -> Result<Option<T>, ServerError> {
+  match read_frame(input, max_content_length)? {
+    Some(content) => serde_json::from_slice(&content)
+      .map(Some)
+      .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e))),
+    None => Ok(None),
+  }
+}
+
+/// Serialize a message, write it with its header and flush the output.
+pub fn write_message<W: Write + ?Sized, T: Serialize + ?Sized>(
+  output: &mut W,
+  message: &T,
+) -> Result<(), ServerError> {
+  let content = serde_json::to_string(message).map_err(ServerError::SerializationError)?;
+  write!(
+    output,
+    "Content-Length: {}\r\n\r\n{}",
+    content.len(),
+    content
+  )
+  .map_err(ServerError::IoError)?;
+  output.flush().map_err(ServerError::IoError)
+}
+
+/// Read a header line (or the empty line that ends the header). Fails if the line is longer than
+/// any header line a peer would send, instead of buffering it without limit.
+fn read_header_line<R: BufRead>(input: &mut R, line: &mut String) -> std::io::Result<usize> {
+  let read = input.take(MAX_HEADER_LINE_LENGTH).read_line(line)?;
+  if read as u64 == MAX_HEADER_LINE_LENGTH && !line.ends_with('\n') {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      "header line too long",
+    ));
+  }
+  Ok(read)
+}
+
+/// Read the content of a message. The buffer grows with the data that actually arrives, so a
+/// bogus `Content-Length` can't make us allocate memory up front.
+fn read_content<R: Read>(input: &mut R, length: usize) -> Result<Vec<u8>, ServerError> {
+  let mut content = Vec::new();
+  input
+    .take(length as u64)
+    .read_to_end(&mut content)
+    .map_err(ServerError::IoError)?;
+  if content.len() < length {
+    return Err(ServerError::IoError(std::io::Error::new(
+      std::io::ErrorKind::UnexpectedEof,
+      "the input ended before the content of the message",
+    )));
+  }
+  Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::{json, Value};
+
+  use super::*;
+
+  fn read(input: &str) -> Result<Option<Vec<u8>>, ServerError> {
+    read_frame(&mut input.as_bytes(), 1024)
+  }
+
+  #[test]
+  fn test_read_frame() {
+    let mut input = "Content-Length: 2\r\n\r\n{}\r\n\r\nContent-Length: 4\r\n\r\nnull".as_bytes();
+    assert_eq!(read_frame(&mut input, 16).unwrap().unwrap(), b"{}");
+    assert_eq!(read_frame(&mut input, 16).unwrap().unwrap(), b"null");
+    assert!(read_frame(&mut input, 16).unwrap().is_none());
+    assert!(read("\r\n").unwrap().is_none());
+  }
+
+  #[test]
+  fn test_read_frame_errors() {
+    assert!(matches!(
+      read("Content-Length: 18446744073709551615\r\n\r\n{}"),
+      Err(ServerError::ContentTooLarge { max: 1024, .. })
+    ));
+    assert!(matches!(
+      read("Content-Length: 10\r\n\r\n{}"),
+      Err(ServerError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+    ));
+    assert!(matches!(
+      read("Content-Length: 2\r\n"),
+      Err(ServerError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+    ));
+    assert!(matches!(
+      read("Content-Length: 2\r\n{}"),
+      Err(ServerError::HeaderParseError { .. })
+    ));
+    assert!(matches!(
+      read("Content-Type: application/json\r\n\r\n"),
+      Err(ServerError::UnknownHeader { .. })
+    ));
+    assert!(matches!(
+      read("Content-Length: -1\r\n\r\n"),
+      Err(ServerError::HeaderParseError { .. })
+    ));
+    let endless_header = "C".repeat(1024 * 1024);
+    assert!(matches!(
+      read(&endless_header),
+      Err(ServerError::IoError(e)) if e.kind() == std::io::ErrorKind::InvalidData
+    ));
+  }
+
+  #[test]
+  fn test_message_round_trip() {
+    let message = json!({"seq": 1, "type": "request", "command": "threads"});
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, &message).unwrap();
+    assert!(buffer.starts_with(b"Content-Length: "));
+    let read: Value = read_message(&mut buffer.as_slice(), DEFAULT_MAX_CONTENT_LENGTH)
+      .unwrap()
+      .unwrap();
+    assert_eq!(read, message);
+  }
+}
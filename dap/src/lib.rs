@@ -31,7 +31,7 @@
 //!
 //! Our dummy server is going to read its input from a text file and write the output to stdout.
 //!
-//! ```rust
+//! ```rust,no_run
 //! use std::fs::File;
 //! use std::io::{BufReader, BufWriter};
 //!
@@ -62,9 +62,9 @@
 //!   };
 //!   if let Command::Initialize(_) = req.command {
 //!     let rsp = req.success(
-//!       ResponseBody::Initialize(Some(types::Capabilities {
+//!       ResponseBody::Initialize(types::Capabilities {
 //!         ..Default::default()
-//!       })),
+//!       }),
 //!     );
 //!
 //!     // When you call respond, send_event etc. the message will be wrapped
@@ -97,14 +97,33 @@
 //!   Ok(())
 //! }
 //! ```
+pub mod ansi;
 pub mod base_message;
+pub mod builders;
+pub mod data_breakpoints;
 pub mod errors;
 pub mod events;
+pub mod framing;
+pub mod locations;
+pub mod lossless;
 pub mod prelude;
+pub mod proxy;
+pub mod recording;
 pub mod requests;
 pub mod responses;
 pub mod reverse_requests;
+pub mod router;
+#[cfg(feature = "schema_validation")]
+pub mod schema;
 pub mod server;
+pub mod sessions;
+pub mod tcp;
+#[cfg(feature = "test_support")]
+pub mod test_support;
+pub mod transport;
+pub mod typed;
 pub mod types;
 pub mod utils;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 pub use utils::get_spec_version;
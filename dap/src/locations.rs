@@ -0,0 +1,128 @@
+//! Bookkeeping for the location references used by the `locations` request.
+//!
+//! `Variable` and `EvaluateResponse` can carry a `declarationLocationReference` or a
+//! `valueLocationReference` that the client later resolves with a `locations` request. Like a
+//! `variablesReference`, such a reference is just a number handed out by the adapter, so the
+//! adapter has to remember what it stands for. [`LocationReferences`] does that: it allocates a
+//! reference for a location and answers `locations` requests for it.
+//!
+//! Location references share the lifetime of variable references, so an adapter should call
+//! [`LocationReferences::clear`] whenever execution resumes.
+use std::collections::HashMap;
+
+use crate::{
+  requests::{Command, Request},
+  responses::{LocationsResponse, Response, ResponseBody},
+  types::Source,
+};
+
+/// Allocates location references and resolves them back to a source location.
+#[derive(Debug)]
+pub struct LocationReferences {
+  next: i64,
+  locations: HashMap<i64, LocationsResponse>,
+}
+
+impl Default for LocationReferences {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl LocationReferences {
+  pub fn new() -> Self {
+    Self {
+      next: 1,
+      locations: HashMap::new(),
+    }
+  }
+
+  /// Allocates a reference for a location. The returned value is always > 0 and is never reused,
+  /// not even after [`clear`](Self::clear), so a stale reference can't resolve to a new location.
+  pub fn allocate(&mut self, location: LocationsResponse) -> i64 {
+    let reference = self.next;
+    self.next += 1;
+    self.locations.insert(reference, location);
+    reference
+  }
+
+  /// Allocates a reference for a line (and optionally a column) in a source.
+  pub fn allocate_line(&mut self, source: Source, line: i64, column: Option<i64>) -> i64 {
+    self.allocate(LocationsResponse {
+      source,
+      line,
+      column,
+      ..Default::default()
+    })
+  }
+
+  /// Returns the location a reference was allocated for.
+  pub fn resolve(&self, reference: i64) -> Option<&LocationsResponse> {
+    self.locations.get(&reference)
+  }
+
+  /// Forgets all allocated references.
+  pub fn clear(&mut self) {
+    self.locations.clear();
+  }
+
+  /// Answers a `locations` request. Unknown references and other commands get an error response.
+  pub fn respond(&self, request: Request) -> Response {
+    let reference = match &request.command {
+      Command::Locations(args) => args.location_reference,
+      _ => return request.error("Not a locations request"),
+    };
+    match self.resolve(reference) {
+      Some(location) => request.success(ResponseBody::Locations(location.clone())),
+      None => request.error(&format!("Unknown location reference {}", reference)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::requests::LocationsArguments;
+
+  fn locations_request(reference: i64) -> Request {
+    Request {
+      seq: 1,
+      command: Command::Locations(LocationsArguments {
+        location_reference: reference,
+      }),
+    }
+  }
+
+  #[test]
+  fn test_allocate_and_resolve() {
+    let mut references = LocationReferences::new();
+    let source = Source {
+      path: Some("/src/main.rs".to_string()),
+      ..Default::default()
+    };
+    let first = references.allocate_line(source.clone(), 10, Some(4));
+    let second = references.allocate_line(source, 20, None);
+    assert!(first > 0);
+    assert_ne!(first, second);
+    assert_eq!(references.resolve(first).unwrap().line, 10);
+    assert_eq!(references.resolve(second).unwrap().column, None);
+
+    let response = references.respond(locations_request(first));
+    assert!(response.success);
+    assert!(matches!(
+      response.body,
+      Some(ResponseBody::Locations(LocationsResponse {
+        line: 10,
+        column: Some(4),
+        ..
+      }))
+    ));
+
+    references.clear();
+    assert!(references.resolve(first).is_none());
+    assert!(!references.respond(locations_request(first)).success);
+
+    let third = references.allocate_line(Source::default(), 1, None);
+    assert!(third > second);
+  }
+}
@@ -0,0 +1,248 @@
+//! Lossless relaying of protocol messages.
+//!
+//! The protocol types only model the properties of the specification version this crate
+//! implements, so deserializing a message and serializing it again drops everything else: newer
+//! properties as well as vendor extensions. The proxy and the recorder pass messages on as raw
+//! JSON, so they are not affected. Code that wants to work with the typed messages, e.g. a proxy
+//! middleware that rewrites a stack trace, can wrap them in [`Lossless`]. It remembers the
+//! properties the type doesn't model and puts them back when the value is serialized again.
+//!
+//! ```rust
+//! use dap::lossless::Lossless;
+//! use dap::types::Source;
+//! use serde_json::json;
+//!
+//! let mut source: Lossless<Source> = serde_json::from_value(json!({
+//!   "name": "main.rs",
+//!   "vendorHint": "generated",
+//! }))
+//! .unwrap();
+//! source.path = Some("/src/main.rs".to_string());
+//! assert_eq!(
+//!   serde_json::to_value(&source).unwrap(),
+//!   json!({"name": "main.rs", "path": "/src/main.rs", "vendorHint": "generated"})
+//! );
+//! ```
+use std::ops::{Deref, DerefMut};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// A protocol value together with the properties of its JSON that the type doesn't model.
+///
+/// The value can be used and modified through `Deref`. Unknown properties are put back where
+/// they were found, as long as the object that held them is still there and doesn't have a
+/// property of the same name. Unknown properties of array elements are put back by position, so
+/// they end up on the wrong element if the elements are reordered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lossless<T> {
+  value: T,
+  unknown: Unknown,
+}
+
+impl<T> Lossless<T> {
+  /// Wrap a value that has no unknown properties.
+  pub fn new(value: T) -> Self {
+    Self {
+      value,
+      unknown: Unknown::default(),
+    }
+  }
+
+  /// Whether the JSON the value was read from had properties the type doesn't model.
+  pub fn has_unknown(&self) -> bool {
+    !self.unknown.is_empty()
+  }
+
+  /// The value, without the unknown properties.
+  pub fn into_inner(self) -> T {
+    self.value
+  }
+}
+
+impl<T: Serialize + DeserializeOwned> Lossless<T> {
+  /// Read a value from JSON, keeping the properties the type doesn't model.
+  pub fn from_value(json: Value) -> Result<Self, serde_json::Error> {
+    let value = T::deserialize(&json)?;
+    let known = serde_json::to_value(&value)?;
+    Ok(Self {
+      value,
+      unknown: Unknown::between(&json, &known),
+    })
+  }
+
+  /// Write the value to JSON, including the unknown properties it was read with.
+  pub fn to_value(&self) -> Result<Value, serde_json::Error> {
+    let mut json = serde_json::to_value(&self.value)?;
+    self.unknown.restore(&mut json);
+    Ok(json)
+  }
+}
+
+impl<T> From<T> for Lossless<T> {
+  fn from(value: T) -> Self {
+    Self::new(value)
+  }
+}
+
+impl<T> Deref for Lossless<T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.value
+  }
+}
+
+impl<T> DerefMut for Lossless<T> {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.value
+  }
+}
+
+impl<T: Serialize + DeserializeOwned> Serialize for Lossless<T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self
+      .to_value()
+      .map_err(serde::ser::Error::custom)?
+      .serialize(serializer)
+  }
+}
+
+impl<'de, T: Serialize + DeserializeOwned> Deserialize<'de> for Lossless<T> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let json = Value::deserialize(deserializer)?;
+    Self::from_value(json).map_err(serde::de::Error::custom)
+  }
+}
+
+/// Where unknown properties further down are found.
+#[derive(Debug, Clone, PartialEq)]
+enum Key {
+  Property(String),
+  Index(usize),
+}
+
+/// The properties of a JSON value that didn't survive deserializing and serializing it again.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Unknown {
+  /// Properties that are missing altogether.
+  properties: Map<String, Value>,
+  /// Properties that were kept but have unknown properties of their own.
+  nested: Vec<(Key, Unknown)>,
+}
+
+impl Unknown {
+  /// Compare the JSON a value was read from with what the value serializes to.
+  fn between(json: &Value, known: &Value) -> Self {
+    let mut unknown = Self::default();
+    match (json, known) {
+      (Value::Object(json), Value::Object(known)) => {
+        for (name, value) in json {
+          match known.get(name) {
+            Some(known) => unknown.push(Key::Property(name.clone()), value, known),
+            None => {
+              unknown.properties.insert(name.clone(), value.clone());
+            }
+          }
+        }
+      }
+      (Value::Array(json), Value::Array(known)) => {
+        for (index, (value, known)) in json.iter().zip(known).enumerate() {
+          unknown.push(Key::Index(index), value, known);
+        }
+      }
+      _ => {}
+    }
+    unknown
+  }
+
+  fn push(&mut self, key: Key, json: &Value, known: &Value) {
+    let unknown = Self::between(json, known);
+    if !unknown.is_empty() {
+      self.nested.push((key, unknown));
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    self.properties.is_empty() && self.nested.is_empty()
+  }
+
+  fn restore(&self, json: &mut Value) {
+    if let Value::Object(object) = json {
+      for (name, value) in &self.properties {
+        object.entry(name.clone()).or_insert_with(|| value.clone());
+      }
+    }
+    for (key, unknown) in &self.nested {
+      let child = match key {
+        Key::Property(name) => json.get_mut(name.as_str()),
+        Key::Index(index) => json.get_mut(*index),
+      };
+      if let Some(child) = child {
+        unknown.restore(child);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+  use crate::types::StackFrame;
+
+  fn frames() -> Value {
+    json!([
+      {
+        "id": 1,
+        "name": "main",
+        "line": 3,
+        "column": 1,
+        "source": {"name": "main.rs", "vendorOrigin": "build.rs"},
+        "canRestart": true,
+        "futureProperty": {"nested": [1, 2]},
+      },
+      {"id": 2, "name": "start", "line": 7, "column": 1},
+    ])
+  }
+
+  #[test]
+  fn test_round_trip() {
+    let value: Lossless<Vec<StackFrame>> = serde_json::from_value(frames()).unwrap();
+    assert!(value.has_unknown());
+    assert_eq!(serde_json::to_value(&value).unwrap(), frames());
+
+    // Without the wrapper, the unknown properties are lost.
+    let frames: Vec<StackFrame> = serde_json::from_value(frames()).unwrap();
+    let json = serde_json::to_value(frames).unwrap();
+    assert!(json[0].get("futureProperty").is_none());
+    assert!(json[0]["source"].get("vendorOrigin").is_none());
+  }
+
+  #[test]
+  fn test_modified_value() {
+    let mut value = Lossless::<Vec<StackFrame>>::from_value(frames()).unwrap();
+    value[0].line = 4;
+    value[0].can_restart = None;
+    value[1].source = value[0].source.take();
+
+    let json = value.to_value().unwrap();
+    assert_eq!(json[0]["line"], 4);
+    assert!(json[0].get("canRestart").is_none());
+    assert_eq!(json[0]["futureProperty"], json!({"nested": [1, 2]}));
+    // The unknown property stays where it was found, which no longer exists.
+    assert!(json[0].get("source").is_none());
+    assert_eq!(json[1]["source"], json!({"name": "main.rs"}));
+  }
+
+  #[test]
+  fn test_new_value() {
+    let value = Lossless::new(StackFrame::default());
+    assert!(!value.has_unknown());
+    assert_eq!(
+      value.to_value().unwrap(),
+      serde_json::to_value(StackFrame::default()).unwrap()
+    );
+  }
+}
@@ -0,0 +1,557 @@
+//! A proxy that sits between a client and an upstream adapter.
+//!
+//! Every message is passed through a chain of [`Middleware`] that can inspect, modify or drop
+//! it, and synthesize new messages in either direction. The proxy keeps separate sequence
+//! numbers towards the client and the adapter, and rewrites `request_seq` (and the `requestId`
+//! of `cancel` requests) so that both sides see consistent numbering.
+//!
+//! ```ignore
+//! let proxy = Proxy::new().with_middleware(|direction, message, _: &mut Outbox| {
+//!   eprintln!("{:?}: {}", direction, message);
+//!   Some(message)
+//! });
+//! let upstream = Upstream::Spawn(std::process::Command::new("my-adapter")).open()?;
+//! proxy.run(std::io::stdin(), std::io::stdout(), upstream)?;
+//! ```
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::channel;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::Value;
+
+use crate::{
+  base_message::{BaseMessage, Sendable},
+  errors::ServerError,
+  events::Event,
+  framing::{read_message, write_message, DEFAULT_MAX_CONTENT_LENGTH},
+  requests::Request,
+  responses::Response,
+  transport::{BoxedReader, BoxedWriter, Transport},
+};
+
+/// The direction a message travels in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+  /// From the client to the adapter.
+  ToAdapter,
+  /// From the adapter to the client.
+  ToClient,
+}
+
+/// Messages synthesized by middleware.
+///
+/// Synthesized messages skip the rest of the chain. Their `seq` is assigned by the proxy, but
+/// `request_seq` is sent as it is, so a response must refer to the `seq` of the request as it was
+/// sent by the other side.
+#[derive(Debug, Default)]
+pub struct Outbox {
+  messages: Vec<(Direction, Value)>,
+}
+
+impl Outbox {
+  /// Send a message in the given direction.
+  pub fn send(&mut self, direction: Direction, message: Value) {
+    self.messages.push((direction, message));
+  }
+
+  /// Send a response to the client, e.g. for a request that was dropped.
+  pub fn respond(&mut self, response: Response) -> Result<(), ServerError> {
+    self.send_sendable(Sendable::Response(response))
+  }
+
+  /// Send an event to the client.
+  pub fn send_event(&mut self, event: Event) -> Result<(), ServerError> {
+    self.send_sendable(Sendable::Event(event))
+  }
+
+  fn send_sendable(&mut self, message: Sendable) -> Result<(), ServerError> {
+    let message = serde_json::to_value(BaseMessage { seq: 0, message })
+      .map_err(ServerError::SerializationError)?;
+    self.send(Direction::ToClient, message);
+    Ok(())
+  }
+}
+
+/// A step in the proxy's message pipeline.
+///
+/// Messages are seen with the numbering of the side that sent them. Returning `None` drops the
+/// message. Middleware is called in the order it was added, for both directions.
+pub trait Middleware: Send {
+  fn handle(&mut self, direction: Direction, message: Value, outbox: &mut Outbox) -> Option<Value>;
+}
+
+impl<F> Middleware for F
+where
+  F: FnMut(Direction, Value, &mut Outbox) -> Option<Value> + Send,
+{
+  fn handle(&mut self, direction: Direction, message: Value, outbox: &mut Outbox) -> Option<Value> {
+    self(direction, message, outbox)
+  }
+}
+
+/// Interpret a message sent by the client as a [`Request`]. Returns `None` for other messages.
+pub fn as_request(message: &Value) -> Option<Request> {
+  if message.get("type").and_then(Value::as_str) != Some("request") {
+    return None;
+  }
+  serde_json::from_value(message.clone()).ok()
+}
+
+/// The adapter the proxy forwards to.
+pub enum Upstream {
+  /// Spawn the adapter and talk to it through its standard input and output.
+  Spawn(Command),
+  /// Connect to a running adapter.
+  Connect(Transport),
+}
+
+/// An open connection to the upstream adapter.
+pub struct UpstreamConnection {
+  pub reader: BoxedReader,
+  pub writer: BoxedWriter,
+  /// The adapter process, if it was spawned by the proxy.
+  pub child: Option<Child>,
+}
+
+impl Upstream {
+  pub fn open(self) -> Result<UpstreamConnection, ServerError> {
+    match self {
+      Upstream::Spawn(mut command) => {
+        let mut child = command
+          .stdin(Stdio::piped())
+          .stdout(Stdio::piped())
+          .spawn()
+          .map_err(ServerError::IoError)?;
+        let (stdin, stdout) = match (child.stdin.take(), child.stdout.take()) {
+          (Some(stdin), Some(stdout)) => (stdin, stdout),
+          _ => {
+            return Err(ServerError::IoError(std::io::Error::new(
+              std::io::ErrorKind::BrokenPipe,
+              "could not open the adapter's standard streams",
+            )))
+          }
+        };
+        Ok(UpstreamConnection {
+          reader: Box::new(stdout),
+          writer: Box::new(stdin),
+          child: Some(child),
+        })
+      }
+      Upstream::Connect(transport) => {
+        let (reader, writer) = transport.connect()?;
+        Ok(UpstreamConnection {
+          reader,
+          writer,
+          child: None,
+        })
+      }
+    }
+  }
+}
+
+/// Relays messages between a client and an adapter.
+#[derive(Default)]
+pub struct Proxy {
+  middleware: Vec<Box<dyn Middleware>>,
+}
+
+impl Proxy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Append a middleware to the chain.
+  pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+    self.middleware.push(Box::new(middleware));
+    self
+  }
+
+  /// Relay messages until either side closes its connection.
+  ///
+  /// If the client closes first, the connection to the adapter is dropped, which closes the
+  /// standard input of a spawned adapter. The thread reading from the side that is still open
+  /// is not waited for.
+  pub fn run<R, W>(
+    self,
+    client_input: R,
+    client_output: W,
+    upstream: UpstreamConnection,
+  ) -> Result<(), ServerError>
+  where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+  {
+    let relay = Arc::new(Mutex::new(Relay {
+      middleware: self.middleware,
+      client: Side::new(Box::new(client_output)),
+      adapter: Some(Side::new(upstream.writer)),
+    }));
+
+    let (done, finished) = channel();
+    let spawn_reader = |input: BoxedReader, direction: Direction| {
+      let relay = relay.clone();
+      let done = done.clone();
+      thread::spawn(move || {
+        let result = relay_messages(BufReader::new(input), direction, &relay);
+        if direction == Direction::ToAdapter {
+          // Let the adapter know that the client is gone.
+          if let Ok(mut relay) = relay.lock() {
+            relay.adapter = None;
+            relay.client.forwarded_requests.clear();
+          }
+        }
+        let _ = done.send(result);
+      });
+    };
+    spawn_reader(Box::new(client_input), Direction::ToAdapter);
+    spawn_reader(upstream.reader, Direction::ToClient);
+
+    let result = finished.recv().unwrap_or(Ok(()));
+    if let Some(mut child) = upstream.child {
+      if result.is_err() {
+        let _ = child.kill();
+      }
+      let _ = child.wait();
+    }
+    result
+  }
+}
+
+fn relay_messages<R: BufRead>(
+  mut input: R,
+  direction: Direction,
+  relay: &Mutex<Relay>,
+) -> Result<(), ServerError> {
+  while let Some(message) = read_message(&mut input, DEFAULT_MAX_CONTENT_LENGTH)? {
+    relay
+      .lock()
+      .map_err(|_| ServerError::OutputLockError)?
+      .process(direction, message)?;
+  }
+  Ok(())
+}
+
+/// Requests forwarded to one side that have not been answered yet, indexed in both directions so
+/// that `cancel` requests can be translated without a scan.
+#[derive(Default)]
+struct ForwardedRequests {
+  /// The `seq` of each request on this side, mapped to the `seq` it had when the other side sent
+  /// it.
+  original_seqs: HashMap<i64, i64>,
+  /// The inverse of `original_seqs`.
+  seqs: HashMap<i64, i64>,
+}
+
+impl ForwardedRequests {
+  fn insert(&mut self, seq: i64, original_seq: i64) {
+    self.original_seqs.insert(seq, original_seq);
+    self.seqs.insert(original_seq, seq);
+  }
+
+  /// Forget a request once it is answered and return the `seq` it had on the other side.
+  fn remove(&mut self, seq: i64) -> Option<i64> {
+    let original_seq = self.original_seqs.remove(&seq)?;
+    self.seqs.remove(&original_seq);
+    Some(original_seq)
+  }
+
+  /// The `seq` on this side of the request the other side sent as `original_seq`.
+  fn seq(&self, original_seq: i64) -> Option<i64> {
+    self.seqs.get(&original_seq).copied()
+  }
+
+  fn clear(&mut self) {
+    self.original_seqs.clear();
+    self.seqs.clear();
+  }
+}
+
+/// One side of the proxy, as seen from the proxy.
+struct Side {
+  output: BoxedWriter,
+  sequence_number: i64,
+  /// Requests sent to this side.
+  forwarded_requests: ForwardedRequests,
+}
+
+impl Side {
+  fn new(output: BoxedWriter) -> Self {
+    Self {
+      output,
+      sequence_number: 0,
+      forwarded_requests: ForwardedRequests::default(),
+    }
+  }
+
+  fn send(&mut self, mut message: Value) -> Result<i64, ServerError> {
+    self.sequence_number += 1;
+    message["seq"] = Value::from(self.sequence_number);
+    write_message(&mut self.output, &message)?;
+    Ok(self.sequence_number)
+  }
+}
+
+struct Relay {
+  middleware: Vec<Box<dyn Middleware>>,
+  client: Side,
+  /// `None` once the client has disconnected.
+  adapter: Option<Side>,
+}
+
+impl Relay {
+  fn process(&mut self, direction: Direction, message: Value) -> Result<(), ServerError> {
+    let mut outbox = Outbox::default();
+    let mut current = Some(message);
+    for middleware in self.middleware.iter_mut() {
+      current = match current {
+        Some(message) => middleware.handle(direction, message, &mut outbox),
+        None => break,
+      };
+    }
+
+    if let Some(message) = current {
+      self.forward(direction, message)?;
+    }
+    for (direction, message) in outbox.messages {
+      if let Some(side) = self.side(direction) {
+        side.send(message)?;
+      }
+    }
+    Ok(())
+  }
+
+  fn side(&mut self, direction: Direction) -> Option<&mut Side> {
+    match direction {
+      Direction::ToAdapter => self.adapter.as_mut(),
+      Direction::ToClient => Some(&mut self.client),
+    }
+  }
+
+  /// Forward a message that was sent by the other side, translating its numbering.
+  fn forward(&mut self, direction: Direction, mut message: Value) -> Result<(), ServerError> {
+    let (source, destination) = match direction {
+      Direction::ToAdapter => match self.adapter.as_mut() {
+        Some(adapter) => (&mut self.client, adapter),
+        None => return Ok(()),
+      },
+      Direction::ToClient => match self.adapter.as_mut() {
+        Some(adapter) => (adapter, &mut self.client),
+        None => return Ok(()),
+      },
+    };
+
+    match message.get("type").and_then(Value::as_str) {
+      Some("request") => {
+        let original_seq = message["seq"].as_i64().unwrap_or_default();
+        if message["command"] == "cancel" {
+          // `requestId` refers to a request that went the same way.
+          if let Some(request_id) = message["arguments"]["requestId"].as_i64() {
+            if let Some(seq) = destination.forwarded_requests.seq(request_id) {
+              message["arguments"]["requestId"] = Value::from(seq);
+            }
+          }
+        }
+        let seq = destination.send(message)?;
+        destination.forwarded_requests.insert(seq, original_seq);
+      }
+      Some("response") => {
+        let request_seq = message["request_seq"].as_i64().unwrap_or_default();
+        // A response to a request that did not come from the destination, i.e. one that was
+        // synthesized by middleware, is not forwarded.
+        if let Some(original_seq) = source.forwarded_requests.remove(request_seq) {
+          let disconnected = message["command"] == "disconnect";
+          message["request_seq"] = Value::from(original_seq);
+          destination.send(message)?;
+          if disconnected {
+            // The session is over, the requests that are still pending won't be answered.
+            source.forwarded_requests.clear();
+            destination.forwarded_requests.clear();
+          }
+        }
+      }
+      _ => {
+        destination.send(message)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::BufWriter;
+  use std::net::{TcpListener, TcpStream};
+  use std::sync::mpsc::Sender;
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::{requests::Command, server::Server, types::Capabilities};
+
+  /// A pair of connected streams.
+  fn stream_pair() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let a = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (b, _) = listener.accept().unwrap();
+    (a, b)
+  }
+
+  /// Start an adapter and a proxy in front of it. Returns the client's connection to the proxy.
+  fn start(proxy: Proxy, received: Sender<(i64, String)>) -> (BufReader<TcpStream>, TcpStream) {
+    let (proxy_upstream, adapter_stream) = stream_pair();
+    thread::spawn(move || {
+      let mut server = Server::new(
+        BufReader::new(adapter_stream.try_clone().unwrap()),
+        BufWriter::new(adapter_stream),
+      );
+      while let Ok(Some(req)) = server.poll_request() {
+        received
+          .send((req.seq, format!("{:?}", req.command)))
+          .unwrap();
+        let response = match &req.command {
+          Command::Initialize(_) => req.success(crate::responses::ResponseBody::Initialize(
+            Capabilities::default(),
+          )),
+          _ => req.ack().unwrap(),
+        };
+        server.respond(response).unwrap();
+      }
+    });
+
+    let (client, proxy_downstream) = stream_pair();
+    thread::spawn(move || {
+      let upstream = UpstreamConnection {
+        reader: Box::new(proxy_upstream.try_clone().unwrap()),
+        writer: Box::new(proxy_upstream),
+        child: None,
+      };
+      let input = proxy_downstream.try_clone().unwrap();
+      proxy.run(input, proxy_downstream, upstream).unwrap();
+    });
+
+    (BufReader::new(client.try_clone().unwrap()), client)
+  }
+
+  fn read<R: BufRead>(input: &mut R) -> Value {
+    read_message(input, DEFAULT_MAX_CONTENT_LENGTH)
+      .unwrap()
+      .unwrap()
+  }
+
+  fn request(seq: i64, command: &str) -> Value {
+    let mut request = json!({"seq": seq, "type": "request", "command": command});
+    if command == "initialize" {
+      request["arguments"] = json!({"adapterID": "test"});
+    }
+    request
+  }
+
+  #[test]
+  fn test_renumbering() {
+    let proxy = Proxy::new().with_middleware(|direction, message: Value, outbox: &mut Outbox| {
+      if direction == Direction::ToClient && message["command"] == "initialize" {
+        outbox.send_event(Event::Initialized).unwrap();
+      }
+      Some(message)
+    });
+    let (received, adapter_requests) = channel();
+    let (mut input, mut output) = start(proxy, received);
+
+    write_message(&mut output, &request(10, "initialize")).unwrap();
+    write_message(&mut output, &request(20, "configurationDone")).unwrap();
+
+    let response = read(&mut input);
+    assert_eq!(response["seq"], 1);
+    assert_eq!(response["request_seq"], 10);
+    let event = read(&mut input);
+    assert_eq!(event["seq"], 2);
+    assert_eq!(event["event"], "initialized");
+    let response = read(&mut input);
+    assert_eq!(response["seq"], 3);
+    assert_eq!(response["request_seq"], 20);
+
+    let seqs: Vec<i64> = adapter_requests
+      .iter()
+      .take(2)
+      .map(|(seq, _)| seq)
+      .collect();
+    assert_eq!(seqs, vec![1, 2]);
+  }
+
+  #[test]
+  fn test_blocking_middleware() {
+    let proxy = Proxy::new().with_middleware(|_, message: Value, outbox: &mut Outbox| {
+      match as_request(&message) {
+        Some(req) if matches!(req.command, Command::ConfigurationDone) => {
+          outbox.respond(req.error("Blocked by proxy")).unwrap();
+          None
+        }
+        _ => Some(message),
+      }
+    });
+    let (received, adapter_requests) = channel();
+    let (mut input, mut output) = start(proxy, received);
+
+    write_message(&mut output, &request(1, "configurationDone")).unwrap();
+    write_message(&mut output, &request(2, "initialize")).unwrap();
+
+    let response = read(&mut input);
+    assert_eq!(response["request_seq"], 1);
+    assert_eq!(response["success"], false);
+    assert_eq!(response["message"], "Blocked by proxy");
+    let response = read(&mut input);
+    assert_eq!(response["request_seq"], 2);
+    assert_eq!(response["success"], true);
+
+    // Only the initialize request reached the adapter.
+    let (seq, command) = adapter_requests.recv().unwrap();
+    assert_eq!(seq, 1);
+    assert!(command.starts_with("Initialize"));
+  }
+
+  #[test]
+  fn test_forwarded_requests() {
+    let mut relay = Relay {
+      middleware: Vec::new(),
+      client: Side::new(Box::new(Vec::new())),
+      adapter: Some(Side::new(Box::new(Vec::new()))),
+    };
+    let forwarded = |relay: &Relay| {
+      let requests = &relay.adapter.as_ref().unwrap().forwarded_requests;
+      (requests.original_seqs.len(), requests.seqs.len())
+    };
+
+    relay
+      .process(Direction::ToAdapter, request(7, "threads"))
+      .unwrap();
+    relay
+      .process(Direction::ToAdapter, request(8, "evaluate"))
+      .unwrap();
+    let mut cancel = request(9, "cancel");
+    cancel["arguments"] = json!({"requestId": 8});
+    relay.process(Direction::ToAdapter, cancel).unwrap();
+    assert_eq!(
+      relay.adapter.as_ref().unwrap().forwarded_requests.seq(8),
+      Some(2)
+    );
+    assert_eq!(forwarded(&relay), (3, 3));
+
+    let response = |request_seq: i64, command: &str| json!({"seq": 1, "type": "response", "request_seq": request_seq, "success": true, "command": command});
+    relay
+      .process(Direction::ToClient, response(3, "cancel"))
+      .unwrap();
+    assert_eq!(forwarded(&relay), (2, 2));
+
+    // Nobody answers the other requests before the session ends.
+    relay
+      .process(Direction::ToAdapter, request(10, "disconnect"))
+      .unwrap();
+    relay
+      .process(Direction::ToClient, response(4, "disconnect"))
+      .unwrap();
+    assert_eq!(forwarded(&relay), (0, 0));
+  }
+}
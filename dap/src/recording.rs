@@ -0,0 +1,404 @@
+//! Recording of DAP sessions and deterministic replay.
+//!
+//! A [`Recorder`] attached to a [`Server`] writes every incoming and outgoing message as a line
+//! of JSON. A [`Replay`] feeds the client side of such a recording into an adapter and compares
+//! what the adapter sends with what was recorded.
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+  errors::{DeserializationError, ServerError},
+  framing::read_message,
+  server::Server,
+};
+
+/// Whether a message was sent by the client or by the adapter.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+  /// Sent by the client to the adapter.
+  Incoming,
+  /// Sent by the adapter to the client.
+  Outgoing,
+}
+
+/// A single line of a recording.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedMessage {
+  /// Milliseconds since the Unix epoch.
+  pub timestamp: u64,
+  pub direction: Direction,
+  /// The message as it was sent over the wire, without the header.
+  pub message: Value,
+}
+
+/// Appends messages to a JSONL sink.
+pub struct Recorder {
+  sink: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Recorder {
+  /// Create a recorder that writes to the given sink.
+  pub fn new<W: Write + Send + 'static>(sink: W) -> Self {
+    Self {
+      sink: Mutex::new(Box::new(sink)),
+    }
+  }
+
+  /// Create a recorder that appends to the file at `path`, creating it if necessary.
+  pub fn to_file<P: AsRef<Path>>(path: P) -> Result<Self, ServerError> {
+    let file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .map_err(ServerError::IoError)?;
+    Ok(Self::new(BufWriter::new(file)))
+  }
+
+  /// Append a message to the recording.
+  pub fn record(&self, direction: Direction, message: &Value) -> Result<(), ServerError> {
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_millis() as u64)
+      .unwrap_or_default();
+    let line = serde_json::to_string(&RecordedMessage {
+      timestamp,
+      direction,
+      message: message.clone(),
+    })
+    .map_err(ServerError::SerializationError)?;
+
+    let mut sink = self.sink.lock().map_err(|_| ServerError::OutputLockError)?;
+    writeln!(sink, "{}", line).map_err(ServerError::IoError)?;
+    sink.flush().map_err(ServerError::IoError)
+  }
+}
+
+/// Replays the client side of a recording into an adapter.
+///
+/// ```ignore
+/// let report = Replay::load("session.jsonl")?
+///   .ignore_field("seq")
+///   .run(|server| my_adapter(server))?;
+/// assert!(report.is_match(), "{}", report);
+/// ```
+pub struct Replay {
+  messages: Vec<RecordedMessage>,
+  ignored_fields: HashSet<String>,
+}
+
+impl Replay {
+  pub fn new(messages: Vec<RecordedMessage>) -> Self {
+    Self {
+      messages,
+      ignored_fields: HashSet::new(),
+    }
+  }
+
+  /// Read a recording from a JSONL stream. Empty lines are skipped.
+  pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, ServerError> {
+    let mut messages = Vec::new();
+    for line in reader.lines() {
+      let line = line.map_err(ServerError::IoError)?;
+      if line.trim().is_empty() {
+        continue;
+      }
+      let message = serde_json::from_str(&line)
+        .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e)))?;
+      messages.push(message);
+    }
+    Ok(Self::new(messages))
+  }
+
+  /// Read a recording from the file at `path`.
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ServerError> {
+    let file = File::open(path).map_err(ServerError::IoError)?;
+    Self::from_reader(BufReader::new(file))
+  }
+
+  /// Ignore the given field when comparing messages. The field is ignored at any depth.
+  pub fn ignore_field(mut self, field: &str) -> Self {
+    self.ignored_fields.insert(field.to_string());
+    self
+  }
+
+  /// Run the adapter on a [`Server`] that reads the recorded incoming messages, then compare
+  /// its output with the recorded outgoing messages.
+  ///
+  /// All incoming messages are available from the start, so the adapter sees the same input
+  /// regardless of timing. The adapter should return once `poll_request` returns `None`.
+  pub fn run<F>(&self, adapter: F) -> Result<ReplayReport, ServerError>
+  where
+    F: FnOnce(Server<Cursor<Vec<u8>>, ReplayOutput>),
+  {
+    let mut input = Vec::new();
+    for recorded in self.recorded(Direction::Incoming) {
+      let content = serde_json::to_string(&recorded).map_err(ServerError::SerializationError)?;
+      write!(
+        input,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+      )
+      .map_err(ServerError::IoError)?;
+    }
+    let output = ReplayOutput::default();
+    adapter(Server::new(
+      BufReader::new(Cursor::new(input)),
+      BufWriter::new(output.clone()),
+    ));
+
+    let written = output
+      .buffer
+      .lock()
+      .map_err(|_| ServerError::OutputLockError)?
+      .clone();
+    let expected = self.recorded(Direction::Outgoing);
+    let actual = read_frames(&written)?;
+
+    let mismatches = (0..expected.len().max(actual.len()))
+      .filter_map(|index| {
+        let expected = expected.get(index).map(|v| self.strip(v));
+        let actual = actual.get(index).map(|v| self.strip(v));
+        if expected == actual {
+          None
+        } else {
+          Some(Mismatch {
+            index,
+            expected,
+            actual,
+          })
+        }
+      })
+      .collect();
+    Ok(ReplayReport { mismatches })
+  }
+
+  fn recorded(&self, direction: Direction) -> Vec<&Value> {
+    self
+      .messages
+      .iter()
+      .filter(|m| m.direction == direction)
+      .map(|m| &m.message)
+      .collect()
+  }
+
+  fn strip(&self, value: &Value) -> Value {
+    match value {
+      Value::Object(map) => Value::Object(
+        map
+          .iter()
+          .filter(|(key, _)| !self.ignored_fields.contains(key.as_str()))
+          .map(|(key, value)| (key.clone(), self.strip(value)))
+          .collect(),
+      ),
+      Value::Array(values) => Value::Array(values.iter().map(|v| self.strip(v)).collect()),
+      other => other.clone(),
+    }
+  }
+}
+
+/// Collects the output of an adapter during a replay.
+#[derive(Clone, Default)]
+pub struct ReplayOutput {
+  buffer: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Write for ReplayOutput {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self
+      .buffer
+      .lock()
+      .map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::Other, "replay output lock is poisoned")
+      })?
+      .extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+/// An outgoing message that differs between the recording and the replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+  /// Position among the outgoing messages.
+  pub index: usize,
+  /// The recorded message, `None` if the adapter sent more messages than recorded.
+  pub expected: Option<Value>,
+  /// The replayed message, `None` if the adapter sent fewer messages than recorded.
+  pub actual: Option<Value>,
+}
+
+impl Display for Mismatch {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let show = |value: &Option<Value>| match value {
+      Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+      None => "<missing>".to_string(),
+    };
+    writeln!(f, "outgoing message #{}:", self.index)?;
+    writeln!(f, "  expected: {}", show(&self.expected))?;
+    write!(f, "  actual:   {}", show(&self.actual))
+  }
+}
+
+/// The result of a replay.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayReport {
+  pub mismatches: Vec<Mismatch>,
+}
+
+impl ReplayReport {
+  /// `true` if the adapter sent exactly the recorded messages.
+  pub fn is_match(&self) -> bool {
+    self.mismatches.is_empty()
+  }
+}
+
+impl Display for ReplayReport {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.is_match() {
+      return write!(f, "replay matches the recording");
+    }
+    writeln!(f, "{} mismatched message(s)", self.mismatches.len())?;
+    for mismatch in &self.mismatches {
+      writeln!(f, "{}", mismatch)?;
+    }
+    Ok(())
+  }
+}
+
+/// Split a `Content-Length` framed byte stream into messages.
+fn read_frames(mut bytes: &[u8]) -> Result<Vec<Value>, ServerError> {
+  let max_content_length = bytes.len();
+  let mut messages = Vec::new();
+  while let Some(message) = read_message(&mut bytes, max_content_length)? {
+    messages.push(message);
+  }
+  Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{events::Event, requests::Command, responses::ResponseBody, types::Capabilities};
+
+  const INPUT: &str = "Content-Length: 90\r\n\r\n{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\"}}Content-Length: 61\r\n\r\n{\"seq\": 2, \"type\": \"request\", \"command\": \"configurationDone\"}";
+
+  fn adapter<R: std::io::Read, W: Write>(mut server: Server<R, W>, supports_goto: bool) {
+    while let Some(req) = server.poll_request().unwrap() {
+      match req.command {
+        Command::Initialize(_) => {
+          let capabilities = Capabilities {
+            supports_goto_targets_request: Some(supports_goto),
+            ..Default::default()
+          };
+          server
+            .respond(req.success(ResponseBody::Initialize(capabilities)))
+            .unwrap();
+          server.send_event(Event::Initialized).unwrap();
+        }
+        _ => server.respond(req.ack().unwrap()).unwrap(),
+      }
+    }
+  }
+
+  fn record_session() -> Vec<u8> {
+    let recording = ReplayOutput::default();
+    let mut server = Server::new(
+      BufReader::new(INPUT.as_bytes()),
+      BufWriter::new(std::io::sink()),
+    );
+    server
+      .set_recorder(Recorder::new(recording.clone()))
+      .unwrap();
+    adapter(server, true);
+    let bytes = recording.buffer.lock().unwrap().clone();
+    bytes
+  }
+
+  #[test]
+  fn test_record() {
+    let replay = Replay::from_reader(Cursor::new(record_session())).unwrap();
+    let directions: Vec<Direction> = replay.messages.iter().map(|m| m.direction).collect();
+    assert_eq!(
+      directions,
+      vec![
+        Direction::Incoming,
+        Direction::Outgoing,
+        Direction::Outgoing,
+        Direction::Incoming,
+        Direction::Outgoing,
+      ]
+    );
+    assert_eq!(replay.messages[0].message["command"], "initialize");
+    assert_eq!(replay.messages[2].message["event"], "initialized");
+    assert_eq!(replay.messages[4].message["request_seq"], 2);
+  }
+
+  #[test]
+  fn test_replay() {
+    let replay = Replay::from_reader(Cursor::new(record_session())).unwrap();
+    let report = replay.run(|server| adapter(server, true)).unwrap();
+    assert!(report.is_match(), "{}", report);
+
+    let report = replay.run(|server| adapter(server, false)).unwrap();
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].index, 0);
+
+    // An adapter that stops early is missing the remaining messages.
+    let report = replay
+      .run(|mut server| {
+        server.poll_request().unwrap();
+      })
+      .unwrap();
+    assert_eq!(report.mismatches.len(), 3);
+    assert!(report.mismatches.iter().all(|m| m.actual.is_none()));
+  }
+
+  #[test]
+  fn test_replay_ignored_fields() {
+    let mut messages = Replay::from_reader(Cursor::new(record_session()))
+      .unwrap()
+      .messages;
+    for message in messages.iter_mut() {
+      if message.direction == Direction::Outgoing {
+        message.message["seq"] = Value::from(100);
+      }
+    }
+
+    let report = Replay::new(messages.clone())
+      .run(|server| adapter(server, true))
+      .unwrap();
+    assert_eq!(report.mismatches.len(), 3);
+
+    let report = Replay::new(messages)
+      .ignore_field("seq")
+      .run(|server| adapter(server, true))
+      .unwrap();
+    assert!(report.is_match(), "{}", report);
+  }
+
+  #[test]
+  fn test_read_frames_rejects_bogus_lengths() {
+    assert_eq!(read_frames(INPUT.as_bytes()).unwrap().len(), 2);
+    for input in [
+      "Content-Length: 18446744073709551615\r\n\r\n{}",
+      "Content-Length: 3\r\n\r\n{}",
+      "Content-Length: 2\r\n{}",
+    ] {
+      assert!(read_frames(input.as_bytes()).is_err(), "{}", input);
+    }
+  }
+}
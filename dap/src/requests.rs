@@ -1,9 +1,10 @@
-use serde::Deserialize;
+#[cfg(feature = "integration_testing")]
+use fake::{Dummy, Fake, Faker};
+#[cfg(feature = "integration_testing")]
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
-#[cfg(feature = "client")]
-use serde::Serialize;
-
 use crate::{
   errors::ServerError,
   prelude::{Response, ResponseBody},
@@ -15,95 +16,140 @@ use crate::{
   },
 };
 
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Clone)]
+#[cfg(feature = "integration_testing")]
+use crate::types::{ObjectFaker, ValueFaker};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum PathFormat {
   Path,
   Uri,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
   Other(String),
 }
 
 //// Arguments for an Initialize request.
 /// In specification: [Initialize](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Initialize)
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct InitializeArguments {
   /// The ID of the client using this adapter.
-  #[serde(rename = "clientID")]
+  #[serde(rename = "clientID", skip_serializing_if = "Option::is_none")]
   pub client_id: Option<String>,
   /// The human-readable name of the client using this adapter.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub client_name: Option<String>,
   /// The ID of the debug adapter.
   #[serde(rename = "adapterID")]
   pub adapter_id: String,
   /// The ISO-639 locale of the client using this adapter, e.g. en-US or de-CH.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub locale: Option<String>,
   /// If true all line i64s are 1-based (default).
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub lines_start_at1: Option<bool>,
   /// If true all column i64s are 1-based (default).
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub columns_start_at1: Option<bool>,
   /// Determines in what format paths are specified. The default is `path`, which
   /// is the native format.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub path_format: Option<PathFormat>,
   /// Client supports the `type` attribute for variables.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_variable_type: Option<bool>,
   /// Client supports the paging of variables.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_variable_paging: Option<bool>,
   /// Client supports the `runInTerminal` request.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_run_in_terminal_request: Option<bool>,
   /// Client supports memory references.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_memory_references: Option<bool>,
   /// Client supports progress reporting.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_progress_reporting: Option<bool>,
   /// Client supports the `invalidated` event.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_invalidated_event: Option<bool>,
   /// Client supports the `memory` event.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_memory_event: Option<bool>,
   /// Client supports the `argsCanBeInterpretedByShell` attribute on the `runInTerminal` request.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_args_can_be_interpreted_by_shell: Option<bool>,
   /// Client supports the `startDebugging` request.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_start_debugging_request: Option<bool>,
+  /// The client will interpret ANSI escape sequences in the display of
+  /// `OutputEvent.output` and `Variable.value` fields when
+  /// `Capabilities.supportsANSIStyling` is also enabled.
+  #[serde(
+    rename = "supportsANSIStyling",
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub supports_ansi_styling: Option<bool>,
 }
 
 //// Arguments for an SetBreakpoints request.
 /// In specification: [SetBreakpoints](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Initialize)
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SetBreakpointsArguments {
   /// The source location of the breakpoints, either `source.path` or
   /// `source.sourceReference` must be specified.
   pub source: Source,
   /// The code locations of the breakpoints.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub breakpoints: Option<Vec<SourceBreakpoint>>,
   /// Deprecated: The code locations of the breakpoints.
   #[deprecated]
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub lines: Option<Vec<i64>>,
   /// A value of true indicates that the underlying source has been modified
   /// which results in new breakpoint locations.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub source_modified: Option<bool>,
 }
 
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+// Derived by hand because the derive would read the deprecated `lines` field.
+#[cfg(feature = "integration_testing")]
+#[allow(deprecated)]
+impl Dummy<Faker> for SetBreakpointsArguments {
+  fn dummy_with_rng<R: Rng + ?Sized>(_: &Faker, rng: &mut R) -> Self {
+    Self {
+      source: Faker.fake_with_rng(rng),
+      breakpoints: Faker.fake_with_rng(rng),
+      lines: Faker.fake_with_rng(rng),
+      source_modified: Faker.fake_with_rng(rng),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct CancelArguments {
   /// The ID (attribute `seq`) of the request to cancel. If missing no request is
   /// cancelled.
   /// Both a `requestId` and a `progressId` can be specified in one request.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub request_id: Option<i64>,
   /// The ID (attribute `progressId`) of the progress to cancel. If missing no
   /// progress is cancelled.
   /// Both a `requestId` and a `progressId` can be specified in one request.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub progress_id: Option<String>,
 }
 
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetExceptionBreakpointsArguments {
   /// Set of exception filters specified by their ID. The set of all possible
   /// exception filters is defined by the `exceptionBreakpointFilters`
@@ -114,28 +160,31 @@ pub struct SetExceptionBreakpointsArguments {
   /// capability. This attribute is only honored by a debug adapter if the
   /// corresponding capability `supportsExceptionFilterOptions` is true. The
   /// `filter` and `filterOptions` sets are additive.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub filter_options: Option<Vec<ExceptionFilterOptions>>,
   /// Configuration options for selected exceptions.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportsExceptionOptions` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub exception_options: Option<Vec<ExceptionOptions>>,
 }
 
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetFunctionBreakpointsArguments {
   /// The function names of the breakpoints.
   pub breakpoints: Vec<FunctionBreakpoint>,
 }
 
 //// Arguments for a Launch request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct LaunchRequestArguments {
   /// If true, the launch request should launch the program without enabling
   /// debugging.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub no_debug: Option<bool>,
   /// Arbitrary data from the previous, restarted session.
   /// The data is sent as the `restart` attribute of the `terminated` event.
@@ -143,54 +192,70 @@ pub struct LaunchRequestArguments {
   ///
   /// Rust-specific: this data must be a string. Server requiring storing binary data should use
   /// an encoding that is suitable for string (e.g. base85 or similar).
-  #[serde(rename = "__restart")]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ValueFaker"))]
+  #[serde(rename = "__restart", skip_serializing_if = "Option::is_none")]
   pub restart_data: Option<Value>,
   /// The request may include additional implementation specific attributes.
-  #[serde(flatten)]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ObjectFaker"))]
+  #[serde(flatten, deserialize_with = "deserialize_additional_data")]
   pub additional_data: Option<Value>,
 }
 
 //// Arguments for an Attach request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct AttachRequestArguments {
   /// Arbitrary data from the previous, restarted session.
   /// The data is sent as the `restart` attribute of the `terminated` event.
   /// The client should leave the data intact.
-  #[serde(rename = "__restart")]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ValueFaker"))]
+  #[serde(rename = "__restart", skip_serializing_if = "Option::is_none")]
   pub restart_data: Option<Value>,
 
   /// The request may include additional implementation specific attributes.
-  #[serde(flatten)]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ObjectFaker"))]
+  #[serde(flatten, deserialize_with = "deserialize_additional_data")]
   pub additional_data: Option<Value>,
 }
 
 //// Union of Attach and Launch arguments for the Restart request.
 //// Currently the same as LaunchRequestArguments but might not be in the future.
-#[derive(Deserialize, Debug, Default, Clone)]
-#[cfg_attr(feature = "client", derive(Serialize))]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct AttachOrLaunchArguments {
   /// If true, the launch request should launch the program without enabling
   /// debugging.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub no_debug: Option<bool>,
 
   /// Arbitrary data from the previous, restarted session.
   /// The data is sent as the `restart` attribute of the `terminated` event.
   /// The client should leave the data intact.
-  #[serde(rename = "__restart")]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ValueFaker"))]
+  #[serde(rename = "__restart", skip_serializing_if = "Option::is_none")]
   pub restart_data: Option<Value>,
 
   /// The request may include additional implementation specific attributes.
-  #[serde(flatten)]
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ObjectFaker"))]
+  #[serde(flatten, deserialize_with = "deserialize_additional_data")]
   pub additional_data: Option<Value>,
 }
 
+/// Deserializes the implementation specific attributes of a request. If there are none, they
+/// are `None` rather than an empty object, which would serialize to nothing as well.
+fn deserialize_additional_data<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<Value>, D::Error> {
+  let data = Option::<Value>::deserialize(deserializer)?;
+  Ok(data.filter(|data| !matches!(data, Value::Object(map) if map.is_empty())))
+}
+
 //// Arguments for a BreakpointLocations request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct BreakpointLocationsArguments {
   /// The source location of the breakpoints, either `source.path` or
   /// `source.reference` must be specified.
@@ -202,24 +267,28 @@ pub struct BreakpointLocationsArguments {
   /// is measured in UTF-16 code units and the client capability
   /// `columnsStartAt1` determines whether it is 0- or 1-based. If no column is
   /// given, the first position in the start line is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub column: Option<i64>,
   /// End line of range to search possible breakpoint locations in. If no end
   /// line is given, then the end line is assumed to be the start line.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub end_line: Option<i64>,
   /// End position within `endLine` to search possible breakpoint locations in.
   /// It is measured in UTF-16 code units and the client capability
   /// `columnsStartAt1` determines whether it is 0- or 1-based. If no end column
   /// is given, the last position in the end line is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub end_column: Option<i64>,
 }
 
 //// Arguments for a Completions request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct CompletionsArguments {
   /// Returns completions in the scope of this stack frame. If not specified, the
   /// completions are returned for the global scope.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub frame_id: Option<i64>,
   /// One or more source lines. Typically this is the text users have typed into
   /// the debug console before they asked for completion.
@@ -230,13 +299,14 @@ pub struct CompletionsArguments {
   pub column: i64,
   /// A line for which to determine the completion proposals. If missing the
   /// first line of the text is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub line: Option<i64>,
 }
 
 //// Arguments for a Continue request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ContinueArguments {
   /// Specifies the active thread. If the debug adapter supports single thread
   /// execution (see `supportsSingleThreadExecutionRequests`) and the argument
@@ -244,19 +314,21 @@ pub struct ContinueArguments {
   pub thread_id: i64,
   /// If this flag is true, execution is resumed only for the thread with given
   /// `threadId`.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
 }
 
 //// Arguments for a DataBreakpointInfo request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DataBreakpointInfoArguments {
   /// Reference to the variable container if the data breakpoint is requested for
   /// a child of the container. The `variablesReference` must have been obtained
   /// in the current suspended state.
   /// See [Lifetime of Object References](https://microsoft.github.io/debug-adapter-protocol/overview#lifetime-of-objects-references)
   /// in the Overview section of the specification for details.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub variables_reference: Option<i64>,
   /// The name of the variable's child to obtain data breakpoint information for.
   /// If `variablesReference` isn't specified, this can be an expression.
@@ -264,22 +336,45 @@ pub struct DataBreakpointInfoArguments {
   /// When `name` is an expression, evaluate it in the scope of this stack frame.
   /// If not specified, the expression is evaluated in the global scope. When
   /// `variablesReference` is specified, this property has no effect.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub frame_id: Option<i64>,
+  /// If specified, a debug adapter should return information for the range of
+  /// memory extending `bytes` number of bytes from the address or variable
+  /// specified by `name`. Breakpoints set using the resulting data ID should
+  /// pause on data access anywhere within that range.
+  ///
+  /// Clients may set this property only if the `supportsDataBreakpointBytes`
+  /// capability is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bytes: Option<i64>,
+  /// If `true`, the `name` is a memory address and the debugger should
+  /// interpret it as a decimal value, or hex value if it is prefixed with `0x`.
+  ///
+  /// Clients may set this property only if the `supportsDataBreakpointBytes`
+  /// capability is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub as_address: Option<bool>,
+  /// The mode of the desired breakpoint. If defined, this must be one of the
+  /// `breakpointModes` the debug adapter advertised in its `Capabilities`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
 }
 
 //// Arguments for a Disassemble request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DisassembleArguments {
   /// Memory reference to the base location containing the instructions to
   /// disassemble.
   pub memory_reference: String,
   /// Offset (in bytes) to be applied to the reference location before
   /// disassembling. Can be negative.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub offset: Option<i64>,
   /// Offset (in instructions) to be applied after the byte offset (if any)
   /// before disassembling. Can be negative.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub instruction_offset: Option<i64>,
   /// Number of instructions to disassemble starting at the specified location
   /// and offset.
@@ -289,40 +384,45 @@ pub struct DisassembleArguments {
   pub instruction_count: i64,
   /// If true, the adapter should attempt to resolve memory addresses and other
   /// values to symbolic names.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub resolve_symbols: Option<bool>,
 }
 
 //// Arguments for a Disconnect request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DisconnectArguments {
   /// A value of true indicates that this `disconnect` request is part of a
   /// restart sequence.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub restart: Option<bool>,
   /// Indicates whether the debuggee should be terminated when the debugger is
   /// disconnected.
   /// If unspecified, the debug adapter is free to do whatever it thinks is best.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportTerminateDebuggee` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub terminate_debuggee: Option<bool>,
   /// Indicates whether the debuggee should stay suspended when the debugger is
   /// disconnected.
   /// If unspecified, the debuggee should resume execution.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportSuspendDebuggee` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub suspend_debuggee: Option<bool>,
 }
 
 //// Arguments for a Evaluate request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct EvaluateArguments {
   /// The expression to evaluate.
   pub expression: String,
   /// Evaluate the expression in the scope of this stack frame. If not specified,
   /// the expression is evaluated in the global scope.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub frame_id: Option<i64>,
   /// The context in which the evaluate request is used.
   /// Values:
@@ -336,26 +436,28 @@ pub struct EvaluateArguments {
   /// This value should only be used if the corresponding capability
   /// `supportsClipboardContext` is true.
   /// etc.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub context: Option<EvaluateArgumentsContext>,
   /// Specifies details on how to format the result.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportsValueFormattingOptions` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub format: Option<ValueFormat>,
 }
 
 /// Arguments for a ExceptionInfo request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionInfoArguments {
   /// Thread for which exception information should be retrieved.
   pub thread_id: i64,
 }
 
 /// Arguments for a Goto request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct GotoArguments {
   /// Set the goto target for this thread.
   pub thread_id: i64,
@@ -364,9 +466,9 @@ pub struct GotoArguments {
 }
 
 /// Arguments for a GotoTargets request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct GotoTargetsArguments {
   /// The source location for which the goto targets are determined.
   pub source: Source,
@@ -375,80 +477,96 @@ pub struct GotoTargetsArguments {
   /// The position within `line` for which the goto targets are determined. It is
   /// measured in UTF-16 code units and the client capability `columnsStartAt1`
   /// determines whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub column: Option<i64>,
 }
 
+/// Arguments for a Locations request.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct LocationsArguments {
+  /// Location reference to resolve.
+  pub location_reference: i64,
+}
+
 /// Arguments for a Modules request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ModulesArguments {
   /// The index of the first module to return, if omitted modules start at 0.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub start_module: Option<i64>,
   /// The i64 of modules to return. If `moduleCount` is not specified or 0,
   /// all modules are returned.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub module_count: Option<i64>,
 }
 
 /// Arguments for a Next request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct NextArguments {
   /// Specifies the thread for which to resume execution for one step (of the
   /// given granularity).
   pub thread_id: i64,
   /// If this flag is true, all other suspended threads are not resumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
   /// Stepping granularity. If no granularity is specified, a granularity of
   /// `statement` is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub granularity: Option<SteppingGranularity>,
 }
 
 /// Arguments for a Pause request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct PauseArguments {
   /// Pause execution for this thread.
   pub thread_id: i64,
 }
 
 /// Arguments for a ReadMemory request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ReadMemoryArguments {
   /// Memory reference to the base location from which data should be read.
   pub memory_reference: String,
   /// Offset (in bytes) to be applied to the reference location before reading
   /// data. Can be negative.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub offset: Option<i64>,
   /// Number of bytes to read at the specified location and offset.
   pub count: i64,
 }
 
 /// Arguments for a ReadMemory request.
-#[derive(Deserialize, Debug, Clone)]
-#[cfg_attr(feature = "client", derive(Serialize))]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct RestartArguments {
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub arguments: Option<AttachOrLaunchArguments>,
 }
 
 /// Arguments for a RestartFrame request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct RestartFrameArguments {
   /// Restart this stackframe.
   pub frame_id: i64,
 }
 
 /// Arguments for a ReverseContinue request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ReverseContinueArguments {
   /// Specifies the active thread. If the debug adapter supports single thread
   /// execution (see `supportsSingleThreadExecutionRequests`) and the
@@ -456,22 +574,23 @@ pub struct ReverseContinueArguments {
   pub thread_id: i64,
   /// If this flag is true, backward execution is resumed only for the thread
   /// with given `threadId`.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
 }
 
 /// Arguments for a Scopes request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ScopesArguments {
   /// Retrieve the scopes for this stackframe.
   pub frame_id: i64,
 }
 
 /// Arguments for a SetDataBreakpoints request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetDataBreakpointsArguments {
   /// The contents of this array replaces all existing data breakpoints. An empty
   /// array clears all data breakpoints.
@@ -479,9 +598,9 @@ pub struct SetDataBreakpointsArguments {
 }
 
 /// Arguments for a SetExpression request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetExpressionArguments {
   /// The l-value expression to assign to.
   pub expression: String,
@@ -489,24 +608,26 @@ pub struct SetExpressionArguments {
   pub value: String,
   /// Evaluate the expressions in the scope of this stack frame. If not
   /// specified, the expressions are evaluated in the global scope.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub frame_id: Option<i64>,
   /// Specifies how the resulting value should be formatted.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub format: Option<ValueFormat>,
 }
 
 /// Arguments for a SetExpression request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetInstructionBreakpointsArguments {
   /// The instruction references of the breakpoints
   pub breakpoints: Vec<InstructionBreakpoint>,
 }
 
 /// Arguments for a SetVariable request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetVariableArguments {
   /// The reference of the variable container.
   /// See [Lifetime of Object References](https://microsoft.github.io/debug-adapter-protocol/overview#lifetime-of-objects-references)
@@ -517,16 +638,18 @@ pub struct SetVariableArguments {
   /// The value of the variable.
   pub value: String,
   /// Specifies details on how to format the response value.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub format: Option<ValueFormat>,
 }
 
 /// Arguments for a Source request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SourceArguments {
   /// Specifies the source content to load. Either `source.path` or
   /// `source.sourceReference` must be specified.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub source: Option<Source>,
   /// The reference to the source. This is the same as `source.sourceReference`.
   /// This is provided for backward compatibility since old clients do not
@@ -535,102 +658,114 @@ pub struct SourceArguments {
 }
 
 /// Arguments for a StackTrace request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StackTraceArguments {
   /// Retrieve the stacktrace for this thread.
   pub thread_id: i64,
   /// The index of the first frame to return, if omitted frames start at 0.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub start_frame: Option<i64>,
   /// The maximum i64 of frames to return. If levels is not specified or 0,
   /// all frames are returned.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub levels: Option<i64>,
   /// Specifies details on how to format the stack frames.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportsValueFormattingOptions` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub format: Option<StackFrameFormat>,
 }
 
 /// Arguments for a StepBack request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StepBackArguments {
   /// Specifies the thread for which to resume execution for one step backwards
   /// (of the given granularity).
   pub thread_id: i64,
   /// If this flag is true, all other suspended threads are not resumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
   /// Stepping granularity to step. If no granularity is specified, a granularity
   /// of `statement` is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub granularity: Option<SteppingGranularity>,
 }
 
 /// Arguments for a StepIn request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StepInArguments {
   /// Specifies the thread for which to resume execution for one step-into (of
   /// the given granularity).
   pub thread_id: i64,
   /// If this flag is true, all other suspended threads are not resumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
   /// Id of the target to step into.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub target_id: Option<i64>,
   /// Stepping granularity. If no granularity is specified, a granularity of
   /// `statement` is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub granularity: Option<SteppingGranularity>,
 }
 
 /// Arguments for a StepInTargets request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StepInTargetsArguments {
   /// The stack frame for which to retrieve the possible step-in targets.
   pub frame_id: i64,
 }
 
 /// Arguments for a StepOut request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StepOutArguments {
   /// Specifies the thread for which to resume execution for one step-out (of the
   /// given granularity).
   pub thread_id: i64,
   /// If this flag is true, all other suspended threads are not resumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub single_thread: Option<bool>,
   /// Stepping granularity. If no granularity is specified, a granularity of
   /// `statement` is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub granularity: Option<SteppingGranularity>,
 }
 
 /// Arguments for a Terminate request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct TerminateArguments {
   /// A value of true indicates that this `terminate` request is part of a
   /// restart sequence.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub restart: Option<bool>,
 }
 
 /// Arguments for a TerminateThreads request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct TerminateThreadsArguments {
   /// Ids of threads to be terminated.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub thread_ids: Option<Vec<i64>>,
 }
 
 /// Arguments for a Variables request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct VariablesArguments {
   /// The variable for which to retrieve its children. The `variablesReference`
   /// must have been obtained in the current suspended state.
@@ -640,27 +775,32 @@ pub struct VariablesArguments {
   /// Filter to limit the child variables to either named or indexed. If omitted,
   /// both types are fetched.
   /// Values: 'indexed', 'named'
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub filter: Option<VariablesArgumentsFilter>,
   /// The index of the first variable to return, if omitted children start at 0.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub start: Option<i64>,
   /// The i64 of variables to return. If count is missing or 0, all variables
   /// are returned.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub count: Option<i64>,
   /// Specifies details on how to format the Variable values.
   /// The attribute is only honored by a debug adapter if the corresponding
   /// capability `supportsValueFormattingOptions` is true.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub format: Option<ValueFormat>,
 }
 
 /// Arguments for a WriteMemory request.
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct WriteMemoryArguments {
   /// Memory reference to the base location to which data should be written.
   pub memory_reference: String,
   /// Offset (in bytes) to be applied to the reference location before writing
   /// data. Can be negative.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub offset: Option<i64>,
   /// Property to control partial writes. If true, the debug adapter should
   /// attempt to write memory even if the entire memory region is not writable.
@@ -669,14 +809,15 @@ pub struct WriteMemoryArguments {
   /// the response via the `offset` and `bytesWritten` properties.
   /// If false or missing, a debug adapter should attempt to verify the region is
   /// writable before writing, and fail the response if it is not.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub allow_partial: Option<bool>,
   /// Bytes to write, encoded using base64.
   pub data: String,
 }
 
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "command", content = "arguments", rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum Command {
   /// The attach request is sent from the client to the debug adapter to attach to a debuggee that
   /// is already running.
@@ -789,6 +930,10 @@ pub enum Command {
   ///
   /// Specification: [LoadedSources request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_LoadedSources)
   LoadedSources,
+  /// Looks up information about a location reference previously returned by the debug adapter.
+  ///
+  /// Specification: [Locations request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Locations)
+  Locations(LocationsArguments),
   /// Modules can be retrieved from the debug adapter with this request which can either return
   /// all modules or a range of modules to support paging.
   /// Clients should only call this request if the corresponding capability
@@ -994,15 +1139,68 @@ pub enum Command {
   Cancel(CancelArguments),
 }
 
+impl Command {
+  /// The name of the command as it appears in the `command` attribute of the request.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Command::Attach(_) => "attach",
+      Command::BreakpointLocations(_) => "breakpointLocations",
+      Command::Completions(_) => "completions",
+      Command::ConfigurationDone => "configurationDone",
+      Command::Continue(_) => "continue",
+      Command::DataBreakpointInfo(_) => "dataBreakpointInfo",
+      Command::Disassemble(_) => "disassemble",
+      Command::Disconnect(_) => "disconnect",
+      Command::Evaluate(_) => "evaluate",
+      Command::ExceptionInfo(_) => "exceptionInfo",
+      Command::Goto(_) => "goto",
+      Command::GotoTargets(_) => "gotoTargets",
+      Command::Initialize(_) => "initialize",
+      Command::Launch(_) => "launch",
+      Command::LoadedSources => "loadedSources",
+      Command::Locations(_) => "locations",
+      Command::Modules(_) => "modules",
+      Command::Next(_) => "next",
+      Command::Pause(_) => "pause",
+      Command::ReadMemory(_) => "readMemory",
+      Command::Restart(_) => "restart",
+      Command::RestartFrame(_) => "restartFrame",
+      Command::ReverseContinue(_) => "reverseContinue",
+      Command::Scopes(_) => "scopes",
+      Command::SetBreakpoints(_) => "setBreakpoints",
+      Command::SetDataBreakpoints(_) => "setDataBreakpoints",
+      Command::SetExceptionBreakpoints(_) => "setExceptionBreakpoints",
+      Command::SetExpression(_) => "setExpression",
+      Command::SetFunctionBreakpoints(_) => "setFunctionBreakpoints",
+      Command::SetInstructionBreakpoints(_) => "setInstructionBreakpoints",
+      Command::SetVariable(_) => "setVariable",
+      Command::Source(_) => "source",
+      Command::StackTrace(_) => "stackTrace",
+      Command::StepBack(_) => "stepBack",
+      Command::StepIn(_) => "stepIn",
+      Command::StepInTargets(_) => "stepInTargets",
+      Command::StepOut(_) => "stepOut",
+      Command::Terminate(_) => "terminate",
+      Command::TerminateThreads(_) => "terminateThreads",
+      Command::Threads => "threads",
+      Command::Variables(_) => "variables",
+      Command::WriteMemory(_) => "writeMemory",
+      Command::Cancel(_) => "cancel",
+    }
+  }
+}
+
 /// Represents a request from a client.
 ///
 /// Note that unlike the specification, this implementation does not define a ProtocolMessage base
 /// interface. Instead, the only common part (the sequence number) is repeated in the struct.
 ///
+/// It is serialized with `"type": "request"`, so clients can send it as it is.
+///
 /// Specification: [Request](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_Request)
-#[cfg_attr(feature = "client", derive(Serialize))]
-#[derive(Deserialize, Debug, Clone)]
-#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "request", rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Request {
   /// Sequence number for the Request.
   ///
@@ -1035,6 +1233,7 @@ impl Request {
       message: None,
       body: Some(body), // to love
       error: None,
+      command: None,
     }
   }
 
@@ -1052,6 +1251,7 @@ impl Request {
       message: Some(ResponseMessage::Error(error.to_string())),
       body: None,
       error: None,
+      command: Some(self.command.name().to_string()),
     }
   }
 
@@ -1065,6 +1265,7 @@ impl Request {
       message: Some(ResponseMessage::Cancelled),
       body: None,
       error: None,
+      command: Some(self.command.name().to_string()),
     }
   }
 
@@ -1078,6 +1279,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Attach),
         error: None,
+        command: None,
       }),
       Command::ConfigurationDone => Ok(Response {
         request_seq: self.seq,
@@ -1085,6 +1287,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::ConfigurationDone),
         error: None,
+        command: None,
       }),
       Command::Disconnect(_) => Ok(Response {
         request_seq: self.seq,
@@ -1092,6 +1295,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Disconnect),
         error: None,
+        command: None,
       }),
       Command::Goto(_) => Ok(Response {
         request_seq: self.seq,
@@ -1099,6 +1303,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Goto),
         error: None,
+        command: None,
       }),
       Command::Launch(_) => Ok(Response {
         request_seq: self.seq,
@@ -1106,6 +1311,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Launch),
         error: None,
+        command: None,
       }),
       Command::Next(_) => Ok(Response {
         request_seq: self.seq,
@@ -1113,6 +1319,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Next),
         error: None,
+        command: None,
       }),
       Command::Pause(_) => Ok(Response {
         request_seq: self.seq,
@@ -1120,6 +1327,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Pause),
         error: None,
+        command: None,
       }),
       Command::Restart(_) => Ok(Response {
         request_seq: self.seq,
@@ -1127,6 +1335,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Next),
         error: None,
+        command: None,
       }),
       Command::RestartFrame(_) => Ok(Response {
         request_seq: self.seq,
@@ -1134,6 +1343,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::RestartFrame),
         error: None,
+        command: None,
       }),
       Command::ReverseContinue(_) => Ok(Response {
         request_seq: self.seq,
@@ -1141,6 +1351,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::ReverseContinue),
         error: None,
+        command: None,
       }),
       Command::StepBack(_) => Ok(Response {
         request_seq: self.seq,
@@ -1148,6 +1359,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::StepBack),
         error: None,
+        command: None,
       }),
       Command::StepIn(_) => Ok(Response {
         request_seq: self.seq,
@@ -1155,6 +1367,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::StepIn),
         error: None,
+        command: None,
       }),
       Command::StepOut(_) => Ok(Response {
         request_seq: self.seq,
@@ -1162,6 +1375,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::StepOut),
         error: None,
+        command: None,
       }),
       Command::Terminate(_) => Ok(Response {
         request_seq: self.seq,
@@ -1169,6 +1383,7 @@ impl Request {
         message: None,
         body: Some(ResponseBody::Terminate),
         error: None,
+        command: None,
       }),
       Command::TerminateThreads(_) => Ok(Response {
         request_seq: self.seq,
@@ -1176,8 +1391,40 @@ impl Request {
         message: None,
         body: Some(ResponseBody::TerminateThreads),
         error: None,
+        command: None,
       }),
       _ => Err(ServerError::ResponseConstructError),
     }
   }
 }
+
+/// The parts every request has, independent of its command: the sequence number, the name of
+/// the command and the raw arguments.
+///
+/// When a request can't be deserialized into a [`Request`] (an unknown command, or arguments
+/// that don't match the command), the envelope still identifies it, so it can be answered with
+/// an error response instead of leaving the client waiting.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RequestEnvelope {
+  /// Sequence number of the request.
+  pub seq: i64,
+  /// The command to execute.
+  pub command: String,
+  /// The arguments of the command, if any.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub arguments: Option<Value>,
+}
+
+impl RequestEnvelope {
+  /// Create an error response for the request.
+  pub fn error(&self, error: &str) -> Response {
+    Response {
+      request_seq: self.seq,
+      success: false,
+      message: Some(ResponseMessage::Error(error.to_string())),
+      body: None,
+      error: None,
+      command: Some(self.command.clone()),
+    }
+  }
+}
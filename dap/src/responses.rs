@@ -1,20 +1,17 @@
 #[cfg(feature = "integration_testing")]
 use fake::Dummy;
-use serde::Serialize;
-
-#[cfg(feature = "client")]
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 use crate::types::{
   Breakpoint, BreakpointLocation, Capabilities, CompletionItem, DataBreakpointAccessType,
   DisassembledInstruction, ExceptionBreakMode, ExceptionDetails, GotoTarget, Message, Module,
-  Scope, Source, StackFrame, Thread, Variable, VariablePresentationHint,
+  Scope, Source, StackFrame, StepInTarget, Thread, Variable, VariablePresentationHint,
 };
 
 /// Represents a response message that is either a cancellation or a short error string.
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ResponseMessage {
   /// Should be sent when the request was canceled
@@ -27,27 +24,24 @@ pub enum ResponseMessage {
   Error(String),
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct BreakpointLocationsResponse {
   /// Sorted set of possible breakpoint locations.
   pub breakpoints: Vec<BreakpointLocation>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct CompletionsResponse {
   /// The possible completions
   pub targets: Vec<CompletionItem>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ContinueResponse {
   /// The value true (or a missing property) signals to the client that all
@@ -57,9 +51,8 @@ pub struct ContinueResponse {
   pub all_threads_continued: Option<bool>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DataBreakpointInfoResponse {
   /// An identifier for the data on which a data breakpoint can be registered
@@ -84,18 +77,16 @@ pub struct DataBreakpointInfoResponse {
   pub can_persist: Option<bool>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DisassembleResponse {
   /// The list of disassembled instructions.
   pub instructions: Vec<DisassembledInstruction>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct EvaluateResponse {
   /// The result of the evaluate request.
@@ -135,11 +126,19 @@ pub struct EvaluateResponse {
   /// capability `supportsMemoryReferences` is true.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub memory_reference: Option<String>,
+  /// A reference that allows the client to request the location where the
+  /// returned value is declared. For example, if a function pointer is
+  /// returned, the adapter may be able to look up the function's location.
+  /// This should be present only if the adapter is likely to be able to
+  /// resolve the location.
+  ///
+  /// This reference shares the same lifetime as the `variablesReference`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub value_location_reference: Option<i64>,
 }
 
-#[derive(Serialize, Debug, Clone, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionInfoResponse {
   /// ID of the exception that was thrown.
@@ -154,27 +153,51 @@ pub struct ExceptionInfoResponse {
   pub details: Option<ExceptionDetails>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct GotoTargetsResponse {
   /// The possible goto targets of the specified location.
   pub targets: Vec<GotoTarget>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct LoadedSourcesResponse {
   /// Set of loaded sources.
   pub sources: Vec<Source>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct LocationsResponse {
+  /// The source containing the location; either `source.path` or
+  /// `source.sourceReference` must be specified.
+  pub source: Source,
+  /// The line number of the location. The client capability `linesStartAt1`
+  /// determines whether it is 0- or 1-based.
+  pub line: i64,
+  /// Position of the location within the `line`. It is measured in UTF-16 code
+  /// units and the client capability `columnsStartAt1` determines whether it is
+  /// 0- or 1-based. If no column is given, the first position in the start line
+  /// is assumed.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column: Option<i64>,
+  /// End line of the location, present if the location refers to a range. The
+  /// client capability `linesStartAt1` determines whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_line: Option<i64>,
+  /// End position of the location within `endLine`, present if the location
+  /// refers to a range. It is measured in UTF-16 code units and the client
+  /// capability `columnsStartAt1` determines whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_column: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ModulesResponse {
   /// All modules or range of modules.
@@ -184,9 +207,8 @@ pub struct ModulesResponse {
   pub total_modules: Option<i64>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ReadMemoryResponse {
   /// The address of the first byte of data returned.
@@ -204,9 +226,8 @@ pub struct ReadMemoryResponse {
   pub data: Option<String>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ScopesResponse {
   /// The scopes of the stackframe. If the array has length zero, there are no
@@ -214,9 +235,8 @@ pub struct ScopesResponse {
   pub scopes: Vec<Scope>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetBreakpointsResponse {
   /// Information about the breakpoints.
@@ -225,9 +245,8 @@ pub struct SetBreakpointsResponse {
   pub breakpoints: Vec<Breakpoint>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetDataBreakpointsResponse {
   /// Information about the breakpoints.
@@ -236,9 +255,8 @@ pub struct SetDataBreakpointsResponse {
   pub breakpoints: Vec<Breakpoint>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetExceptionBreakpointsResponse {
   /// Information about the exception breakpoints or filters.
@@ -251,9 +269,8 @@ pub struct SetExceptionBreakpointsResponse {
   pub breakpoints: Option<Vec<Breakpoint>>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetFunctionBreakpointsResponse {
   /// Information about the breakpoints. The array elements correspond to the
@@ -261,9 +278,8 @@ pub struct SetFunctionBreakpointsResponse {
   pub breakpoints: Vec<Breakpoint>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetInstructionBreakpointsResponse {
   /// Information about the breakpoints. The array elements correspond to the
@@ -271,9 +287,8 @@ pub struct SetInstructionBreakpointsResponse {
   pub breakpoints: Vec<Breakpoint>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetVariableResponse {
   /// The new value of the variable.
@@ -304,9 +319,8 @@ pub struct SetVariableResponse {
   pub indexed_variables: Option<i32>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SourceResponse {
   /// Content of the source reference.
@@ -316,9 +330,8 @@ pub struct SourceResponse {
   pub mime_type: Option<String>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SetExpressionResponse {
   /// The new value of the expression.
@@ -354,9 +367,8 @@ pub struct SetExpressionResponse {
   pub indexed_variables: Option<i32>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StackTraceResponse {
   /// The frames of the stackframe. If the array has length zero, there are no
@@ -373,27 +385,32 @@ pub struct StackTraceResponse {
   pub total_frames: Option<i64>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct StepInTargetsResponse {
+  /// The possible step-in targets of the specified source location.
+  pub targets: Vec<StepInTarget>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ThreadsResponse {
   /// All threads.
   pub threads: Vec<Thread>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct VariablesResponse {
   /// All (or a range) of variables for the given variable reference.
   pub variables: Vec<Variable>,
 }
 
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct WriteMemoryResponse {
   /// Property that should be returned when `allowPartial` is true to indicate
@@ -407,9 +424,8 @@ pub struct WriteMemoryResponse {
   pub bytes_written: Option<i64>,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "command", content = "body", rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ResponseBody {
   /// Response to attach request. This is just an acknowledgement, so no body field is required.
@@ -483,6 +499,10 @@ pub enum ResponseBody {
   ///
   /// Specification: [LoadedSources request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_LoadedSources)
   LoadedSources(LoadedSourcesResponse),
+  /// Response to `locations` request.
+  ///
+  /// Specification: [Locations request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Locations)
+  Locations(LocationsResponse),
   /// Response to `modules` request.
   ///
   /// Specification: [Modules request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Modules)
@@ -594,6 +614,10 @@ pub enum ResponseBody {
   ///
   /// Specification: [StepIn request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_StepIn)
   StepIn,
+  /// Response to `stepInTargets` request.
+  ///
+  /// Specification: [StepInTargets request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_StepInTargets)
+  StepInTargets(StepInTargetsResponse),
   /// Response to `stepOut` request. This is just an acknowledgement, so no body field is required.
   ///
   /// Specification: [StepOut request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_StepOut)
@@ -624,6 +648,10 @@ pub enum ResponseBody {
   ///
   /// Specification: [WriteMemory request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_WriteMemory)
   WriteMemory(WriteMemoryResponse),
+  /// Response to `cancel` request. This is just an acknowledgement, so no body field is required.
+  ///
+  /// Specification: [Cancel request](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Cancel)
+  Cancel,
 }
 
 /// Represents response to the client.
@@ -635,13 +663,10 @@ pub enum ResponseBody {
 /// ResponseBody enum.
 ///
 /// Specification: [Response](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_Response)
-#[derive(Serialize, Debug, Default, Clone)]
-#[serde(rename_all = "camelCase")]
-#[cfg_attr(feature = "client", derive(Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Response {
   /// Sequence number of the corresponding request.
-  #[serde(rename = "request_seq")]
   pub request_seq: i64,
   /// Outcome of the request.
   /// If true, the request was successful and the `body` attribute may contain
@@ -657,14 +682,110 @@ pub struct Response {
   /// Values:
   /// 'cancelled': request was cancelled.
   /// etc.
-  #[serde(skip_serializing_if = "Option::is_none")]
   pub message: Option<ResponseMessage>,
   /// Contains request result if success is true and error details if success is
   /// false.
-  #[serde(flatten, skip_serializing_if = "Option::is_none")]
   pub body: Option<ResponseBody>,
-  /// A structured error message.
+  /// A structured error message. Sent as `body.error` of responses without a body.
   pub error: Option<Message>,
+  /// The command requested. The body carries the command if there is one, so this is only
+  /// needed for responses without a body, such as the ones created by
+  /// [`Request::error`](crate::requests::Request::error).
+  pub command: Option<String>,
+}
+
+impl Serialize for Response {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Wire<'a> {
+      request_seq: i64,
+      success: bool,
+      #[serde(skip_serializing_if = "Option::is_none")]
+      message: Option<&'a ResponseMessage>,
+      #[serde(flatten, skip_serializing_if = "Option::is_none")]
+      body: Option<&'a ResponseBody>,
+      #[serde(skip_serializing_if = "Option::is_none")]
+      command: Option<&'a str>,
+      #[serde(rename = "body", skip_serializing_if = "Option::is_none")]
+      error_body: Option<ErrorBody<'a>>,
+    }
+
+    #[derive(Serialize)]
+    struct ErrorBody<'a> {
+      #[serde(skip_serializing_if = "Option::is_none")]
+      error: Option<&'a Message>,
+    }
+
+    // Without a body, the command and (for failed requests) the error details have to be sent
+    // separately. The specification requires a body on every error response.
+    let bodyless = self.body.is_none();
+    Wire {
+      request_seq: self.request_seq,
+      success: self.success,
+      message: self.message.as_ref(),
+      body: self.body.as_ref(),
+      command: self.command.as_deref().filter(|_| bodyless),
+      error_body: if bodyless && !self.success {
+        Some(ErrorBody {
+          error: self.error.as_ref(),
+        })
+      } else {
+        None
+      },
+    }
+    .serialize(serializer)
+  }
+}
+
+impl<'de> Deserialize<'de> for Response {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    #[derive(Deserialize)]
+    struct Wire {
+      request_seq: i64,
+      success: bool,
+      #[serde(default)]
+      message: Option<ResponseMessage>,
+      command: String,
+      #[serde(default)]
+      body: Option<Value>,
+    }
+
+    #[derive(Deserialize)]
+    struct ErrorBody {
+      #[serde(default)]
+      error: Option<Message>,
+    }
+
+    // The reverse of `serialize`: the command and the body form a `ResponseBody` if they match
+    // one. Otherwise (e.g. for commands this crate doesn't know) this is a response without a
+    // body, and the body of a failed response is read for the error details only.
+    let wire = Wire::deserialize(deserializer)?;
+    let mut tagged = Map::new();
+    tagged.insert("command".to_string(), Value::String(wire.command.clone()));
+    if let Some(body) = &wire.body {
+      tagged.insert("body".to_string(), body.clone());
+    }
+    let (body, error, command) = match ResponseBody::deserialize(Value::Object(tagged)) {
+      Ok(body) => (Some(body), None, None),
+      Err(_) => {
+        let error = match wire.body {
+          Some(body) if !wire.success => ErrorBody::deserialize(body)
+            .ok()
+            .and_then(|body| body.error),
+          _ => None,
+        };
+        (None, error, Some(wire.command))
+      }
+    };
+    Ok(Response {
+      request_seq: wire.request_seq,
+      success: wire.success,
+      message: wire.message,
+      body,
+      error,
+      command,
+    })
+  }
 }
 
 #[cfg(test)]
@@ -679,6 +800,7 @@ mod test {
       message: Some(ResponseMessage::Error("test".to_string())),
       body: None,
       error: None,
+      command: None,
     };
     let val = serde_json::to_value(a).unwrap();
 
@@ -692,6 +814,7 @@ mod test {
       message: Some(ResponseMessage::Cancelled),
       body: None,
       error: None,
+      command: None,
     };
     let val = serde_json::to_value(a).unwrap();
     assert!(val.get("message").unwrap().is_string());
@@ -703,9 +826,86 @@ mod test {
       message: Some(ResponseMessage::NotStopped),
       body: None,
       error: None,
+      command: None,
     };
     let val = serde_json::to_value(a).unwrap();
     assert!(val.get("message").unwrap().is_string());
     assert!(val.get("message").unwrap().as_str().unwrap() == "notStopped");
   }
+
+  #[test]
+  fn test_error_response_has_command_and_body() {
+    let a = Response {
+      request_seq: 3,
+      success: false,
+      message: Some(ResponseMessage::Error("no such thread".to_string())),
+      body: None,
+      error: None,
+      command: Some("stackTrace".to_string()),
+    };
+    let val = serde_json::to_value(a).unwrap();
+    assert_eq!(val["command"], "stackTrace");
+    assert_eq!(val["body"], serde_json::json!({}));
+    assert!(val.get("error").is_none());
+
+    // The body provides the command of successful responses.
+    let a = Response {
+      request_seq: 3,
+      success: true,
+      message: None,
+      body: Some(ResponseBody::Threads(ThreadsResponse { threads: vec![] })),
+      error: None,
+      command: Some("threads".to_string()),
+    };
+    let val = serde_json::to_string(&a).unwrap();
+    assert_eq!(val.matches("\"command\"").count(), 1);
+  }
+
+  #[test]
+  fn test_error_response_round_trip() {
+    let a = Response {
+      request_seq: 3,
+      success: false,
+      message: Some(ResponseMessage::Cancelled),
+      body: None,
+      error: Some(Message {
+        id: 7,
+        format: "cancelled".to_string(),
+        ..Default::default()
+      }),
+      command: Some("stackTrace".to_string()),
+    };
+    let val = serde_json::to_value(&a).unwrap();
+    assert_eq!(serde_json::from_value::<Response>(val).unwrap(), a);
+  }
+
+  #[test]
+  fn test_response_deserialize() {
+    let response: Response = serde_json::from_value(serde_json::json!({
+      "seq": 4, "type": "response", "request_seq": 3, "success": true, "command": "threads",
+      "body": {"threads": [{"id": 1, "name": "main"}]}
+    }))
+    .unwrap();
+    assert!(
+      matches!(response.body, Some(ResponseBody::Threads(ref body)) if body.threads.len() == 1)
+    );
+    assert_eq!(response.command, None);
+
+    // Failed and unknown requests keep their command, and the error details of the body.
+    let response: Response = serde_json::from_value(serde_json::json!({
+      "request_seq": 3, "success": false, "command": "stackTrace", "message": "no such thread",
+      "body": {"error": {"id": 7, "format": "no thread {id}", "variables": {"id": "5"}}}
+    }))
+    .unwrap();
+    assert!(response.body.is_none());
+    assert_eq!(response.command.as_deref(), Some("stackTrace"));
+    assert_eq!(response.error.unwrap().id, 7);
+
+    let response: Response = serde_json::from_value(serde_json::json!({
+      "request_seq": 3, "success": true, "command": "custom", "body": {"answer": 42}
+    }))
+    .unwrap();
+    assert!(response.body.is_none());
+    assert_eq!(response.command.as_deref(), Some("custom"));
+  }
 }
@@ -1,20 +1,24 @@
 use std::collections::HashMap;
 
-use serde::Serialize;
-
-#[cfg(feature = "client")]
-use serde::Deserialize;
+#[cfg(feature = "integration_testing")]
+use fake::Dummy;
+use serde::{Deserialize, Serialize};
 
 use crate::types::{RunInTerminalRequestArgumentsKind, StartDebuggingRequestKind};
 
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Default, Clone)]
+#[cfg(feature = "integration_testing")]
+use crate::types::ObjectFaker;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct RunInTerminalRequestArguments {
   /// What kind of terminal to launch.
   /// Values: 'integrated', 'external'
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub kind: Option<RunInTerminalRequestArgumentsKind>,
   /// Title of the terminal.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub title: Option<String>,
   /// Working directory for the command. For non-empty, valid paths this
   /// typically results in execution of a change directory command.
@@ -23,6 +27,7 @@ pub struct RunInTerminalRequestArguments {
   pub args: Vec<String>,
   /// Environment key-value pairs that are added to or removed from the default
   /// environment.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub env: Option<HashMap<String, Option<String>>>,
   /// This property should only be set if the corresponding capability
   /// `supportsArgsCanBeInterpretedByShell` is true. If the client uses an
@@ -30,17 +35,19 @@ pub struct RunInTerminalRequestArguments {
   /// attempt to escape characters with special meanings for the shell. The user
   /// is fully responsible for escaping as needed and that arguments using
   /// special characters may not be portable across shells.
+  #[serde(skip_serializing_if = "Option::is_none")]
   pub args_can_be_interpreted_by_shell: Option<bool>,
 }
 
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StartDebuggingRequestArguments {
   /// Arguments passed to the new debug session. The arguments must only contain
   /// properties understood by the `launch` or `attach` requests of the debug
   /// adapter and they must not contain any client-specific properties (e.g.
   /// `type`) or client-specific features (e.g. substitutable 'variables').
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "ObjectFaker"))]
   pub configuration: HashMap<String, serde_json::Value>,
   /// Indicates whether the new debug session should be started with a `launch`
   /// or `attach` request.
@@ -48,9 +55,9 @@ pub struct StartDebuggingRequestArguments {
   pub request: StartDebuggingRequestKind,
 }
 
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "command", content = "arguments", rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ReverseCommand {
   /// This request is sent from the debug adapter to the client to run a command in a terminal.
   ///
@@ -83,29 +90,30 @@ pub enum ReverseCommand {
   StartDebugging(StartDebuggingRequestArguments),
 }
 
+impl ReverseCommand {
+  /// The name of the command as it appears in the `command` attribute of the request.
+  pub fn name(&self) -> &'static str {
+    match self {
+      ReverseCommand::RunInTerminal(_) => "runInTerminal",
+      ReverseCommand::StartDebugging(_) => "startDebugging",
+    }
+  }
+}
+
 /// A debug adapter initiated request.
 ///
 /// The specification treats reverse requests identically to all other requests
-/// (even though there is a separate section for them). However, in Rust, it is
-/// beneficial to separate them because then we don't need to generate a huge
-/// amount of serialization code for all requests and supporting types (that the
-/// vast majority of would never be serialized by the adapter, only deserialized).
-#[cfg_attr(feature = "client", derive(Deserialize))]
-#[derive(Serialize, Debug, Clone)]
+/// (even though there is a separate section for them). In Rust, they are kept
+/// separate so that matching on a [`Command`](crate::requests::Command) only has
+/// to deal with the requests an adapter receives.
+///
+/// A reverse request is always sent inside a [`BaseMessage`](crate::base_message::BaseMessage),
+/// whose `seq` is the sequence number of the request that the client refers to as `request_seq`
+/// in its response.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ReverseRequest {
-  /// Sequence number for the Request.
-  ///
-  /// From the [specification](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_ProtocolMessage):
-  ///
-  /// Sequence number of the message (also known as message ID). The `seq` for
-  /// the first message sent by a client or debug adapter is 1, and for each
-  /// subsequent message is 1 greater than the previous message sent by that
-  /// actor. `seq` can be used to order requests, responses, and events, and to
-  /// associate requests with their corresponding responses. For protocol
-  /// messages of type `request` the sequence number can be used to cancel the
-  /// request.
-  pub seq: i64,
   /// The command to execute.
   ///
   /// This is stringly typed in the specification, but represented as an enum for better
@@ -113,3 +121,57 @@ pub struct ReverseRequest {
   #[serde(flatten)]
   pub command: ReverseCommand,
 }
+
+/// Body of the client's response to a `runInTerminal` reverse request.
+///
+/// Specification: [RunInTerminal](https://microsoft.github.io/debug-adapter-protocol/specification#Reverse_Requests_RunInTerminal)
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct RunInTerminalResponse {
+  /// The process ID. The value should be less than or equal to 2147483647
+  /// (2^31-1).
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub process_id: Option<i64>,
+  /// The process ID of the terminal shell. The value should be less than or
+  /// equal to 2147483647 (2^31-1).
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub shell_process_id: Option<i64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "command", content = "body", rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub enum ReverseResponseBody {
+  /// Response to a `runInTerminal` reverse request.
+  ///
+  /// Specification: [RunInTerminal](https://microsoft.github.io/debug-adapter-protocol/specification#Reverse_Requests_RunInTerminal)
+  RunInTerminal(RunInTerminalResponse),
+  /// Response to a `startDebugging` reverse request. This is just an acknowledgement, so no body
+  /// field is required.
+  ///
+  /// Specification: [StartDebugging](https://microsoft.github.io/debug-adapter-protocol/specification#Reverse_Requests_StartDebugging)
+  StartDebugging,
+}
+
+/// The client's response to a [`ReverseRequest`].
+///
+/// This is the mirror image of [`Response`](crate::responses::Response), kept separate for the
+/// same reason reverse requests are.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct ReverseResponse {
+  /// Sequence number of the corresponding reverse request.
+  #[serde(rename = "request_seq")]
+  pub request_seq: i64,
+  /// Outcome of the request.
+  pub success: bool,
+  /// Contains the raw error in short form if `success` is false.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+  /// Contains the request result if `success` is true.
+  #[serde(flatten)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub body: Option<ReverseResponseBody>,
+}
@@ -0,0 +1,303 @@
+//! Dispatching requests to closures registered per command.
+//!
+//! A [`Router`] answers each request with the handler registered for its command, and answers
+//! requests for commands without a handler with an error. It also answers the `initialize`
+//! request: the capabilities that correspond to a command (such as `supportsFunctionBreakpoints`
+//! for `setFunctionBreakpoints`) are derived from the registered handlers, so an adapter can't
+//! advertise a request it doesn't handle, or handle one it doesn't advertise.
+//!
+//! ```rust
+//! use dap::prelude::*;
+//! use dap::router::Router;
+//! use dap::typed::{SetFunctionBreakpoints, Threads};
+//!
+//! let mut router = Router::new();
+//! router
+//!   .on::<Threads, _, String>(|()| Ok(responses::ThreadsResponse::default()))
+//!   .on::<SetFunctionBreakpoints, _, String>(|args| {
+//!     Ok(responses::SetFunctionBreakpointsResponse {
+//!       breakpoints: vec![Default::default(); args.breakpoints.len()],
+//!       ..Default::default()
+//!     })
+//!   });
+//! assert_eq!(
+//!   router.capabilities().supports_function_breakpoints,
+//!   Some(true)
+//! );
+//! ```
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::{Read, Write};
+
+use crate::{
+  errors::ServerError,
+  requests::Request,
+  responses::{Response, ResponseBody},
+  server::Server,
+  typed::*,
+  types::Capabilities,
+};
+
+type Handler<'a> = Box<dyn FnMut(Request) -> Response + 'a>;
+
+type CapabilityFlag = fn(&mut Capabilities) -> &mut Option<bool>;
+
+/// The capabilities that are advertised if, and only if, handlers for all of the listed commands
+/// are registered.
+const COMMAND_CAPABILITIES: &[(&[&str], CapabilityFlag)] = &[
+  (&[Cancel::COMMAND], |c| &mut c.supports_cancel_request),
+  (&[BreakpointLocations::COMMAND], |c| {
+    &mut c.supports_breakpoint_locations_request
+  }),
+  (&[Completions::COMMAND], |c| {
+    &mut c.supports_completions_request
+  }),
+  (&[ConfigurationDone::COMMAND], |c| {
+    &mut c.supports_configuration_done_request
+  }),
+  (
+    &[DataBreakpointInfo::COMMAND, SetDataBreakpoints::COMMAND],
+    |c| &mut c.supports_data_breakpoints,
+  ),
+  (&[Disassemble::COMMAND], |c| {
+    &mut c.supports_disassemble_request
+  }),
+  (&[ExceptionInfo::COMMAND], |c| {
+    &mut c.supports_exception_info_request
+  }),
+  (&[Goto::COMMAND, GotoTargets::COMMAND], |c| {
+    &mut c.supports_goto_targets_request
+  }),
+  (&[LoadedSources::COMMAND], |c| {
+    &mut c.supports_loaded_sources_request
+  }),
+  (&[Modules::COMMAND], |c| &mut c.supports_modules_request),
+  (&[ReadMemory::COMMAND], |c| {
+    &mut c.supports_read_memory_request
+  }),
+  (&[Restart::COMMAND], |c| &mut c.supports_restart_request),
+  (&[RestartFrame::COMMAND], |c| &mut c.supports_restart_frame),
+  (&[SetExpression::COMMAND], |c| {
+    &mut c.supports_set_expression
+  }),
+  (&[SetFunctionBreakpoints::COMMAND], |c| {
+    &mut c.supports_function_breakpoints
+  }),
+  (&[SetInstructionBreakpoints::COMMAND], |c| {
+    &mut c.supports_instruction_breakpoints
+  }),
+  (&[SetVariable::COMMAND], |c| &mut c.supports_set_variable),
+  (&[StepBack::COMMAND, ReverseContinue::COMMAND], |c| {
+    &mut c.supports_step_back
+  }),
+  (&[StepInTargets::COMMAND], |c| {
+    &mut c.supports_step_in_targets_request
+  }),
+  (&[Terminate::COMMAND], |c| &mut c.supports_terminate_request),
+  (&[TerminateThreads::COMMAND], |c| {
+    &mut c.supports_terminate_threads_request
+  }),
+  (&[WriteMemory::COMMAND], |c| {
+    &mut c.supports_write_memory_request
+  }),
+];
+
+/// Answers requests with the closures registered for their command.
+pub struct Router<'a> {
+  handlers: HashMap<&'static str, Handler<'a>>,
+  capabilities: Capabilities,
+}
+
+impl<'a> Default for Router<'a> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a> Router<'a> {
+  pub fn new() -> Self {
+    Self::with_capabilities(Capabilities::default())
+  }
+
+  /// Creates a router that advertises `capabilities` in addition to the ones derived from the
+  /// registered handlers, e.g. `exceptionBreakpointFilters` or `supportsConditionalBreakpoints`.
+  /// The capabilities that are derived are overwritten.
+  pub fn with_capabilities(capabilities: Capabilities) -> Self {
+    Self {
+      handlers: HashMap::new(),
+      capabilities,
+    }
+  }
+
+  /// Registers the handler for the request `R`, replacing the previous one. An `Err` returned by
+  /// the handler is sent to the client as an error response.
+  ///
+  /// A handler for [`Initialize`] is optional. If there is one, the capabilities it returns are
+  /// advertised instead of the ones passed to [`with_capabilities`](Self::with_capabilities),
+  /// and the derived capabilities are still overwritten.
+  pub fn on<R, F, E>(&mut self, mut handler: F) -> &mut Self
+  where
+    R: ClientRequest,
+    F: FnMut(R::Arguments) -> Result<R::Response, E> + 'a,
+    E: Display,
+  {
+    let handler = move |request: Request| match R::from_command(request.command.clone()) {
+      Ok(arguments) => match handler(arguments) {
+        Ok(response) => request.success(R::into_body(response)),
+        Err(e) => request.error(&e.to_string()),
+      },
+      Err(_) => request.error(&format!("Not a {} request", R::COMMAND)),
+    };
+    self.handlers.insert(R::COMMAND, Box::new(handler));
+    self
+  }
+
+  /// Returns `true` if there is a handler for the command.
+  pub fn handles(&self, command: &str) -> bool {
+    self.handlers.contains_key(command)
+  }
+
+  /// The capabilities sent in the response to the `initialize` request, unless there is a
+  /// handler for it.
+  pub fn capabilities(&self) -> Capabilities {
+    let mut capabilities = self.capabilities.clone();
+    self.derive_capabilities(&mut capabilities);
+    capabilities
+  }
+
+  fn derive_capabilities(&self, capabilities: &mut Capabilities) {
+    for (commands, flag) in COMMAND_CAPABILITIES {
+      let handled = commands.iter().all(|command| self.handles(command));
+      *flag(capabilities) = if handled { Some(true) } else { None };
+    }
+  }
+
+  /// Answers a request with its handler, or with an error if there is none.
+  pub fn handle(&mut self, request: Request) -> Response {
+    let command = request.command.name();
+    match self.handlers.get_mut(command) {
+      Some(handler) => {
+        let mut response = handler(request);
+        if let Some(ResponseBody::Initialize(capabilities)) = &mut response.body {
+          self.derive_capabilities(capabilities);
+        }
+        response
+      }
+      None if command == Initialize::COMMAND => {
+        request.success(ResponseBody::Initialize(self.capabilities()))
+      }
+      None => request.error(&format!("Unsupported command: {}", command)),
+    }
+  }
+
+  /// Answers the requests read by `server` until its input ends.
+  pub fn serve<R: Read, W: Write>(&mut self, server: &mut Server<R, W>) -> Result<(), ServerError> {
+    while let Some(request) = server.poll_request()? {
+      let response = self.handle(request);
+      server.respond(response)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    requests::{Command, GotoTargetsArguments, InitializeArguments},
+    responses::{GotoTargetsResponse, ThreadsResponse},
+    types::Thread,
+  };
+
+  fn request(command: Command) -> Request {
+    Request { seq: 1, command }
+  }
+
+  fn initialize(router: &mut Router) -> Capabilities {
+    let response = router.handle(request(Command::Initialize(InitializeArguments::default())));
+    match response.body {
+      Some(ResponseBody::Initialize(capabilities)) => capabilities,
+      other => panic!("unexpected body {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_handle() {
+    let mut calls = 0;
+    let mut router = Router::new();
+    router
+      .on::<Threads, _, String>(|()| {
+        calls += 1;
+        Ok(ThreadsResponse {
+          threads: vec![Thread {
+            id: 1,
+            name: "main".to_string(),
+          }],
+        })
+      })
+      .on::<GotoTargets, _, _>(|args: GotoTargetsArguments| {
+        Err(format!("No targets at line {}", args.line))
+      });
+
+    let response = router.handle(request(Command::Threads));
+    assert!(response.success);
+    assert!(matches!(
+      response.body,
+      Some(ResponseBody::Threads(ThreadsResponse { ref threads, .. })) if threads.len() == 1
+    ));
+
+    let response = router.handle(request(Command::GotoTargets(GotoTargetsArguments {
+      line: 7,
+      ..Default::default()
+    })));
+    assert!(!response.success);
+    assert_eq!(response.command.as_deref(), Some("gotoTargets"));
+
+    let response = router.handle(request(Command::LoadedSources));
+    assert!(!response.success);
+    assert_eq!(response.command.as_deref(), Some("loadedSources"));
+
+    drop(router);
+    assert_eq!(calls, 1);
+  }
+
+  #[test]
+  fn test_derived_capabilities() {
+    let mut router = Router::with_capabilities(Capabilities {
+      supports_conditional_breakpoints: Some(true),
+      supports_modules_request: Some(true),
+      ..Default::default()
+    });
+    router
+      .on::<SetFunctionBreakpoints, _, String>(|_| Ok(Default::default()))
+      .on::<Disassemble, _, String>(|_| Ok(Default::default()))
+      .on::<Goto, _, String>(|_| Ok(()));
+
+    let capabilities = initialize(&mut router);
+    assert_eq!(capabilities.supports_function_breakpoints, Some(true));
+    assert_eq!(capabilities.supports_disassemble_request, Some(true));
+    assert_eq!(capabilities.supports_conditional_breakpoints, Some(true));
+    // Not handled, so not advertised either.
+    assert_eq!(capabilities.supports_modules_request, None);
+    // Needs `gotoTargets` as well.
+    assert_eq!(capabilities.supports_goto_targets_request, None);
+
+    router.on::<GotoTargets, _, String>(|_| Ok(GotoTargetsResponse::default()));
+    assert_eq!(
+      router.capabilities().supports_goto_targets_request,
+      Some(true)
+    );
+
+    router.on::<Initialize, _, String>(|args| {
+      Ok(Capabilities {
+        supports_step_back: Some(true),
+        supports_ansi_styling: args.supports_ansi_styling,
+        ..Default::default()
+      })
+    });
+    let capabilities = initialize(&mut router);
+    assert_eq!(capabilities.supports_step_back, None);
+    assert_eq!(capabilities.supports_function_breakpoints, Some(true));
+    assert_eq!(capabilities.supports_conditional_breakpoints, None);
+  }
+}
@@ -0,0 +1,135 @@
+//! Validation of protocol messages against the DAP JSON schema.
+//!
+//! With the `schema_validation` feature enabled, [`ServerOutput::send`](crate::server::ServerOutput::send)
+//! validates every message before writing it and fails with [`ServerError::SchemaViolation`]
+//! instead of sending a message that breaks the specification.
+use std::collections::HashMap;
+
+use jsonschema::JSONSchema;
+use serde_json::{json, Value};
+
+use crate::errors::ServerError;
+
+const SCHEMA: &str =
+  include_str!("../schema/7f284b169ecd19602487eb4d290ae651d4398ce7_debugAdapterProtocol.json");
+
+/// Validates messages against the bundled DAP JSON schema.
+///
+/// Each message is validated against the most specific definition that matches it, e.g.
+/// `StoppedEvent` or `ThreadsResponse`. Definitions are compiled on first use and cached.
+pub struct SchemaValidator {
+  definitions: Value,
+  compiled: HashMap<String, JSONSchema>,
+}
+
+impl Default for SchemaValidator {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl SchemaValidator {
+  pub fn new() -> Self {
+    let schema: Value = serde_json::from_str(SCHEMA).expect("the bundled schema is valid JSON");
+    Self {
+      definitions: schema["definitions"].clone(),
+      compiled: HashMap::new(),
+    }
+  }
+
+  /// Validate a message as it is sent over the wire.
+  pub fn validate(&mut self, message: &Value) -> Result<(), ServerError> {
+    let definition = self.definition_for(message);
+    if !self.compiled.contains_key(&definition) {
+      // Compiling the definition as a reference into the whole set of definitions lets the
+      // validator resolve `$ref`s itself, including recursive ones such as `Source.sources`.
+      let schema = json!({
+        "$ref": format!("#/definitions/{}", definition),
+        "definitions": self.definitions,
+      });
+      let compiled = JSONSchema::compile(&schema).map_err(|e| ServerError::SchemaViolation {
+        definition: definition.clone(),
+        errors: vec![format!("could not compile the schema: {}", e)],
+      })?;
+      self.compiled.insert(definition.clone(), compiled);
+    }
+
+    let result = self.compiled[&definition].validate(message);
+    result.map_err(|errors| ServerError::SchemaViolation {
+      errors: errors
+        .map(|e| format!("at '{}': {}", e.instance_path, e))
+        .collect(),
+      definition,
+    })
+  }
+
+  fn definition_for(&self, message: &Value) -> String {
+    let (kind, name) = match message["type"].as_str() {
+      Some("response") if message["success"] == false => return "ErrorResponse".to_string(),
+      Some("response") => ("Response", &message["command"]),
+      Some("event") => ("Event", &message["event"]),
+      Some("request") => ("Request", &message["command"]),
+      _ => return "ProtocolMessage".to_string(),
+    };
+    let specific = format!("{}{}", capitalize(name.as_str().unwrap_or_default()), kind);
+    if self.definitions.get(&specific).is_some() {
+      specific
+    } else {
+      kind.to_string()
+    }
+  }
+}
+
+fn capitalize(name: &str) -> String {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_uppercase().chain(chars).collect(),
+    None => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_validate() {
+    let mut validator = SchemaValidator::new();
+    let stopped = json!({
+      "seq": 3, "type": "event", "event": "stopped",
+      "body": {"reason": "breakpoint", "threadId": 1}
+    });
+    assert!(validator.validate(&stopped).is_ok());
+
+    let missing_reason = json!({"seq": 3, "type": "event", "event": "stopped", "body": {}});
+    match validator.validate(&missing_reason) {
+      Err(ServerError::SchemaViolation { definition, errors }) => {
+        assert_eq!(definition, "StoppedEvent");
+        assert_eq!(errors.len(), 1);
+      }
+      other => panic!("unexpected result {:?}", other),
+    }
+
+    let threads = json!({
+      "seq": 4, "type": "response", "request_seq": 2, "success": true, "command": "threads",
+      "body": {"threads": [{"id": 1}]}
+    });
+    assert!(validator.validate(&threads).is_err());
+  }
+
+  #[test]
+  fn test_recursive_refs() {
+    let mut validator = SchemaValidator::new();
+    let loaded_sources = json!({
+      "seq": 5, "type": "response", "request_seq": 3, "success": true, "command": "loadedSources",
+      "body": {"sources": [{"name": "a.rs", "sources": [{"name": "b.rs", "sources": [{}]}]}]}
+    });
+    assert!(validator.validate(&loaded_sources).is_ok());
+
+    let invalid_nested = json!({
+      "seq": 6, "type": "response", "request_seq": 4, "success": true, "command": "loadedSources",
+      "body": {"sources": [{"sources": [{"sources": [{"name": 42}]}]}]}
+    });
+    assert!(validator.validate(&invalid_nested).is_err());
+  }
+}
@@ -1,24 +1,38 @@
+use std::collections::VecDeque;
 use std::fmt::Debug;
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::sync::{Arc, Mutex};
 
-use serde_json;
+use serde::Deserialize;
+use serde_json::{self, Value};
 
 use crate::{
   base_message::{BaseMessage, Sendable},
   errors::{DeserializationError, ServerError},
   events::Event,
-  requests::Request,
+  framing::read_frame,
+  recording::{Direction, Recorder},
+  requests::{Command, InitializeArguments, Request, RequestEnvelope},
   responses::Response,
-  reverse_requests::ReverseRequest,
+  reverse_requests::{
+    ReverseCommand, ReverseRequest, ReverseResponse, ReverseResponseBody,
+    RunInTerminalRequestArguments, RunInTerminalResponse, StartDebuggingRequestArguments,
+  },
 };
 
+#[cfg(feature = "schema_validation")]
+use crate::schema::SchemaValidator;
+
+pub use crate::framing::DEFAULT_MAX_CONTENT_LENGTH;
+
+/// A message read from the client: either a regular request or a response to one of our
+/// reverse requests.
 #[derive(Debug)]
-enum ServerState {
-  /// Expecting a header
-  Header,
-  /// Expecting content
-  Content,
+enum IncomingMessage {
+  Request(Request),
+  Response(ReverseResponse),
+  /// A request that could not be deserialized and has already been answered with an error.
+  Rejected,
 }
 
 /// Handles message encoding and decoding of messages.
@@ -28,6 +42,19 @@ enum ServerState {
 pub struct Server<R: Read, W: Write> {
   input_buffer: BufReader<R>,
 
+  /// Requests that arrived while waiting for the response to a reverse request. These are
+  /// handed out by `poll_request` before reading any further input.
+  pending_requests: VecDeque<Request>,
+
+  /// The arguments of the `initialize` request, once it has been received.
+  client_capabilities: Option<InitializeArguments>,
+
+  /// Records incoming messages, if recording is enabled.
+  recorder: Option<Arc<Recorder>>,
+
+  /// Messages with a larger `Content-Length` are rejected without reading them.
+  max_content_length: usize,
+
   /// A sharable `ServerOutput` object for sending messages and events from
   /// other threads.
   pub output: Arc<Mutex<ServerOutput<W>>>,
@@ -42,6 +69,9 @@ pub struct Server<R: Read, W: Write> {
 pub struct ServerOutput<W: Write> {
   output_buffer: BufWriter<W>,
   sequence_number: i64,
+  recorder: Option<Arc<Recorder>>,
+  #[cfg(feature = "schema_validation")]
+  validator: SchemaValidator,
 }
 
 impl<R: Read, W: Write> Server<R, W> {
@@ -50,74 +80,239 @@ impl<R: Read, W: Write> Server<R, W> {
     let server_output = Arc::new(Mutex::new(ServerOutput {
       output_buffer: output,
       sequence_number: 0,
+      recorder: None,
+      #[cfg(feature = "schema_validation")]
+      validator: SchemaValidator::new(),
     }));
 
     Self {
       input_buffer: input,
+      pending_requests: VecDeque::new(),
+      client_capabilities: None,
+      recorder: None,
+      max_content_length: DEFAULT_MAX_CONTENT_LENGTH,
       output: server_output,
     }
   }
 
+  /// Limit the size of incoming messages. A message with a larger `Content-Length` makes
+  /// [`poll_request`](Self::poll_request) fail with [`ServerError::ContentTooLarge`] before any
+  /// memory is allocated for it. The default is [`DEFAULT_MAX_CONTENT_LENGTH`].
+  pub fn set_max_content_length(&mut self, max_content_length: usize) {
+    self.max_content_length = max_content_length;
+  }
+
+  /// Record every incoming and outgoing message with the given recorder from now on.
+  pub fn set_recorder(&mut self, recorder: Recorder) -> Result<(), ServerError> {
+    let recorder = Arc::new(recorder);
+    self
+      .output
+      .lock()
+      .map_err(|_| ServerError::OutputLockError)?
+      .recorder = Some(recorder.clone());
+    self.recorder = Some(recorder);
+    Ok(())
+  }
+
+  /// The arguments the client sent with its `initialize` request, or `None` if the request has
+  /// not been received yet.
+  pub fn client_capabilities(&self) -> Option<&InitializeArguments> {
+    self.client_capabilities.as_ref()
+  }
+
   /// Wait for a request from the development tool
   ///
   /// This will start reading the `input` buffer that is passed to it and will try to interpret
   /// the incoming bytes according to the DAP protocol.
   pub fn poll_request(&mut self) -> Result<Option<Request>, ServerError> {
-    let mut state = ServerState::Header;
-    let mut buffer = String::new();
-    let mut content_length: usize = 0;
+    if let Some(request) = self.pending_requests.pop_front() {
+      return Ok(Some(request));
+    }
 
     loop {
-      match self.input_buffer.read_line(&mut buffer) {
-        Ok(read_size) => {
-          if read_size == 0 {
-            break Ok(None);
-          }
-          match state {
-            ServerState::Header => {
-              let parts: Vec<&str> = buffer.trim_end().split(':').collect();
-              if parts.len() == 2 {
-                match parts[0] {
-                  "Content-Length" => {
-                    content_length = match parts[1].trim().parse() {
-                      Ok(val) => val,
-                      Err(_) => return Err(ServerError::HeaderParseError { line: buffer }),
-                    };
-                    buffer.clear();
-                    buffer.reserve(content_length);
-                    state = ServerState::Content;
-                  }
-                  other => {
-                    return Err(ServerError::UnknownHeader {
-                      header: other.to_string(),
-                    })
-                  }
-                }
-              } else {
-                return Err(ServerError::HeaderParseError { line: buffer });
-              }
-            }
-            ServerState::Content => {
-              buffer.clear();
-              let mut content = vec![0; content_length];
-              self
-                .input_buffer
-                .read_exact(content.as_mut_slice())
-                .map_err(ServerError::IoError)?;
-
-              let content = std::str::from_utf8(content.as_slice())
-                .map_err(|e| ServerError::ParseError(DeserializationError::DecodingError(e)))?;
-              let request: Request = serde_json::from_str(content)
-                .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e)))?;
-              return Ok(Some(request));
-            }
-          }
+      match self.read_message()? {
+        Some(IncomingMessage::Request(request)) => return Ok(Some(request)),
+        // Nobody is waiting for this response anymore.
+        Some(IncomingMessage::Response(_)) | Some(IncomingMessage::Rejected) => continue,
+        None => return Ok(None),
+      }
+    }
+  }
+
+  /// Read a single message from the input and deserialize it.
+  fn read_message(&mut self) -> Result<Option<IncomingMessage>, ServerError> {
+    let content = match self.read_content()? {
+      Some(content) => content,
+      None => return Ok(None),
+    };
+
+    let value: Value = serde_json::from_str(&content)
+      .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e)))?;
+    if let Some(recorder) = &self.recorder {
+      recorder.record(Direction::Incoming, &value)?;
+    }
+    if value.get("type").and_then(Value::as_str) == Some("response") {
+      let response: ReverseResponse = serde_json::from_value(value)
+        .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e)))?;
+      return Ok(Some(IncomingMessage::Response(response)));
+    }
+
+    let request = match Request::deserialize(&value) {
+      Ok(request) => request,
+      Err(e) => return self.reject(&value, e),
+    };
+    if let Command::Initialize(args) = &request.command {
+      self.client_capabilities = Some(args.clone());
+    }
+    Ok(Some(IncomingMessage::Request(request)))
+  }
+
+  /// Answer a request that could not be deserialized with an error response carrying the
+  /// deserialization error, so the client isn't left waiting. Fails only if the message is not
+  /// recognizable as a request at all.
+  fn reject(
+    &mut self,
+    value: &Value,
+    error: serde_json::Error,
+  ) -> Result<Option<IncomingMessage>, ServerError> {
+    let envelope = match RequestEnvelope::deserialize(value) {
+      Ok(envelope) => envelope,
+      Err(_) => {
+        return Err(ServerError::ParseError(DeserializationError::SerdeError(
+          error,
+        )))
+      }
+    };
+    let response = envelope.error(&format!(
+      "Invalid '{}' request: {}",
+      envelope.command, error
+    ));
+    self
+      .output
+      .lock()
+      .map_err(|_| ServerError::OutputLockError)?
+      .respond(response)?;
+    Ok(Some(IncomingMessage::Rejected))
+  }
+
+  /// Read the header and the content of a single message. Returns the content as a string.
+  fn read_content(&mut self) -> Result<Option<String>, ServerError> {
+    let content = match read_frame(&mut self.input_buffer, self.max_content_length)? {
+      Some(content) => content,
+      None => return Ok(None),
+    };
+    String::from_utf8(content)
+      .map(Some)
+      .map_err(|e| ServerError::ParseError(DeserializationError::DecodingError(e.utf8_error())))
+  }
+
+  /// Block until the client responds to the reverse request with the given sequence number.
+  ///
+  /// Requests that arrive in the meantime are queued and returned by subsequent calls to
+  /// `poll_request`.
+  fn wait_for_response(&mut self, request_seq: i64) -> Result<ReverseResponse, ServerError> {
+    loop {
+      match self.read_message()? {
+        Some(IncomingMessage::Response(response)) if response.request_seq == request_seq => {
+          return Ok(response)
+        }
+        Some(IncomingMessage::Response(_)) | Some(IncomingMessage::Rejected) => continue,
+        Some(IncomingMessage::Request(request)) => self.pending_requests.push_back(request),
+        None => {
+          return Err(ServerError::IoError(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "input closed while waiting for a reverse response",
+          )))
         }
-        Err(e) => return Err(ServerError::IoError(e)),
       }
     }
   }
 
+  /// Run a command in a terminal provided by the client and wait for the result.
+  ///
+  /// This sends a `runInTerminal` reverse request and blocks until the client responds. The
+  /// `process_id` of the returned value is the PID of the debuggee (if the client could
+  /// determine it).
+  ///
+  /// If the client did not declare `supportsRunInTerminalRequest` in its `initialize` request,
+  /// the command is spawned locally by the adapter instead and `process_id` is the PID of the
+  /// spawned process. In that case, the process runs alongside the adapter, its standard streams
+  /// are inherited from it and it is waited for on a background thread, so it doesn't linger as a
+  /// zombie once it exits.
+  pub fn run_in_terminal(
+    &mut self,
+    args: RunInTerminalRequestArguments,
+  ) -> Result<RunInTerminalResponse, ServerError> {
+    let supported = self
+      .client_capabilities
+      .as_ref()
+      .and_then(|caps| caps.supports_run_in_terminal_request)
+      .unwrap_or(false);
+    if !supported {
+      return spawn_locally(&args);
+    }
+
+    let request_seq = {
+      let mut output = self
+        .output
+        .lock()
+        .map_err(|_| ServerError::OutputLockError)?;
+      output.send_reverse_command(ReverseCommand::RunInTerminal(args))?
+    };
+
+    let response = self.wait_for_response(request_seq)?;
+    match response.body {
+      Some(ReverseResponseBody::RunInTerminal(body)) if response.success => Ok(body),
+      _ => Err(ServerError::ReverseRequestFailed {
+        command: "runInTerminal".to_string(),
+        message: response.message.unwrap_or_default(),
+      }),
+    }
+  }
+
+  /// Ask the client to start a new debug session of the same type and wait for it to
+  /// acknowledge the request.
+  ///
+  /// The client will open a new connection to the adapter for the child session. Use a
+  /// [`SessionRegistry`](crate::sessions::SessionRegistry) to prepare `args` so that the child's
+  /// `launch` or `attach` request can be matched with the parent.
+  ///
+  /// Fails with [`ServerError::ReverseRequestUnsupported`] without sending anything if the client
+  /// did not declare `supportsStartDebuggingRequest` in its `initialize` request.
+  pub fn start_debugging(
+    &mut self,
+    args: StartDebuggingRequestArguments,
+  ) -> Result<(), ServerError> {
+    let supported = self
+      .client_capabilities
+      .as_ref()
+      .and_then(|caps| caps.supports_start_debugging_request)
+      .unwrap_or(false);
+    if !supported {
+      return Err(ServerError::ReverseRequestUnsupported {
+        command: "startDebugging".to_string(),
+      });
+    }
+
+    let request_seq = {
+      let mut output = self
+        .output
+        .lock()
+        .map_err(|_| ServerError::OutputLockError)?;
+      output.send_reverse_command(ReverseCommand::StartDebugging(args))?
+    };
+
+    let response = self.wait_for_response(request_seq)?;
+    if response.success {
+      Ok(())
+    } else {
+      Err(ServerError::ReverseRequestFailed {
+        command: "startDebugging".to_string(),
+        message: response.message.unwrap_or_default(),
+      })
+    }
+  }
+
   pub fn send(&mut self, body: Sendable) -> Result<(), ServerError> {
     let mut output = self
       .output
@@ -139,16 +334,62 @@ impl<R: Read, W: Write> Server<R, W> {
   }
 }
 
+/// Spawn the command described by `args` as a child process of the adapter.
+fn spawn_locally(
+  args: &RunInTerminalRequestArguments,
+) -> Result<RunInTerminalResponse, ServerError> {
+  let (program, program_args) = args.args.split_first().ok_or_else(|| {
+    ServerError::IoError(std::io::Error::new(
+      std::io::ErrorKind::InvalidInput,
+      "runInTerminal requires at least one argument",
+    ))
+  })?;
+
+  let mut command = std::process::Command::new(program);
+  command.args(program_args);
+  if !args.cwd.is_empty() {
+    command.current_dir(&args.cwd);
+  }
+  if let Some(env) = &args.env {
+    for (key, value) in env {
+      match value {
+        Some(value) => command.env(key, value),
+        None => command.env_remove(key),
+      };
+    }
+  }
+
+  let mut child = command.spawn().map_err(ServerError::IoError)?;
+  let process_id = child.id() as i64;
+  std::thread::spawn(move || child.wait());
+  Ok(RunInTerminalResponse {
+    process_id: Some(process_id),
+    ..Default::default()
+  })
+}
+
 impl<W: Write> ServerOutput<W> {
+  /// Send a message to the client.
+  ///
+  /// With the `schema_validation` feature, messages that do not conform to the specification are
+  /// not sent and a [`ServerError::SchemaViolation`] is returned instead.
   pub fn send(&mut self, body: Sendable) -> Result<(), ServerError> {
-    self.sequence_number += 1;
-
     let message = BaseMessage {
-      seq: self.sequence_number,
+      seq: self.sequence_number + 1,
       message: body,
     };
 
     let resp_json = serde_json::to_string(&message).map_err(ServerError::SerializationError)?;
+    #[cfg(feature = "schema_validation")]
+    {
+      let value = serde_json::from_str(&resp_json).map_err(ServerError::SerializationError)?;
+      self.validator.validate(&value)?;
+    }
+    self.sequence_number += 1;
+    if let Some(recorder) = &self.recorder {
+      let value = serde_json::from_str(&resp_json).map_err(ServerError::SerializationError)?;
+      recorder.record(Direction::Outgoing, &value)?;
+    }
     write!(
       self.output_buffer,
       "Content-Length: {}\r\n\r\n",
@@ -172,6 +413,14 @@ impl<W: Write> ServerOutput<W> {
   pub fn send_reverse_request(&mut self, request: ReverseRequest) -> Result<(), ServerError> {
     self.send(Sendable::ReverseRequest(request))
   }
+
+  /// Send a reverse request with the given command and return its sequence number, which the
+  /// client will refer to as `request_seq` in its response.
+  pub fn send_reverse_command(&mut self, command: ReverseCommand) -> Result<i64, ServerError> {
+    let seq = self.sequence_number + 1;
+    self.send_reverse_request(ReverseRequest { command })?;
+    Ok(seq)
+  }
 }
 
 #[cfg(test)]
@@ -239,4 +488,259 @@ mod tests {
       }
     ));
   }
+
+  fn frame(content: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{}", content.len(), content)
+  }
+
+  /// The messages the server has written so far.
+  fn written_messages<R: Read>(server: &Server<R, Vec<u8>>) -> Vec<Value> {
+    let output = server.output.lock().unwrap();
+    let written = String::from_utf8(output.output_buffer.get_ref().clone()).unwrap();
+    written
+      .split("Content-Length: ")
+      .filter_map(|message| message.split("\r\n\r\n").nth(1))
+      .map(|content| serde_json::from_str(content).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_run_in_terminal_round_trip() {
+    let input = [
+      frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\", \"supportsRunInTerminalRequest\": true}}"),
+      frame("{\"seq\": 2, \"type\": \"request\", \"command\": \"threads\"}"),
+      frame("{\"seq\": 3, \"type\": \"response\", \"request_seq\": 1, \"success\": true, \"command\": \"runInTerminal\", \"body\": {\"processId\": 4242, \"shellProcessId\": 4241}}"),
+    ]
+    .concat();
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+
+    let req = server.poll_request().unwrap().unwrap();
+    assert!(matches!(req.command, Command::Initialize(_)));
+
+    let rsp = server
+      .run_in_terminal(RunInTerminalRequestArguments {
+        args: vec!["/bin/true".to_string()],
+        ..Default::default()
+      })
+      .unwrap();
+    assert_eq!(rsp.process_id, Some(4242));
+    assert_eq!(rsp.shell_process_id, Some(4241));
+
+    // Sent as a request like any other, which the response refers to with its `request_seq`.
+    let messages = written_messages(&server);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["type"], "request");
+    assert_eq!(messages[0]["seq"], 1);
+    assert_eq!(messages[0]["command"], "runInTerminal");
+    assert_eq!(messages[0]["arguments"]["args"][0], "/bin/true");
+
+    // The request that arrived while waiting for the response is not lost.
+    let req = server.poll_request().unwrap().unwrap();
+    assert_eq!(req.seq, 2);
+    assert!(matches!(req.command, Command::Threads));
+    assert!(server.poll_request().unwrap().is_none());
+  }
+
+  #[test]
+  fn test_run_in_terminal_failure() {
+    let input = [
+      frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\", \"supportsRunInTerminalRequest\": true}}"),
+      frame("{\"seq\": 2, \"type\": \"response\", \"request_seq\": 1, \"success\": false, \"command\": \"runInTerminal\", \"message\": \"no terminal\"}"),
+    ]
+    .concat();
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+    server.poll_request().unwrap().unwrap();
+
+    let err = server
+      .run_in_terminal(RunInTerminalRequestArguments {
+        args: vec!["/bin/true".to_string()],
+        ..Default::default()
+      })
+      .unwrap_err();
+    assert!(
+      matches!(err, ServerError::ReverseRequestFailed { message, .. } if message == "no terminal")
+    );
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_run_in_terminal_local_fallback() {
+    let input = frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\"}}");
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+    server.poll_request().unwrap().unwrap();
+
+    let rsp = server
+      .run_in_terminal(RunInTerminalRequestArguments {
+        args: vec!["sh".to_string(), "-c".to_string(), "exit 0".to_string()],
+        ..Default::default()
+      })
+      .unwrap();
+    assert!(rsp.process_id.unwrap() > 0);
+    assert!(rsp.shell_process_id.is_none());
+  }
+
+  #[test]
+  fn test_start_debugging() {
+    let input = [
+      frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\", \"supportsStartDebuggingRequest\": true}}"),
+      frame("{\"seq\": 2, \"type\": \"response\", \"request_seq\": 1, \"success\": true, \"command\": \"startDebugging\"}"),
+    ]
+    .concat();
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+    server.poll_request().unwrap().unwrap();
+
+    server
+      .start_debugging(StartDebuggingRequestArguments {
+        configuration: Default::default(),
+        request: crate::types::StartDebuggingRequestKind::Launch,
+      })
+      .unwrap();
+  }
+
+  #[test]
+  fn test_start_debugging_unsupported() {
+    let input = frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\"}}");
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+    server.poll_request().unwrap().unwrap();
+
+    let err = server
+      .start_debugging(StartDebuggingRequestArguments {
+        configuration: Default::default(),
+        request: crate::types::StartDebuggingRequestKind::Launch,
+      })
+      .unwrap_err();
+    assert!(matches!(err, ServerError::ReverseRequestUnsupported { .. }));
+    assert!(written_messages(&server).is_empty());
+  }
+
+  #[test]
+  fn test_invalid_requests_are_answered() {
+    let input = [
+      frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"fromTheFuture\"}"),
+      frame("{\"seq\": 2, \"type\": \"request\", \"command\": \"next\", \"arguments\": {}}"),
+      frame("{\"seq\": 3, \"type\": \"request\", \"command\": \"threads\"}"),
+      frame("{\"type\": \"request\", \"command\": \"threads\"}"),
+    ]
+    .concat();
+    let mut server_in = Cursor::new(input.into_bytes());
+    let mut server = Server::new(BufReader::new(&mut server_in), BufWriter::new(Vec::new()));
+
+    // The invalid requests are answered and skipped.
+    let req = server.poll_request().unwrap().unwrap();
+    assert_eq!(req.seq, 3);
+    let responses = written_messages(&server);
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["request_seq"], 1);
+    assert_eq!(responses[0]["success"], false);
+    assert_eq!(responses[0]["command"], "fromTheFuture");
+    assert_eq!(responses[1]["request_seq"], 2);
+    assert_eq!(responses[1]["command"], "next");
+    let message = responses[1]["message"].as_str().unwrap();
+    assert!(
+      message.starts_with("Invalid 'next' request: "),
+      "{}",
+      message
+    );
+    assert!(message.contains("threadId"), "{}", message);
+
+    // Without a sequence number there is nobody to answer.
+    assert!(matches!(
+      server.poll_request(),
+      Err(ServerError::ParseError(_))
+    ));
+  }
+
+  fn poll(input: &[u8], max_content_length: Option<usize>) -> Result<Option<Request>, ServerError> {
+    let mut server = Server::new(BufReader::new(input), BufWriter::new(Vec::new()));
+    if let Some(max) = max_content_length {
+      server.set_max_content_length(max);
+    }
+    server.poll_request()
+  }
+
+  // The content used to be allocated with whatever size the header asked for, which aborts the
+  // process for large values.
+  #[test]
+  fn test_untrusted_content_length() {
+    for length in [
+      "18446744073709551615",
+      "9223372036854775807",
+      "1000000000000",
+    ] {
+      let input = format!("Content-Length: {}\r\n\r\n{{}}", length);
+      assert!(matches!(
+        poll(input.as_bytes(), None),
+        Err(ServerError::ContentTooLarge { .. })
+      ));
+    }
+
+    // Below the limit, a length that is larger than the input is an I/O error.
+    let result = poll(b"Content-Length: 1000000\r\n\r\n{}", None);
+    assert!(
+      matches!(result, Err(ServerError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof)
+    );
+
+    let request = frame("{\"seq\": 1, \"type\": \"request\", \"command\": \"threads\"}");
+    assert!(poll(request.as_bytes(), None).unwrap().is_some());
+    assert!(matches!(
+      poll(request.as_bytes(), Some(16)),
+      Err(ServerError::ContentTooLarge { max: 16, .. })
+    ));
+  }
+
+  #[test]
+  fn test_untrusted_header() {
+    let endless_header = vec![b'C'; 1024 * 1024];
+    assert!(matches!(
+      poll(&endless_header, None),
+      Err(ServerError::IoError(e)) if e.kind() == std::io::ErrorKind::InvalidData
+    ));
+    assert!(poll(b"Content-Length: -1\r\n\r\n", None).is_err());
+    assert!(poll(b"Content-Length: 2\r\n\r\n\xff\xfe", None).is_err());
+    assert!(poll(b"\xff\xfe\r\n", None).is_err());
+  }
+
+  #[cfg(feature = "schema_validation")]
+  #[test]
+  fn test_schema_validation() {
+    use crate::events::ThreadEventBody;
+    use crate::types::ThreadEventReason;
+
+    let mut output = ServerOutput {
+      output_buffer: BufWriter::new(Vec::new()),
+      sequence_number: 0,
+      recorder: None,
+      validator: SchemaValidator::new(),
+    };
+    output
+      .send_event(Event::Thread(ThreadEventBody {
+        reason: ThreadEventReason::Started,
+        thread_id: 1,
+      }))
+      .unwrap();
+    let written = output.output_buffer.get_ref().len();
+    assert!(written > 0);
+
+    // A successful response without a body lacks the `command` attribute.
+    let result = output.respond(Response {
+      request_seq: 1,
+      success: true,
+      ..Default::default()
+    });
+    match result {
+      Err(ServerError::SchemaViolation { definition, errors }) => {
+        assert_eq!(definition, "Response");
+        assert!(errors[0].contains("command"), "{:?}", errors);
+      }
+      other => panic!("unexpected result {:?}", other),
+    }
+    // Nothing was written and the sequence number was not used up.
+    assert_eq!(output.output_buffer.get_ref().len(), written);
+    assert_eq!(output.sequence_number, 1);
+  }
 }
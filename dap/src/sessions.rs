@@ -0,0 +1,314 @@
+//! Orchestration of child debug sessions started with the `startDebugging` reverse request.
+//!
+//! When an adapter asks the client to start a child session (e.g. for a subprocess), the client
+//! opens a *new* connection to the adapter and sends `initialize` followed by `launch` or `attach`
+//! with the `configuration` the parent passed along. The [`SessionRegistry`] correlates that
+//! connection with the parent: [`SessionRegistry::prepare_child`] stores a backend under a
+//! unique token and places the token into the configuration, and [`SessionRegistry::route`]
+//! looks the token up again when the child's `launch` or `attach` request arrives.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use crate::{
+  errors::SessionError, requests::Command, reverse_requests::StartDebuggingRequestArguments,
+  types::StartDebuggingRequestKind,
+};
+
+/// The key under which the session token is stored in the child's `configuration`.
+pub const SESSION_TOKEN_KEY: &str = "__sessionToken";
+
+/// Identifies a debug session within a [`SessionRegistry`].
+pub type SessionId = u64;
+
+/// A child session that was requested by a parent but whose connection has not arrived yet.
+struct PendingChild<B> {
+  parent: SessionId,
+  request: StartDebuggingRequestKind,
+  backend: B,
+}
+
+/// A child session that has been matched with its connection.
+#[derive(Debug)]
+pub struct ChildSession<B> {
+  /// The id of the new session.
+  pub id: SessionId,
+  /// The id of the session that requested this one.
+  pub parent: SessionId,
+  /// The backend that was registered for the child by the parent.
+  pub backend: B,
+}
+
+struct RegistryState<B> {
+  next_id: SessionId,
+  pending: HashMap<String, PendingChild<B>>,
+  parents: HashMap<SessionId, SessionId>,
+}
+
+/// Keeps track of the parent/child relationships between debug sessions.
+///
+/// The registry is meant to be shared (e.g. in an `Arc`) between the connections handled by an
+/// adapter, so all methods take `&self`.
+pub struct SessionRegistry<B> {
+  state: Mutex<RegistryState<B>>,
+}
+
+impl<B> Default for SessionRegistry<B> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<B> SessionRegistry<B> {
+  pub fn new() -> Self {
+    Self {
+      state: Mutex::new(RegistryState {
+        next_id: 1,
+        pending: HashMap::new(),
+        parents: HashMap::new(),
+      }),
+    }
+  }
+
+  fn lock(&self) -> Result<std::sync::MutexGuard<'_, RegistryState<B>>, SessionError> {
+    self.state.lock().map_err(|_| SessionError::LockError)
+  }
+
+  /// Register a new top-level session and return its id.
+  pub fn new_session(&self) -> Result<SessionId, SessionError> {
+    let mut state = self.lock()?;
+    let id = state.next_id;
+    state.next_id += 1;
+    Ok(id)
+  }
+
+  /// Register a child session of `parent` that will be served by `backend`.
+  ///
+  /// Returns the arguments for the `startDebugging` reverse request. The `configuration` is
+  /// passed through unchanged, except for the session token that is added under
+  /// [`SESSION_TOKEN_KEY`].
+  pub fn prepare_child(
+    &self,
+    parent: SessionId,
+    request: StartDebuggingRequestKind,
+    mut configuration: HashMap<String, Value>,
+    backend: B,
+  ) -> Result<StartDebuggingRequestArguments, SessionError> {
+    let mut state = self.lock()?;
+    let token = make_token(state.next_id);
+    state.next_id += 1;
+    state.pending.insert(
+      token.clone(),
+      PendingChild {
+        parent,
+        request: request.clone(),
+        backend,
+      },
+    );
+    configuration.insert(SESSION_TOKEN_KEY.to_string(), Value::String(token));
+    Ok(StartDebuggingRequestArguments {
+      configuration,
+      request,
+    })
+  }
+
+  /// Match an incoming `launch` or `attach` request with a pending child session.
+  ///
+  /// Returns `Ok(None)` if the command is not a `launch` or `attach` request or if it carries no
+  /// session token, i.e. it belongs to a top-level session. Otherwise the token is removed from
+  /// the request arguments and the child session is returned along with its backend.
+  pub fn route(&self, command: &mut Command) -> Result<Option<ChildSession<B>>, SessionError> {
+    let (additional_data, kind) = match command {
+      Command::Launch(args) => (&mut args.additional_data, StartDebuggingRequestKind::Launch),
+      Command::Attach(args) => (&mut args.additional_data, StartDebuggingRequestKind::Attach),
+      _ => return Ok(None),
+    };
+    let token = match additional_data
+      .as_mut()
+      .and_then(Value::as_object_mut)
+      .and_then(|data| data.remove(SESSION_TOKEN_KEY))
+    {
+      Some(Value::String(token)) => token,
+      _ => return Ok(None),
+    };
+
+    let mut state = self.lock()?;
+    let pending = state
+      .pending
+      .remove(&token)
+      .ok_or_else(|| SessionError::UnknownToken {
+        token: token.clone(),
+      })?;
+    if pending.request != kind {
+      let expected = match &pending.request {
+        StartDebuggingRequestKind::Launch => "launch".to_string(),
+        StartDebuggingRequestKind::Attach => "attach".to_string(),
+        StartDebuggingRequestKind::String(kind) => kind.clone(),
+      };
+      state.pending.insert(token.clone(), pending);
+      return Err(SessionError::RequestKindMismatch { token, expected });
+    }
+
+    let id = state.next_id;
+    state.next_id += 1;
+    state.parents.insert(id, pending.parent);
+    Ok(Some(ChildSession {
+      id,
+      parent: pending.parent,
+      backend: pending.backend,
+    }))
+  }
+
+  /// The parent of the given session, if it is a child session.
+  pub fn parent(&self, session: SessionId) -> Result<Option<SessionId>, SessionError> {
+    Ok(self.lock()?.parents.get(&session).copied())
+  }
+
+  /// The child sessions of the given session that have been routed so far.
+  pub fn children(&self, session: SessionId) -> Result<Vec<SessionId>, SessionError> {
+    let state = self.lock()?;
+    let mut children: Vec<_> = state
+      .parents
+      .iter()
+      .filter(|(_, parent)| **parent == session)
+      .map(|(child, _)| *child)
+      .collect();
+    children.sort_unstable();
+    Ok(children)
+  }
+
+  /// Remove a session from the registry. Child sessions that the session requested but that
+  /// never connected are dropped as well. The ones that did connect are left running, so they
+  /// become top-level sessions.
+  pub fn end_session(&self, session: SessionId) -> Result<(), SessionError> {
+    let mut state = self.lock()?;
+    state
+      .parents
+      .retain(|child, parent| *child != session && *parent != session);
+    state.pending.retain(|_, pending| pending.parent != session);
+    Ok(())
+  }
+}
+
+/// Create a token that is unique within a registry and unlikely to collide across adapter
+/// processes.
+fn make_token(id: SessionId) -> String {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.subsec_nanos())
+    .unwrap_or_default();
+  format!("{:x}-{:08x}-{}", std::process::id(), nanos, id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::requests::Request;
+
+  fn launch_with(configuration: &HashMap<String, Value>) -> Request {
+    let value = serde_json::json!({
+      "seq": 1,
+      "type": "request",
+      "command": "launch",
+      "arguments": configuration,
+    });
+    serde_json::from_value(value).unwrap()
+  }
+
+  #[test]
+  fn test_route_child_launch() {
+    let registry = SessionRegistry::new();
+    let parent = registry.new_session().unwrap();
+
+    let mut configuration = HashMap::new();
+    configuration.insert("program".to_string(), Value::String("worker".to_string()));
+    let args = registry
+      .prepare_child(
+        parent,
+        StartDebuggingRequestKind::Launch,
+        configuration,
+        "worker backend",
+      )
+      .unwrap();
+    assert!(args.configuration.contains_key(SESSION_TOKEN_KEY));
+
+    let mut req = launch_with(&args.configuration);
+    let child = registry.route(&mut req.command).unwrap().unwrap();
+    assert_eq!(child.parent, parent);
+    assert_eq!(child.backend, "worker backend");
+    assert_eq!(registry.parent(child.id).unwrap(), Some(parent));
+    assert_eq!(registry.children(parent).unwrap(), vec![child.id]);
+
+    // The token is stripped, the rest of the configuration is left alone.
+    let data = match &req.command {
+      Command::Launch(launch) => launch.additional_data.as_ref().unwrap(),
+      _ => panic!("not a launch request"),
+    };
+    assert!(data.get(SESSION_TOKEN_KEY).is_none());
+    assert_eq!(data.get("program").unwrap(), "worker");
+
+    // A token can only be claimed once.
+    let mut req = launch_with(&args.configuration);
+    assert!(matches!(
+      registry.route(&mut req.command),
+      Err(SessionError::UnknownToken { .. })
+    ));
+
+    // Ending the parent leaves its children without one.
+    let args = registry
+      .prepare_child(
+        parent,
+        StartDebuggingRequestKind::Launch,
+        HashMap::new(),
+        "other backend",
+      )
+      .unwrap();
+    let mut req = launch_with(&args.configuration);
+    let other = registry.route(&mut req.command).unwrap().unwrap();
+    registry.end_session(parent).unwrap();
+    assert_eq!(registry.parent(child.id).unwrap(), None);
+    assert_eq!(registry.parent(other.id).unwrap(), None);
+    assert!(registry.children(parent).unwrap().is_empty());
+  }
+
+  #[test]
+  fn test_route_top_level_session() {
+    let registry: SessionRegistry<()> = SessionRegistry::new();
+    let mut req = launch_with(&HashMap::new());
+    assert!(registry.route(&mut req.command).unwrap().is_none());
+    assert!(registry
+      .route(&mut Command::ConfigurationDone)
+      .unwrap()
+      .is_none());
+  }
+
+  #[test]
+  fn test_route_kind_mismatch() {
+    let registry = SessionRegistry::new();
+    let parent = registry.new_session().unwrap();
+    let args = registry
+      .prepare_child(
+        parent,
+        StartDebuggingRequestKind::Attach,
+        HashMap::new(),
+        (),
+      )
+      .unwrap();
+
+    let mut req = launch_with(&args.configuration);
+    assert!(matches!(
+      registry.route(&mut req.command),
+      Err(SessionError::RequestKindMismatch { .. })
+    ));
+
+    // Ending the parent drops the child that never connected.
+    registry.end_session(parent).unwrap();
+    let mut req = launch_with(&args.configuration);
+    assert!(matches!(
+      registry.route(&mut req.command),
+      Err(SessionError::UnknownToken { .. })
+    ));
+  }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{errors::ServerError, server::Server};
+
+/// How long to wait before accepting again after an error such as running out of file
+/// descriptors.
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The connections that are currently being served, keyed by an internal connection id.
+type Connections = Arc<Mutex<HashMap<u64, TcpStream>>>;
+
+/// Serves DAP sessions over TCP, e.g. for clients that connect to a `debugServer` port.
+///
+/// Every accepted connection gets its own [`Server`] which is passed to the session handler on a
+/// dedicated thread, so sessions are fully independent of each other.
+pub struct TcpServer {
+  listener: TcpListener,
+  max_sessions: Option<usize>,
+  shutdown: Arc<AtomicBool>,
+  connections: Connections,
+}
+
+/// A handle that can be used to stop a running [`TcpServer`] from another thread.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+  addr: SocketAddr,
+  shutdown: Arc<AtomicBool>,
+  connections: Connections,
+}
+
+impl TcpServer {
+  /// Listen for connections on the given address. Use port 0 to let the operating system pick a
+  /// free port (see [`TcpServer::local_addr`]).
+  pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, ServerError> {
+    let listener = TcpListener::bind(addr).map_err(ServerError::IoError)?;
+    Ok(Self {
+      listener,
+      max_sessions: None,
+      shutdown: Arc::new(AtomicBool::new(false)),
+      connections: Arc::new(Mutex::new(HashMap::new())),
+    })
+  }
+
+  /// Limit the number of sessions that are served concurrently. Connections that arrive while
+  /// the limit is reached are closed immediately.
+  pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+    self.max_sessions = Some(max_sessions);
+    self
+  }
+
+  /// The address the server is listening on.
+  pub fn local_addr(&self) -> Result<SocketAddr, ServerError> {
+    self.listener.local_addr().map_err(ServerError::IoError)
+  }
+
+  /// Create a handle for stopping the server.
+  pub fn shutdown_handle(&self) -> Result<ShutdownHandle, ServerError> {
+    Ok(ShutdownHandle {
+      addr: self.local_addr()?,
+      shutdown: self.shutdown.clone(),
+      connections: self.connections.clone(),
+    })
+  }
+
+  /// Accept connections and serve each of them with `handler` on a separate thread.
+  ///
+  /// This blocks until [`ShutdownHandle::shutdown`] is called, then waits for all running
+  /// sessions to finish before returning. Failing to accept a connection doesn't stop the server;
+  /// after errors such as running out of file descriptors it waits a moment before accepting
+  /// again.
+  pub fn serve<F>(&self, handler: F) -> Result<(), ServerError>
+  where
+    F: Fn(Server<TcpStream, TcpStream>) + Send + Sync + 'static,
+  {
+    let handler = Arc::new(handler);
+    let mut sessions: HashMap<u64, JoinHandle<()>> = HashMap::new();
+    let result = self.accept(&handler, &mut sessions);
+    for (_, session) in sessions {
+      let _ = session.join();
+    }
+    result
+  }
+
+  fn accept<F>(
+    &self,
+    handler: &Arc<F>,
+    sessions: &mut HashMap<u64, JoinHandle<()>>,
+  ) -> Result<(), ServerError>
+  where
+    F: Fn(Server<TcpStream, TcpStream>) + Send + Sync + 'static,
+  {
+    let mut next_id: u64 = 0;
+
+    for stream in self.listener.incoming() {
+      if self.shutdown.load(Ordering::SeqCst) {
+        break;
+      }
+      let stream = match stream {
+        Ok(stream) => stream,
+        Err(e) if is_connection_error(&e) => continue,
+        // Most likely out of file descriptors or memory. Back off so running sessions get a
+        // chance to finish, instead of spinning on the same error.
+        Err(_) => {
+          thread::sleep(ACCEPT_ERROR_BACKOFF);
+          continue;
+        }
+      };
+
+      // Sessions remove themselves from `connections` when they are done.
+      let active = {
+        let connections = self
+          .connections
+          .lock()
+          .map_err(|_| ServerError::ConnectionsLockError)?;
+        sessions.retain(|id, _| connections.contains_key(id));
+        connections.len()
+      };
+      if let Some(max_sessions) = self.max_sessions {
+        if active >= max_sessions {
+          let _ = stream.shutdown(Shutdown::Both);
+          continue;
+        }
+      }
+
+      let id = next_id;
+      next_id += 1;
+      match self.start_session(id, stream, handler.clone()) {
+        Ok(session) => {
+          sessions.insert(id, session);
+        }
+        // The client went away before we could set up the session.
+        Err(_) => continue,
+      }
+    }
+    Ok(())
+  }
+
+  fn start_session<F>(
+    &self,
+    id: u64,
+    stream: TcpStream,
+    handler: Arc<F>,
+  ) -> Result<JoinHandle<()>, ServerError>
+  where
+    F: Fn(Server<TcpStream, TcpStream>) + Send + Sync + 'static,
+  {
+    let input = stream.try_clone().map_err(ServerError::IoError)?;
+    let control = stream.try_clone().map_err(ServerError::IoError)?;
+    self
+      .connections
+      .lock()
+      .map_err(|_| ServerError::ConnectionsLockError)?
+      .insert(id, control);
+    // `shutdown` may have run between accepting this connection and registering it above, in
+    // which case it didn't close this one.
+    if self.shutdown.load(Ordering::SeqCst) {
+      let _ = stream.shutdown(Shutdown::Read);
+    }
+
+    let registration = Registration {
+      id,
+      connections: self.connections.clone(),
+    };
+    Ok(thread::spawn(move || {
+      let _registration = registration;
+      handler(Server::new(BufReader::new(input), BufWriter::new(stream)));
+    }))
+  }
+}
+
+/// Removes a session from the connections when its thread ends, even if the handler panics, so
+/// that it doesn't hold on to a slot of `max_sessions`.
+struct Registration {
+  id: u64,
+  connections: Connections,
+}
+
+impl Drop for Registration {
+  fn drop(&mut self) {
+    if let Ok(mut connections) = self.connections.lock() {
+      connections.remove(&self.id);
+    }
+  }
+}
+
+impl ShutdownHandle {
+  /// Stop accepting new connections.
+  ///
+  /// Running sessions are not killed: the incoming half of their connection is closed, so
+  /// `poll_request` returns `None` and the session handler can wind down (and still send final
+  /// messages such as a `terminated` event).
+  pub fn shutdown(&self) {
+    self.shutdown.store(true, Ordering::SeqCst);
+    if let Ok(connections) = self.connections.lock() {
+      for stream in connections.values() {
+        let _ = stream.shutdown(Shutdown::Read);
+      }
+    }
+    // Wake up the accept loop, which is blocked waiting for a connection. An unspecified
+    // address such as `0.0.0.0` can't be connected to, the loopback address of the same family
+    // reaches the listener as well.
+    let mut addr = self.addr;
+    if addr.ip().is_unspecified() {
+      addr.set_ip(match addr {
+        SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+        SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+      });
+    }
+    let _ = TcpStream::connect(addr);
+  }
+}
+
+/// Errors that only concern the connection that was being accepted.
+fn is_connection_error(e: &std::io::Error) -> bool {
+  matches!(
+    e.kind(),
+    std::io::ErrorKind::Interrupted
+      | std::io::ErrorKind::ConnectionAborted
+      | std::io::ErrorKind::ConnectionRefused
+      | std::io::ErrorKind::ConnectionReset
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{Read, Write};
+
+  use super::*;
+  use crate::{
+    events::Event,
+    framing::{read_frame, DEFAULT_MAX_CONTENT_LENGTH},
+    requests::Command,
+  };
+
+  const INITIALIZE: &str = "{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\"}}";
+
+  fn echo_session(mut server: Server<TcpStream, TcpStream>) {
+    while let Ok(Some(req)) = server.poll_request() {
+      if let Command::Initialize(_) = req.command {
+        server.send_event(Event::Initialized).unwrap();
+      }
+    }
+  }
+
+  fn read_message(reader: &mut BufReader<TcpStream>) -> String {
+    let content = read_frame(reader, DEFAULT_MAX_CONTENT_LENGTH)
+      .unwrap()
+      .unwrap();
+    String::from_utf8(content).unwrap()
+  }
+
+  fn initialize(stream: &mut TcpStream) -> String {
+    write!(
+      stream,
+      "Content-Length: {}\r\n\r\n{}",
+      INITIALIZE.len(),
+      INITIALIZE
+    )
+    .unwrap();
+    read_message(&mut BufReader::new(stream.try_clone().unwrap()))
+  }
+
+  #[test]
+  fn test_concurrent_sessions() {
+    let server = TcpServer::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = server.shutdown_handle().unwrap();
+    let serving = thread::spawn(move || server.serve(echo_session));
+
+    let mut first = TcpStream::connect(addr).unwrap();
+    let mut second = TcpStream::connect(addr).unwrap();
+    assert!(initialize(&mut second).contains("\"initialized\""));
+    assert!(initialize(&mut first).contains("\"initialized\""));
+
+    handle.shutdown();
+    serving.join().unwrap().unwrap();
+  }
+
+  #[test]
+  fn test_max_sessions() {
+    let server = TcpServer::bind("127.0.0.1:0").unwrap().with_max_sessions(1);
+    let addr = server.local_addr().unwrap();
+    let handle = server.shutdown_handle().unwrap();
+    let serving = thread::spawn(move || server.serve(echo_session));
+
+    let mut first = TcpStream::connect(addr).unwrap();
+    assert!(initialize(&mut first).contains("\"initialized\""));
+
+    // The second connection is over the limit and gets closed right away.
+    let mut second = TcpStream::connect(addr).unwrap();
+    second
+      .set_read_timeout(Some(Duration::from_secs(5)))
+      .unwrap();
+    let mut buf = [0; 1];
+    assert_eq!(second.read(&mut buf).unwrap(), 0);
+
+    handle.shutdown();
+    serving.join().unwrap().unwrap();
+  }
+
+  #[test]
+  fn test_panicking_session_frees_its_slot() {
+    let crashed = AtomicBool::new(false);
+    let server = TcpServer::bind("127.0.0.1:0").unwrap().with_max_sessions(1);
+    let addr = server.local_addr().unwrap();
+    let handle = server.shutdown_handle().unwrap();
+    let serving = thread::spawn(move || {
+      server.serve(move |server| {
+        if !crashed.swap(true, Ordering::SeqCst) {
+          panic!("the first session crashes");
+        }
+        echo_session(server)
+      })
+    });
+
+    // The connection is closed once the session is removed from the connections.
+    let mut first = TcpStream::connect(addr).unwrap();
+    first
+      .set_read_timeout(Some(Duration::from_secs(5)))
+      .unwrap();
+    let mut buf = [0; 1];
+    assert_eq!(first.read(&mut buf).unwrap(), 0);
+
+    let mut second = TcpStream::connect(addr).unwrap();
+    assert!(initialize(&mut second).contains("\"initialized\""));
+
+    handle.shutdown();
+    serving.join().unwrap().unwrap();
+  }
+
+  #[test]
+  fn test_shutdown_unspecified_address() {
+    let server = TcpServer::bind("0.0.0.0:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let handle = server.shutdown_handle().unwrap();
+    let serving = thread::spawn(move || server.serve(echo_session));
+
+    let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    assert!(initialize(&mut client).contains("\"initialized\""));
+
+    handle.shutdown();
+    serving.join().unwrap().unwrap();
+  }
+}
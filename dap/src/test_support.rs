@@ -0,0 +1,519 @@
+//! Support for testing adapters without an editor.
+//!
+//! A [`TestClient`] runs an adapter on a [`Server`] connected through an in-memory pipe and
+//! plays the client side of a conversation with it:
+//!
+//! ```ignore
+//! let mut client = TestClient::spawn(|server| my_adapter(server));
+//! client
+//!   .send("initialize", json!({"adapterID": "test"}))
+//!   .expect_response("initialize", |body| body["supportsConfigurationDoneRequest"] == true)
+//!   .expect_event("initialized")
+//!   .send("setBreakpoints", json!({"source": {"path": "main.rs"}, "breakpoints": [{"line": 3}]}))
+//!   .expect_message(json!({"type": "response", "body": {"breakpoints": [{"verified": true}]}}));
+//! client.finish();
+//! ```
+//!
+//! The `expect_*` methods panic with a readable description of the mismatch, so they can be
+//! used like assertions.
+use std::collections::HashMap;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::{
+  framing::{read_message, write_message, DEFAULT_MAX_CONTENT_LENGTH},
+  server::Server,
+};
+
+/// How long `expect_*` methods wait for the next message by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Create a unidirectional in-memory pipe. Reading blocks until data is written; once the
+/// writer is dropped, the reader returns end of file.
+pub fn pipe() -> (PipeWriter, PipeReader) {
+  let (sender, receiver) = channel();
+  (
+    PipeWriter { sender },
+    PipeReader {
+      receiver,
+      pending: Vec::new(),
+      position: 0,
+    },
+  )
+}
+
+/// One end of a [`duplex`] connection.
+pub struct Endpoint {
+  pub reader: PipeReader,
+  pub writer: PipeWriter,
+}
+
+impl Endpoint {
+  /// Create a [`Server`] that communicates through this endpoint.
+  pub fn into_server(self) -> Server<PipeReader, PipeWriter> {
+    Server::new(BufReader::new(self.reader), BufWriter::new(self.writer))
+  }
+}
+
+/// Create a bidirectional in-memory connection. Whatever is written to one endpoint can be read
+/// from the other one.
+pub fn duplex() -> (Endpoint, Endpoint) {
+  let (a_writer, b_reader) = pipe();
+  let (b_writer, a_reader) = pipe();
+  (
+    Endpoint {
+      reader: a_reader,
+      writer: a_writer,
+    },
+    Endpoint {
+      reader: b_reader,
+      writer: b_writer,
+    },
+  )
+}
+
+/// The writing end of a [`pipe`].
+pub struct PipeWriter {
+  sender: Sender<Vec<u8>>,
+}
+
+impl Write for PipeWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self
+      .sender
+      .send(buf.to_vec())
+      .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "pipe reader was dropped"))?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// The reading end of a [`pipe`].
+pub struct PipeReader {
+  receiver: Receiver<Vec<u8>>,
+  pending: Vec<u8>,
+  position: usize,
+}
+
+impl Read for PipeReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    while self.position == self.pending.len() {
+      match self.receiver.recv() {
+        Ok(data) => {
+          self.pending = data;
+          self.position = 0;
+        }
+        Err(_) => return Ok(0),
+      }
+    }
+    let size = buf.len().min(self.pending.len() - self.position);
+    buf[..size].copy_from_slice(&self.pending[self.position..self.position + size]);
+    self.position += size;
+    Ok(size)
+  }
+}
+
+/// Plays the client side of a scripted conversation with an adapter.
+pub struct TestClient {
+  writer: Option<PipeWriter>,
+  messages: Receiver<Result<Value, String>>,
+  adapter: Option<JoinHandle<()>>,
+  reader: Option<JoinHandle<()>>,
+  timeout: Duration,
+  seq: i64,
+  /// The commands of the requests sent so far, by sequence number. Responses only carry the
+  /// command when they succeed.
+  commands: HashMap<i64, String>,
+  last_reverse_request: Option<Value>,
+}
+
+impl TestClient {
+  /// Run `adapter` on a new thread, with a [`Server`] connected to this client.
+  pub fn spawn<F>(adapter: F) -> Self
+  where
+    F: FnOnce(Server<PipeReader, PipeWriter>) + Send + 'static,
+  {
+    let (client, server) = duplex();
+    let adapter = thread::spawn(move || adapter(server.into_server()));
+
+    let (sender, messages) = channel();
+    let input = client.reader;
+    let reader = thread::spawn(move || {
+      let mut input = BufReader::new(input);
+      loop {
+        match read_message(&mut input, DEFAULT_MAX_CONTENT_LENGTH) {
+          Ok(Some(message)) => {
+            if sender.send(Ok(message)).is_err() {
+              return;
+            }
+          }
+          Ok(None) => return,
+          Err(e) => {
+            let _ = sender.send(Err(e.to_string()));
+            return;
+          }
+        }
+      }
+    });
+
+    Self {
+      writer: Some(client.writer),
+      messages,
+      adapter: Some(adapter),
+      reader: Some(reader),
+      timeout: DEFAULT_TIMEOUT,
+      seq: 0,
+      commands: HashMap::new(),
+      last_reverse_request: None,
+    }
+  }
+
+  /// Change how long `expect_*` methods wait for the next message.
+  pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Send a request. `arguments` is omitted from the message if it is `null`.
+  pub fn send(&mut self, command: &str, arguments: Value) -> &mut Self {
+    self.seq += 1;
+    self.commands.insert(self.seq, command.to_string());
+    let mut request = json!({"seq": self.seq, "type": "request", "command": command});
+    if !arguments.is_null() {
+      request["arguments"] = arguments;
+    }
+    self.send_raw(request)
+  }
+
+  /// Send a message as it is. Its `seq` is not checked or updated.
+  pub fn send_raw(&mut self, message: Value) -> &mut Self {
+    let writer = self
+      .writer
+      .as_mut()
+      .expect("The connection to the adapter is already closed");
+    write_message(writer, &message).expect("The adapter closed the connection");
+    self
+  }
+
+  /// Wait for the next message and return it.
+  pub fn next_message(&mut self) -> Value {
+    match self.messages.recv_timeout(self.timeout) {
+      Ok(Ok(message)) => message,
+      Ok(Err(e)) => panic!("Could not read the adapter's output: {}", e),
+      Err(RecvTimeoutError::Timeout) => {
+        panic!("Timed out after {:?} waiting for a message", self.timeout)
+      }
+      Err(RecvTimeoutError::Disconnected) => {
+        panic!("The adapter closed the connection while a message was expected")
+      }
+    }
+  }
+
+  /// Expect the next message to contain everything in `expected`. Objects in `expected` may
+  /// leave out fields; arrays and other values must match exactly.
+  pub fn expect_message(&mut self, expected: Value) -> &mut Self {
+    let actual = self.next_message();
+    let mut differences = Vec::new();
+    diff(&expected, &actual, "$", &mut differences);
+    if !differences.is_empty() {
+      panic!(
+        "Unexpected message:\n{}\n\nDifferences:\n  {}",
+        pretty(&actual),
+        differences.join("\n  ")
+      );
+    }
+    self
+  }
+
+  /// Expect a successful response to `command` whose body satisfies `predicate`. The body is
+  /// `null` if the response has none.
+  pub fn expect_response<P: FnOnce(&Value) -> bool>(
+    &mut self,
+    command: &str,
+    predicate: P,
+  ) -> &mut Self {
+    let response = self.expect_response_to(command);
+    if response["success"] != true {
+      panic!("Expected a successful response:\n{}", pretty(&response));
+    }
+    if !predicate(&response["body"]) {
+      panic!(
+        "The body of the '{}' response does not match:\n{}",
+        command,
+        pretty(&response["body"])
+      );
+    }
+    self
+  }
+
+  /// Expect a failed response to `command`.
+  pub fn expect_error_response(&mut self, command: &str) -> &mut Self {
+    let response = self.expect_response_to(command);
+    if response["success"] != false {
+      panic!("Expected a failed response:\n{}", pretty(&response));
+    }
+    self
+  }
+
+  /// Expect an event.
+  pub fn expect_event(&mut self, event: &str) -> &mut Self {
+    self.expect_event_matching(event, |_| true)
+  }
+
+  /// Expect an event whose body satisfies `predicate`. The body is `null` if the event has none.
+  pub fn expect_event_matching<P: FnOnce(&Value) -> bool>(
+    &mut self,
+    event: &str,
+    predicate: P,
+  ) -> &mut Self {
+    let message = self.next_message();
+    if message["type"] != "event" || message["event"] != event {
+      panic!("Expected event '{}', got:\n{}", event, pretty(&message));
+    }
+    if !predicate(&message["body"]) {
+      panic!(
+        "The body of the '{}' event does not match:\n{}",
+        event,
+        pretty(&message["body"])
+      );
+    }
+    self
+  }
+
+  /// Expect a reverse request, e.g. `runInTerminal`. Answer it with [`TestClient::respond`].
+  pub fn expect_reverse_request(&mut self, command: &str) -> &mut Self {
+    let request = self.next_message();
+    if request["type"] != "request" || request["command"] != command {
+      panic!("Expected request '{}', got:\n{}", command, pretty(&request));
+    }
+    self.last_reverse_request = Some(request);
+    self
+  }
+
+  /// Send a successful response to the last reverse request. `body` is omitted if it is `null`.
+  pub fn respond(&mut self, body: Value) -> &mut Self {
+    let request = self
+      .last_reverse_request
+      .take()
+      .expect("There is no reverse request to respond to");
+    self.seq += 1;
+    let mut response = json!({
+      "seq": self.seq,
+      "type": "response",
+      "request_seq": request["seq"],
+      "command": request["command"],
+      "success": true,
+    });
+    if !body.is_null() {
+      response["body"] = body;
+    }
+    self.send_raw(response)
+  }
+
+  /// Close the connection and wait for the adapter to return. Panics if the adapter panicked or
+  /// sent messages that were not expected.
+  pub fn finish(mut self) {
+    self.writer = None;
+    if let Some(adapter) = self.adapter.take() {
+      if adapter.join().is_err() {
+        panic!("The adapter panicked");
+      }
+    }
+    if let Some(reader) = self.reader.take() {
+      let _ = reader.join();
+    }
+    let unexpected: Vec<String> = self
+      .messages
+      .try_iter()
+      .map(|message| match message {
+        Ok(message) => pretty(&message),
+        Err(e) => e,
+      })
+      .collect();
+    if !unexpected.is_empty() {
+      panic!("Unexpected messages:\n{}", unexpected.join("\n"));
+    }
+  }
+
+  fn expect_response_to(&mut self, command: &str) -> Value {
+    let message = self.next_message();
+    let responds_to = message["request_seq"]
+      .as_i64()
+      .and_then(|seq| self.commands.get(&seq));
+    if message["type"] != "response" || responds_to.map(String::as_str) != Some(command) {
+      panic!(
+        "Expected response to '{}', got:\n{}",
+        command,
+        pretty(&message)
+      );
+    }
+    message
+  }
+}
+
+/// Collect the places where `actual` does not contain `expected`.
+fn diff(expected: &Value, actual: &Value, path: &str, differences: &mut Vec<String>) {
+  match (expected, actual) {
+    (Value::Object(expected), Value::Object(actual)) => {
+      for (key, expected) in expected {
+        let path = format!("{}.{}", path, key);
+        match actual.get(key) {
+          Some(actual) => diff(expected, actual, &path, differences),
+          None => differences.push(format!(
+            "{}: expected {}, but it is missing",
+            path, expected
+          )),
+        }
+      }
+    }
+    (Value::Array(expected_items), Value::Array(actual_items))
+      if expected_items.len() == actual_items.len() =>
+    {
+      for (index, (expected, actual)) in expected_items.iter().zip(actual_items).enumerate() {
+        diff(
+          expected,
+          actual,
+          &format!("{}[{}]", path, index),
+          differences,
+        );
+      }
+    }
+    _ if expected != actual => {
+      differences.push(format!("{}: expected {}, got {}", path, expected, actual))
+    }
+    _ => {}
+  }
+}
+
+fn pretty(value: &Value) -> String {
+  serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    events::Event,
+    requests::Command,
+    responses::{ResponseBody, SetBreakpointsResponse},
+    reverse_requests::StartDebuggingRequestArguments,
+    types::{Breakpoint, Capabilities, StartDebuggingRequestKind},
+  };
+
+  fn adapter<R: Read, W: Write>(mut server: Server<R, W>) {
+    while let Some(req) = server.poll_request().unwrap() {
+      match &req.command {
+        Command::Initialize(_) => {
+          let capabilities = Capabilities {
+            supports_configuration_done_request: Some(true),
+            ..Default::default()
+          };
+          server
+            .respond(req.success(ResponseBody::Initialize(capabilities)))
+            .unwrap();
+          server.send_event(Event::Initialized).unwrap();
+        }
+        Command::SetBreakpoints(args) => {
+          let breakpoints = args
+            .breakpoints
+            .iter()
+            .flatten()
+            .map(|bp| Breakpoint {
+              verified: true,
+              line: Some(bp.line),
+              ..Default::default()
+            })
+            .collect();
+          server
+            .respond(
+              req.success(ResponseBody::SetBreakpoints(SetBreakpointsResponse {
+                breakpoints,
+              })),
+            )
+            .unwrap();
+        }
+        _ => server.respond(req.error("Not supported")).unwrap(),
+      }
+    }
+  }
+
+  #[test]
+  fn test_conversation() {
+    let mut client = TestClient::spawn(adapter);
+    client
+      .send("initialize", json!({"adapterID": "test"}))
+      .expect_response("initialize", |body| {
+        body["supportsConfigurationDoneRequest"] == true
+      })
+      .expect_event("initialized")
+      .send(
+        "setBreakpoints",
+        json!({"source": {"path": "main.rs"}, "breakpoints": [{"line": 3}]}),
+      )
+      .expect_message(json!({
+        "type": "response",
+        "request_seq": 2,
+        "body": {"breakpoints": [{"verified": true, "line": 3}]}
+      }))
+      .send("threads", Value::Null)
+      .expect_error_response("threads");
+    client.finish();
+  }
+
+  #[test]
+  #[should_panic(expected = "$.body.breakpoints[0].line: expected 4, got 3")]
+  fn test_mismatch() {
+    let mut client = TestClient::spawn(adapter);
+    client
+      .send(
+        "setBreakpoints",
+        json!({"source": {"path": "main.rs"}, "breakpoints": [{"line": 3}]}),
+      )
+      .expect_message(json!({"body": {"breakpoints": [{"line": 4}]}}));
+  }
+
+  #[test]
+  #[should_panic(expected = "Timed out")]
+  fn test_timeout() {
+    let mut client = TestClient::spawn(adapter);
+    client
+      .with_timeout(Duration::from_millis(50))
+      .expect_event("initialized");
+  }
+
+  #[test]
+  #[should_panic(expected = "Unexpected messages")]
+  fn test_unexpected_messages() {
+    let mut client = TestClient::spawn(adapter);
+    client.send("initialize", json!({"adapterID": "test"}));
+    client.finish();
+  }
+
+  #[test]
+  fn test_reverse_request() {
+    let mut client = TestClient::spawn(|mut server| {
+      server.poll_request().unwrap();
+      server
+        .start_debugging(StartDebuggingRequestArguments {
+          configuration: Default::default(),
+          request: StartDebuggingRequestKind::Launch,
+        })
+        .unwrap();
+    });
+    client
+      .send(
+        "initialize",
+        json!({"adapterID": "test", "supportsStartDebuggingRequest": true}),
+      )
+      .expect_reverse_request("startDebugging")
+      .respond(Value::Null);
+    client.finish();
+  }
+}
@@ -0,0 +1,378 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{errors::ServerError, server::Server};
+
+/// A boxed input stream, as returned by [`Transport::connect`].
+pub type BoxedReader = Box<dyn Read + Send>;
+/// A boxed output stream, as returned by [`Transport::connect`].
+pub type BoxedWriter = Box<dyn Write + Send>;
+
+/// Describes how the adapter and the client are connected.
+///
+/// A transport can be parsed from a small URL-like spec:
+///
+///  * `stdio`: standard input and output of the adapter process.
+///  * `tcp://127.0.0.1:4711`: a TCP connection to the given address.
+///  * `unix:///tmp/dap.sock`: a Unix domain socket at the given path.
+///  * `fifo:///tmp/dap.in,/tmp/dap.out`: a pair of named FIFOs. The adapter reads from the
+///    first and writes to the second one. The FIFOs must exist already (e.g. created with
+///    `mkfifo`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+  Stdio,
+  Tcp(String),
+  Unix(PathBuf),
+  Fifo { input: PathBuf, output: PathBuf },
+}
+
+impl FromStr for Transport {
+  type Err = ServerError;
+
+  fn from_str(spec: &str) -> Result<Self, Self::Err> {
+    let invalid = |reason: &str| ServerError::InvalidTransport {
+      spec: spec.to_string(),
+      reason: reason.to_string(),
+    };
+
+    if spec == "stdio" {
+      return Ok(Transport::Stdio);
+    }
+    let (scheme, rest) = spec
+      .split_once("://")
+      .ok_or_else(|| invalid("expected 'stdio' or '<scheme>://<address>'"))?;
+    if rest.is_empty() {
+      return Err(invalid("missing address"));
+    }
+    match scheme {
+      "tcp" => Ok(Transport::Tcp(rest.to_string())),
+      "unix" => Ok(Transport::Unix(PathBuf::from(rest))),
+      "fifo" => match rest.split_once(',') {
+        Some((input, output)) if !input.is_empty() && !output.is_empty() => Ok(Transport::Fifo {
+          input: PathBuf::from(input),
+          output: PathBuf::from(output),
+        }),
+        _ => Err(invalid("expected 'fifo://<input path>,<output path>'")),
+      },
+      _ => Err(invalid("unknown scheme")),
+    }
+  }
+}
+
+impl Display for Transport {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Transport::Stdio => write!(f, "stdio"),
+      Transport::Tcp(addr) => write!(f, "tcp://{}", addr),
+      Transport::Unix(path) => write!(f, "unix://{}", path.display()),
+      Transport::Fifo { input, output } => {
+        write!(f, "fifo://{},{}", input.display(), output.display())
+      }
+    }
+  }
+}
+
+impl Transport {
+  /// Wait for a client on this transport and create a [`Server`] for the connection.
+  ///
+  /// For `tcp` and `unix`, this listens on the address and accepts a single connection. A stale
+  /// socket file left behind by a previous adapter is removed first (see [`bind_unix`]) and the
+  /// socket file is removed again once the client has connected.
+  pub fn accept(&self) -> Result<Server<BoxedReader, BoxedWriter>, ServerError> {
+    let (input, output): (BoxedReader, BoxedWriter) = match self {
+      Transport::Stdio => (Box::new(std::io::stdin()), Box::new(std::io::stdout())),
+      Transport::Tcp(addr) => {
+        let listener = TcpListener::bind(addr.as_str()).map_err(ServerError::IoError)?;
+        let (stream, _) = listener.accept().map_err(ServerError::IoError)?;
+        split_tcp(stream)?
+      }
+      #[cfg(unix)]
+      Transport::Unix(path) => {
+        let listener = bind_unix(path)?;
+        let accepted = listener.accept();
+        let _ = std::fs::remove_file(path);
+        let (stream, _) = accepted.map_err(ServerError::IoError)?;
+        split_unix(stream)?
+      }
+      Transport::Fifo { input, output } => (
+        Box::new(open_fifo_for_reading(input)?),
+        Box::new(open_fifo_for_writing(output)?),
+      ),
+      #[cfg(not(unix))]
+      Transport::Unix(_) => return Err(unsupported(self)),
+    };
+    Ok(Server::new(BufReader::new(input), BufWriter::new(output)))
+  }
+
+  /// Connect to an adapter on this transport, as a client would. Returns the stream to read the
+  /// adapter's messages from and the stream to write requests to.
+  ///
+  /// For `fifo`, the paths are the same as for the adapter, i.e. the client writes to `input`
+  /// and reads from `output`.
+  pub fn connect(&self) -> Result<(BoxedReader, BoxedWriter), ServerError> {
+    match self {
+      Transport::Stdio => Ok((Box::new(std::io::stdin()), Box::new(std::io::stdout()))),
+      Transport::Tcp(addr) => {
+        split_tcp(TcpStream::connect(addr.as_str()).map_err(ServerError::IoError)?)
+      }
+      #[cfg(unix)]
+      Transport::Unix(path) => split_unix(UnixStream::connect(path).map_err(ServerError::IoError)?),
+      #[cfg(not(unix))]
+      Transport::Unix(_) => Err(unsupported(self)),
+      // Open in the opposite order of `accept` so the two sides don't wait for each other.
+      Transport::Fifo { input, output } => {
+        let writer = open_fifo_for_writing(input)?;
+        let reader = open_fifo_for_reading(output)?;
+        Ok((Box::new(reader), Box::new(writer)))
+      }
+    }
+  }
+}
+
+impl Server<TcpStream, TcpStream> {
+  /// Construct a new Server that communicates over an established TCP connection.
+  pub fn from_tcp_stream(stream: TcpStream) -> Result<Self, ServerError> {
+    let input = stream.try_clone().map_err(ServerError::IoError)?;
+    Ok(Server::new(BufReader::new(input), BufWriter::new(stream)))
+  }
+}
+
+#[cfg(unix)]
+impl Server<UnixStream, UnixStream> {
+  /// Construct a new Server that communicates over an established Unix socket connection.
+  pub fn from_unix_stream(stream: UnixStream) -> Result<Self, ServerError> {
+    let input = stream.try_clone().map_err(ServerError::IoError)?;
+    Ok(Server::new(BufReader::new(input), BufWriter::new(stream)))
+  }
+}
+
+/// Listen on a Unix domain socket at `path`.
+///
+/// If a socket file already exists at `path` but nobody is listening on it (e.g. because a
+/// previous adapter crashed), the file is removed first. If another process is still listening,
+/// or if `path` is not a socket at all, an `AddrInUse` error is returned. Any other error while
+/// probing the socket is returned as is, and the file is left in place.
+#[cfg(unix)]
+pub fn bind_unix<P: AsRef<Path>>(path: P) -> Result<UnixListener, ServerError> {
+  let path = path.as_ref();
+  match std::fs::symlink_metadata(path) {
+    Ok(metadata) if !metadata.file_type().is_socket() => {
+      return Err(ServerError::IoError(std::io::Error::new(
+        std::io::ErrorKind::AddrInUse,
+        format!("{} exists and is not a socket", path.display()),
+      )))
+    }
+    Ok(_) => match UnixStream::connect(path) {
+      Ok(_) => {
+        return Err(ServerError::IoError(std::io::Error::new(
+          std::io::ErrorKind::AddrInUse,
+          format!("{} is in use by another process", path.display()),
+        )))
+      }
+      Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+        std::fs::remove_file(path).map_err(ServerError::IoError)?
+      }
+      Err(e) => return Err(ServerError::IoError(e)),
+    },
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+    Err(e) => return Err(ServerError::IoError(e)),
+  }
+  UnixListener::bind(path).map_err(ServerError::IoError)
+}
+
+fn split_tcp(stream: TcpStream) -> Result<(BoxedReader, BoxedWriter), ServerError> {
+  let input = stream.try_clone().map_err(ServerError::IoError)?;
+  Ok((Box::new(input), Box::new(stream)))
+}
+
+#[cfg(unix)]
+fn split_unix(stream: UnixStream) -> Result<(BoxedReader, BoxedWriter), ServerError> {
+  let input = stream.try_clone().map_err(ServerError::IoError)?;
+  Ok((Box::new(input), Box::new(stream)))
+}
+
+fn open_fifo_for_reading(path: &Path) -> Result<File, ServerError> {
+  File::open(path).map_err(ServerError::IoError)
+}
+
+fn open_fifo_for_writing(path: &Path) -> Result<File, ServerError> {
+  OpenOptions::new()
+    .write(true)
+    .open(path)
+    .map_err(ServerError::IoError)
+}
+
+#[cfg(not(unix))]
+fn unsupported(transport: &Transport) -> ServerError {
+  ServerError::InvalidTransport {
+    spec: transport.to_string(),
+    reason: "not supported on this platform".to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::BufRead;
+  use std::thread;
+
+  use super::*;
+  use crate::requests::Command;
+
+  const INITIALIZE: &str = "{\"seq\": 1, \"type\": \"request\", \"command\": \"initialize\", \"arguments\": {\"adapterID\": \"test\"}}";
+
+  fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("dap-rs-{}-{}", std::process::id(), name))
+  }
+
+  #[test]
+  fn test_parse_transport() {
+    assert_eq!("stdio".parse::<Transport>().unwrap(), Transport::Stdio);
+    assert_eq!(
+      "tcp://127.0.0.1:4711".parse::<Transport>().unwrap(),
+      Transport::Tcp("127.0.0.1:4711".to_string())
+    );
+    assert_eq!(
+      "unix:///tmp/dap.sock".parse::<Transport>().unwrap(),
+      Transport::Unix(PathBuf::from("/tmp/dap.sock"))
+    );
+    assert_eq!(
+      "fifo:///tmp/dap.in,/tmp/dap.out"
+        .parse::<Transport>()
+        .unwrap(),
+      Transport::Fifo {
+        input: PathBuf::from("/tmp/dap.in"),
+        output: PathBuf::from("/tmp/dap.out"),
+      }
+    );
+    for spec in ["unix:///tmp/dap.sock", "tcp://[::1]:4711", "stdio"] {
+      assert_eq!(spec.parse::<Transport>().unwrap().to_string(), spec);
+    }
+    for spec in ["", "stdin", "tcp://", "udp://127.0.0.1:1", "fifo:///tmp/in"] {
+      assert!(matches!(
+        spec.parse::<Transport>(),
+        Err(ServerError::InvalidTransport { .. })
+      ));
+    }
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_unix_transport() {
+    let path = temp_path("unix.sock");
+    let transport = Transport::Unix(path.clone());
+
+    let client_transport = transport.clone();
+    let client = thread::spawn(move || {
+      // Wait for the adapter to bind the socket.
+      let (reader, mut writer) = loop {
+        match client_transport.connect() {
+          Ok(streams) => break streams,
+          Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+        }
+      };
+      write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        INITIALIZE.len(),
+        INITIALIZE
+      )
+      .unwrap();
+      writer.flush().unwrap();
+      let mut header = String::new();
+      BufReader::new(reader).read_line(&mut header).unwrap();
+      header
+    });
+
+    let mut server = transport.accept().unwrap();
+    let req = server.poll_request().unwrap().unwrap();
+    assert!(matches!(req.command, Command::Initialize(_)));
+    server
+      .send_event(crate::events::Event::Initialized)
+      .unwrap();
+    assert!(client.join().unwrap().starts_with("Content-Length:"));
+    assert!(!path.exists());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_bind_unix_removes_stale_socket() {
+    let path = temp_path("stale.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let listener = bind_unix(&path).unwrap();
+    // Somebody is listening, so the socket is not stale.
+    assert!(matches!(bind_unix(&path), Err(ServerError::IoError(_))));
+
+    // Dropping the listener leaves the socket file behind.
+    drop(listener);
+    assert!(path.exists());
+    let _listener = bind_unix(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_bind_unix_keeps_other_files() {
+    let path = temp_path("important.txt");
+    std::fs::write(&path, "keep me").unwrap();
+
+    let err = bind_unix(&path).unwrap_err();
+    assert!(matches!(err, ServerError::IoError(e) if e.kind() == std::io::ErrorKind::AddrInUse));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_fifo_transport() {
+    let input = temp_path("fifo.in");
+    let output = temp_path("fifo.out");
+    for path in [&input, &output] {
+      let _ = std::fs::remove_file(path);
+      let status = std::process::Command::new("mkfifo").arg(path).status();
+      if !matches!(status, Ok(status) if status.success()) {
+        // mkfifo is not available, nothing to test.
+        return;
+      }
+    }
+    let transport = Transport::Fifo {
+      input: input.clone(),
+      output: output.clone(),
+    };
+
+    let client_transport = transport.clone();
+    let client = thread::spawn(move || {
+      let (reader, mut writer) = client_transport.connect().unwrap();
+      write!(
+        writer,
+        "Content-Length: {}\r\n\r\n{}",
+        INITIALIZE.len(),
+        INITIALIZE
+      )
+      .unwrap();
+      writer.flush().unwrap();
+      let mut header = String::new();
+      BufReader::new(reader).read_line(&mut header).unwrap();
+      header
+    });
+
+    let mut server = transport.accept().unwrap();
+    let req = server.poll_request().unwrap().unwrap();
+    assert!(matches!(req.command, Command::Initialize(_)));
+    server
+      .send_event(crate::events::Event::Initialized)
+      .unwrap();
+    assert!(client.join().unwrap().starts_with("Content-Length:"));
+
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+  }
+}
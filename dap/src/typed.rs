@@ -0,0 +1,264 @@
+//! Type-level pairing of requests with their arguments and response bodies.
+//!
+//! [`Command`] and [`ResponseBody`] are separate enums, so nothing ties
+//! `Command::StackTrace(StackTraceArguments)` to `ResponseBody::StackTrace(StackTraceResponse)`.
+//! This module has a marker type for every request, in the style of `lsp_types::request`, that
+//! implements [`DapRequest`] and can be used to write generic, type-safe handlers:
+//!
+//! ```rust
+//! use dap::prelude::*;
+//! use dap::typed::{ClientRequest, Threads};
+//! use dap::types::Thread;
+//!
+//! fn handle<R: ClientRequest>(
+//!   request: Request,
+//!   handler: impl FnOnce(R::Arguments) -> R::Response,
+//! ) -> Response {
+//!   match R::from_command(request.command.clone()) {
+//!     Ok(arguments) => request.success(R::into_body(handler(arguments))),
+//!     Err(_) => request.error(&format!("Not a {} request", R::COMMAND)),
+//!   }
+//! }
+//!
+//! let request = Request {
+//!   seq: 1,
+//!   command: Command::Threads,
+//! };
+//! let response = handle::<Threads>(request, |()| responses::ThreadsResponse {
+//!   threads: vec![Thread {
+//!     id: 1,
+//!     name: "main".to_string(),
+//!     ..Default::default()
+//!   }],
+//!   ..Default::default()
+//! });
+//! assert!(response.success);
+//! ```
+use crate::{
+  requests::*,
+  responses::*,
+  reverse_requests::{
+    ReverseCommand, ReverseResponseBody, RunInTerminalRequestArguments, RunInTerminalResponse,
+    StartDebuggingRequestArguments,
+  },
+  types::Capabilities,
+};
+
+/// A request of the protocol, with the types of its arguments and of the body of its response.
+pub trait DapRequest {
+  /// The `command` of the request on the wire.
+  const COMMAND: &'static str;
+  /// The arguments of the request, `()` if it has none.
+  type Arguments;
+  /// The body of a successful response, `()` if the response is just an acknowledgement.
+  type Response;
+}
+
+/// A request sent by the client to the adapter.
+#[allow(clippy::result_large_err)] // Handed back so it can be matched against other requests.
+pub trait ClientRequest: DapRequest {
+  /// Wraps the arguments into a [`Command`].
+  fn into_command(arguments: Self::Arguments) -> Command;
+  /// Unwraps the arguments from a [`Command`], or returns the command if it is a different one.
+  fn from_command(command: Command) -> Result<Self::Arguments, Command>;
+  /// Wraps the response into a [`ResponseBody`].
+  fn into_body(response: Self::Response) -> ResponseBody;
+  /// Unwraps the response from a [`ResponseBody`], or returns the body if it belongs to a
+  /// different request.
+  fn from_body(body: ResponseBody) -> Result<Self::Response, ResponseBody>;
+}
+
+/// A reverse request, sent by the adapter to the client.
+#[allow(clippy::result_large_err)] // Handed back so it can be matched against other requests.
+pub trait AdapterRequest: DapRequest {
+  /// Wraps the arguments into a [`ReverseCommand`].
+  fn into_command(arguments: Self::Arguments) -> ReverseCommand;
+  /// Unwraps the arguments from a [`ReverseCommand`], or returns the command if it is a different
+  /// one.
+  fn from_command(command: ReverseCommand) -> Result<Self::Arguments, ReverseCommand>;
+  /// Wraps the response into a [`ReverseResponseBody`].
+  fn into_body(response: Self::Response) -> ReverseResponseBody;
+  /// Unwraps the response from a [`ReverseResponseBody`], or returns the body if it belongs to a
+  /// different request.
+  fn from_body(body: ReverseResponseBody) -> Result<Self::Response, ReverseResponseBody>;
+}
+
+/// Wraps a value into a variant, or returns the variant itself if it carries no value (`()`).
+macro_rules! wrap {
+  ($variant:path, $value:ident, ()) => {{
+    let () = $value;
+    $variant
+  }};
+  ($variant:path, $value:ident, $ty:ty) => {
+    $variant($value)
+  };
+}
+
+/// Unwraps the value of a variant, `()` if it carries none.
+macro_rules! unwrap {
+  ($variant:path, $enum:expr, ()) => {
+    match $enum {
+      $variant => Ok(()),
+      other => Err(other),
+    }
+  };
+  ($variant:path, $enum:expr, $ty:ty) => {
+    match $enum {
+      $variant(value) => Ok(value),
+      other => Err(other),
+    }
+  };
+}
+
+macro_rules! requests {
+  (
+    $trait:ident, $command_enum:ident, $body_enum:ident;
+    $($name:ident, $command:literal, $arguments:tt, $response:tt;)*
+  ) => {
+    $(
+      #[doc = concat!("The `", $command, "` request.")]
+      #[derive(Debug)]
+      pub enum $name {}
+
+      impl DapRequest for $name {
+        const COMMAND: &'static str = $command;
+        type Arguments = $arguments;
+        type Response = $response;
+      }
+
+      impl $trait for $name {
+        fn into_command(arguments: Self::Arguments) -> $command_enum {
+          wrap!($command_enum::$name, arguments, $arguments)
+        }
+
+        fn from_command(command: $command_enum) -> Result<Self::Arguments, $command_enum> {
+          unwrap!($command_enum::$name, command, $arguments)
+        }
+
+        fn into_body(response: Self::Response) -> $body_enum {
+          wrap!($body_enum::$name, response, $response)
+        }
+
+        fn from_body(body: $body_enum) -> Result<Self::Response, $body_enum> {
+          unwrap!($body_enum::$name, body, $response)
+        }
+      }
+    )*
+  };
+}
+
+requests! {
+  ClientRequest, Command, ResponseBody;
+  Attach, "attach", AttachRequestArguments, ();
+  BreakpointLocations, "breakpointLocations", BreakpointLocationsArguments,
+    BreakpointLocationsResponse;
+  Cancel, "cancel", CancelArguments, ();
+  Completions, "completions", CompletionsArguments, CompletionsResponse;
+  ConfigurationDone, "configurationDone", (), ();
+  Continue, "continue", ContinueArguments, ContinueResponse;
+  DataBreakpointInfo, "dataBreakpointInfo", DataBreakpointInfoArguments,
+    DataBreakpointInfoResponse;
+  Disassemble, "disassemble", DisassembleArguments, DisassembleResponse;
+  Disconnect, "disconnect", DisconnectArguments, ();
+  Evaluate, "evaluate", EvaluateArguments, EvaluateResponse;
+  ExceptionInfo, "exceptionInfo", ExceptionInfoArguments, ExceptionInfoResponse;
+  Goto, "goto", GotoArguments, ();
+  GotoTargets, "gotoTargets", GotoTargetsArguments, GotoTargetsResponse;
+  Initialize, "initialize", InitializeArguments, Capabilities;
+  Launch, "launch", LaunchRequestArguments, ();
+  LoadedSources, "loadedSources", (), LoadedSourcesResponse;
+  Locations, "locations", LocationsArguments, LocationsResponse;
+  Modules, "modules", ModulesArguments, ModulesResponse;
+  Next, "next", NextArguments, ();
+  Pause, "pause", PauseArguments, ();
+  ReadMemory, "readMemory", ReadMemoryArguments, ReadMemoryResponse;
+  Restart, "restart", RestartArguments, ();
+  RestartFrame, "restartFrame", RestartFrameArguments, ();
+  ReverseContinue, "reverseContinue", ReverseContinueArguments, ();
+  Scopes, "scopes", ScopesArguments, ScopesResponse;
+  SetBreakpoints, "setBreakpoints", SetBreakpointsArguments, SetBreakpointsResponse;
+  SetDataBreakpoints, "setDataBreakpoints", SetDataBreakpointsArguments,
+    SetDataBreakpointsResponse;
+  SetExceptionBreakpoints, "setExceptionBreakpoints", SetExceptionBreakpointsArguments,
+    SetExceptionBreakpointsResponse;
+  SetExpression, "setExpression", SetExpressionArguments, SetExpressionResponse;
+  SetFunctionBreakpoints, "setFunctionBreakpoints", SetFunctionBreakpointsArguments,
+    SetFunctionBreakpointsResponse;
+  SetInstructionBreakpoints, "setInstructionBreakpoints", SetInstructionBreakpointsArguments,
+    SetInstructionBreakpointsResponse;
+  SetVariable, "setVariable", SetVariableArguments, SetVariableResponse;
+  Source, "source", SourceArguments, SourceResponse;
+  StackTrace, "stackTrace", StackTraceArguments, StackTraceResponse;
+  StepBack, "stepBack", StepBackArguments, ();
+  StepIn, "stepIn", StepInArguments, ();
+  StepInTargets, "stepInTargets", StepInTargetsArguments, StepInTargetsResponse;
+  StepOut, "stepOut", StepOutArguments, ();
+  Terminate, "terminate", TerminateArguments, ();
+  TerminateThreads, "terminateThreads", TerminateThreadsArguments, ();
+  Threads, "threads", (), ThreadsResponse;
+  Variables, "variables", VariablesArguments, VariablesResponse;
+  WriteMemory, "writeMemory", WriteMemoryArguments, WriteMemoryResponse;
+}
+
+requests! {
+  AdapterRequest, ReverseCommand, ReverseResponseBody;
+  RunInTerminal, "runInTerminal", RunInTerminalRequestArguments, RunInTerminalResponse;
+  StartDebugging, "startDebugging", StartDebuggingRequestArguments, ();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::StartDebuggingRequestKind;
+
+  #[test]
+  fn test_client_request() {
+    let arguments = StackTraceArguments {
+      thread_id: 3,
+      ..Default::default()
+    };
+    let command = StackTrace::into_command(arguments.clone());
+    assert_eq!(command.name(), StackTrace::COMMAND);
+    assert_eq!(StackTrace::from_command(command), Ok(arguments));
+    assert_eq!(
+      StackTrace::from_command(Command::Threads),
+      Err(Command::Threads)
+    );
+
+    let response = StackTraceResponse {
+      total_frames: Some(0),
+      ..Default::default()
+    };
+    let body = StackTrace::into_body(response.clone());
+    assert_eq!(StackTrace::from_body(body), Ok(response));
+    assert!(StackTrace::from_body(ResponseBody::Threads(Default::default())).is_err());
+
+    let command = ConfigurationDone::into_command(());
+    assert_eq!(command, Command::ConfigurationDone);
+    assert_eq!(ConfigurationDone::from_command(command), Ok(()));
+    assert_eq!(
+      ConfigurationDone::into_body(()),
+      ResponseBody::ConfigurationDone
+    );
+    assert_eq!(
+      Initialize::into_body(Capabilities::default()),
+      ResponseBody::Initialize(Capabilities::default())
+    );
+  }
+
+  #[test]
+  fn test_adapter_request() {
+    let arguments = StartDebuggingRequestArguments {
+      configuration: Default::default(),
+      request: StartDebuggingRequestKind::Launch,
+    };
+    let command = StartDebugging::into_command(arguments.clone());
+    assert_eq!(StartDebugging::from_command(command.clone()), Ok(arguments));
+    assert!(RunInTerminal::from_command(command).is_err());
+    assert_eq!(
+      RunInTerminal::from_body(RunInTerminal::into_body(Default::default())),
+      Ok(RunInTerminalResponse::default())
+    );
+    assert_eq!(StartDebugging::COMMAND, "startDebugging");
+  }
+}
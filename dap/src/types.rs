@@ -3,12 +3,14 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::errors::BreakpointModeError;
+
 #[cfg(feature = "integration_testing")]
 use fake::{Dummy, Fake, Faker};
 #[cfg(feature = "integration_testing")]
 use rand::Rng;
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionBreakpointsFilter {
@@ -35,7 +37,7 @@ pub struct ExceptionBreakpointsFilter {
   pub condition_description: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ColumnDescriptorType {
@@ -43,9 +45,13 @@ pub enum ColumnDescriptorType {
   Number,
   Boolean,
   UnixTimestampUTC,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  Other(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ColumnDescriptor {
@@ -65,7 +71,7 @@ pub struct ColumnDescriptor {
   pub width: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ChecksumAlgorithm {
   MD5,
@@ -73,9 +79,13 @@ pub enum ChecksumAlgorithm {
   SHA256,
   #[serde(rename = "timestamp")]
   Timestamp,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Capabilities {
@@ -216,23 +226,130 @@ pub struct Capabilities {
   /// `stepBack`).
   #[serde(skip_serializing_if = "Option::is_none")]
   pub supports_single_thread_execution_requests: Option<bool>,
+  /// The debug adapter supports the `asAddress` and `bytes` fields in the
+  /// `dataBreakpointInfo` request.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub supports_data_breakpoint_bytes: Option<bool>,
+  /// Modes of breakpoints supported by the debug adapter, such as 'hardware' or
+  /// 'software'. If present, the client may allow the user to select a mode and
+  /// include it in its `setBreakpoints` request.
+  ///
+  /// Clients may present the first applicable mode in this array as the
+  /// 'default' mode in gestures that set breakpoints.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub breakpoint_modes: Option<Vec<BreakpointMode>>,
+  /// The debug adapter supports ANSI escape sequences in styling of
+  /// `OutputEvent.output` and `Variable.value` fields.
+  #[serde(
+    rename = "supportsANSIStyling",
+    skip_serializing_if = "Option::is_none"
+  )]
+  pub supports_ansi_styling: Option<bool>,
+}
+
+impl Capabilities {
+  /// Looks up a mode declared in `breakpointModes` that applies to the given kind of breakpoint.
+  pub fn breakpoint_mode(
+    &self,
+    mode: &str,
+    applicability: &BreakpointModeApplicability,
+  ) -> Option<&BreakpointMode> {
+    self
+      .breakpoint_modes
+      .iter()
+      .flatten()
+      .find(|declared| declared.mode == mode && declared.applies_to.contains(applicability))
+  }
+
+  /// Checks the `mode` requested for a breakpoint against the modes declared in
+  /// `breakpointModes`. Breakpoints without a mode are always valid.
+  ///
+  /// The error is meant to be shown to the user as the `message` of an unverified `Breakpoint`.
+  pub fn validate_breakpoint_mode(
+    &self,
+    mode: Option<&str>,
+    applicability: &BreakpointModeApplicability,
+  ) -> Result<(), BreakpointModeError> {
+    let mode = match mode {
+      Some(mode) => mode,
+      None => return Ok(()),
+    };
+    if self.breakpoint_mode(mode, applicability).is_some() {
+      return Ok(());
+    }
+    let declared = self
+      .breakpoint_modes
+      .iter()
+      .flatten()
+      .any(|declared| declared.mode == mode);
+    if declared {
+      Err(BreakpointModeError::NotApplicable {
+        mode: mode.to_string(),
+        applicability: applicability.clone(),
+      })
+    } else {
+      Err(BreakpointModeError::UnknownMode {
+        mode: mode.to_string(),
+      })
+    }
+  }
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 pub struct CustomValue(pub Value);
 
+/// Generates arbitrary JSON for the fields that hold one. Never `null`, because an optional field
+/// that is `null` can't be told apart from a missing one.
 #[cfg(feature = "integration_testing")]
-struct ValueFaker;
+pub(crate) struct ValueFaker;
 
 #[cfg(feature = "integration_testing")]
-impl Dummy<ValueFaker> for CustomValue {
+impl Dummy<ValueFaker> for Value {
   fn dummy_with_rng<R: Rng + ?Sized>(_: &ValueFaker, rng: &mut R) -> Self {
-    CustomValue(match rng.gen_range(0..=5) {
-      1 => Value::Bool(rng.gen()),
-      2 => Value::Number(serde_json::Number::from_f64(rng.gen()).unwrap()),
-      3 => Value::String(Faker.fake::<String>()),
-      _ => Value::Null,
-    })
+    match rng.gen_range(0..=5) {
+      0 => Value::Bool(rng.gen()),
+      1 => Value::Number(serde_json::Number::from_f64(rng.gen()).unwrap()),
+      2 => Value::Number(rng.gen::<i32>().into()),
+      3 => Value::Array(vec![Value::String(Faker.fake_with_rng(rng))]),
+      4 => ObjectFaker.fake_with_rng(rng),
+      _ => Value::String(Faker.fake_with_rng(rng)),
+    }
+  }
+}
+
+#[cfg(feature = "integration_testing")]
+impl Dummy<ValueFaker> for CustomValue {
+  fn dummy_with_rng<R: Rng + ?Sized>(config: &ValueFaker, rng: &mut R) -> Self {
+    CustomValue(Value::dummy_with_rng(config, rng))
+  }
+}
+
+/// Generates a JSON object with at least one property, e.g. for the implementation specific
+/// attributes that are flattened into the `launch` arguments.
+#[cfg(feature = "integration_testing")]
+pub(crate) struct ObjectFaker;
+
+#[cfg(feature = "integration_testing")]
+impl Dummy<ObjectFaker> for HashMap<String, Value> {
+  fn dummy_with_rng<R: Rng + ?Sized>(_: &ObjectFaker, rng: &mut R) -> Self {
+    (0..rng.gen_range(1..=3))
+      .map(|_| {
+        let value = match rng.gen_range(0..=2) {
+          0 => Value::Bool(rng.gen()),
+          1 => Value::Number(rng.gen::<i32>().into()),
+          _ => Value::String(Faker.fake_with_rng(rng)),
+        };
+        (Faker.fake_with_rng(rng), value)
+      })
+      .collect()
+  }
+}
+
+#[cfg(feature = "integration_testing")]
+impl Dummy<ObjectFaker> for Value {
+  fn dummy_with_rng<R: Rng + ?Sized>(config: &ObjectFaker, rng: &mut R) -> Self {
+    let properties: HashMap<String, Value> = config.fake_with_rng(rng);
+    Value::Object(properties.into_iter().collect())
   }
 }
 
@@ -242,7 +359,7 @@ impl Dummy<ValueFaker> for CustomValue {
 /// specifying breakpoints.
 ///
 /// Specification: [Source](https://microsoft.github.io/debug-adapter-protocol/specification#Types_Source)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Source {
@@ -274,6 +391,8 @@ pub struct Source {
   pub origin: Option<String>,
   /// A list of sources that are related to this source. These may be the source
   /// that generated this source.
+  // Few and short, so that generating the recursive type terminates quickly.
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "(Faker, 0..3)"))]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub sources: Option<Vec<Source>>,
   /// Additional data that a debug adapter might want to loop through the client.
@@ -287,7 +406,60 @@ pub struct Source {
   pub checksums: Option<Vec<Checksum>>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Describes one or more type of breakpoint a `BreakpointMode` applies to. This is a non-exhaustive
+/// enumeration and may expand as future breakpoint types are added.
+///
+/// Specification: [BreakpointModeApplicability](https://microsoft.github.io/debug-adapter-protocol/specification#Types_BreakpointModeApplicability)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub enum BreakpointModeApplicability {
+  /// In `SourceBreakpoint`s
+  Source,
+  /// In exception breakpoints applied in the `ExceptionFilterOptions`
+  Exception,
+  /// In data breakpoints requested in the `DataBreakpointInfo` request
+  Data,
+  /// In `InstructionBreakpoint`s
+  Instruction,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  String(String),
+}
+
+impl std::fmt::Display for BreakpointModeApplicability {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BreakpointModeApplicability::Source => write!(f, "source"),
+      BreakpointModeApplicability::Exception => write!(f, "exception"),
+      BreakpointModeApplicability::Data => write!(f, "data"),
+      BreakpointModeApplicability::Instruction => write!(f, "instruction"),
+      BreakpointModeApplicability::String(other) => write!(f, "{}", other),
+    }
+  }
+}
+
+/// A `BreakpointMode` is provided as a option when setting breakpoints on sources or instructions.
+///
+/// Specification: [BreakpointMode](https://microsoft.github.io/debug-adapter-protocol/specification#Types_BreakpointMode)
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct BreakpointMode {
+  /// The internal ID of the mode. This value is passed to the `setBreakpoints`
+  /// request.
+  pub mode: String,
+  /// The name of the breakpoint mode. This is shown in the UI.
+  pub label: String,
+  /// A help text providing additional information about the breakpoint mode.
+  /// This string is typically shown as a hover and can be translated.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub description: Option<String>,
+  /// Describes one or more type of breakpoint this mode applies to.
+  pub applies_to: Vec<BreakpointModeApplicability>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct SourceBreakpoint {
@@ -316,11 +488,15 @@ pub struct SourceBreakpoint {
   /// capability `supportsLogPoints` is true.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub log_message: Option<String>,
+  /// The mode of this breakpoint. If defined, this must be one of the
+  /// `breakpointModes` the debug adapter advertised in its `Capabilities`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
 }
 
 /// Information about a breakpoint created in setBreakpoints, setFunctionBreakpoints,
 /// setInstructionBreakpoints, or setDataBreakpoints requests.
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Breakpoint {
@@ -366,16 +542,21 @@ pub struct Breakpoint {
   pub offset: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum PresentationHint {
   Normal,
   Emphasize,
+  #[serde(rename = "deemphasize")]
   DeEmphasize,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Checksum {
@@ -387,7 +568,7 @@ pub struct Checksum {
 
 /// An ExceptionFilterOptions is used to specify an exception filter together with a condition for
 /// the setExceptionBreakpoints request.
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionFilterOptions {
@@ -399,13 +580,17 @@ pub struct ExceptionFilterOptions {
   /// true.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub condition: Option<String>,
+  /// The mode of this breakpoint. If defined, this must be one of the
+  /// `breakpointModes` the debug adapter advertised in its `Capabilities`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
 }
 
 /// This enumeration defines all possible conditions when a thrown exception should result in a
 /// break.
 ///
 /// Specification: [`ExceptionBreakMode`](https://microsoft.github.io/debug-adapter-protocol/specification#Types_ExceptionBreakMode)
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ExceptionBreakMode {
@@ -417,6 +602,10 @@ pub enum ExceptionBreakMode {
   Unhandled,
   /// breaks if the exception is not handled by user code
   UserUnhandled,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
 impl Default for ExceptionBreakMode {
@@ -431,7 +620,7 @@ impl Default for ExceptionBreakMode {
 /// or missing, or it matches anything except the names provided if negate is true.
 ///
 /// Specification: [`ExceptionPathSegment`](https://microsoft.github.io/debug-adapter-protocol/specification#Types_ExceptionPathSegment)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionPathSegment {
@@ -447,7 +636,7 @@ pub struct ExceptionPathSegment {
 /// An ExceptionOptions assigns configuration options to a set of exceptions.
 ///
 /// Specification: [`ExceptionOptions`](https://microsoft.github.io/debug-adapter-protocol/specification#Types_ExceptionOptions)
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionOptions {
@@ -464,7 +653,7 @@ pub struct ExceptionOptions {
 /// Properties of a breakpoint passed to the setFunctionBreakpoints request.
 ///
 /// Specification: [FunctionBreakpoint](https://microsoft.github.io/debug-adapter-protocol/specification#Types_FunctionBreakpoint)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct FunctionBreakpoint {
@@ -483,18 +672,19 @@ pub struct FunctionBreakpoint {
   pub hit_condition: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum BreakpointEventReason {
   Changed,
   New,
   Removed,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum InvalidatedAreas {
@@ -502,29 +692,38 @@ pub enum InvalidatedAreas {
   Stacks,
   Threads,
   Variables,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum LoadedSourceEventReason {
   New,
   Changed,
   Removed,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ModuleEventReason {
   New,
   Changed,
   Removed,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Module {
@@ -563,7 +762,7 @@ pub struct Module {
   pub address_range: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ModuleId {
@@ -572,7 +771,7 @@ pub enum ModuleId {
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum OutputEventCategory {
@@ -581,29 +780,38 @@ pub enum OutputEventCategory {
   Stdout,
   Stderr,
   Telemetry,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum OutputEventGroup {
   Start,
   StartCollapsed,
   End,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ProcessEventStartMethod {
   Launch,
   Attach,
   AttachForSuspendedLaunch,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum StoppedEventReason {
@@ -613,24 +821,29 @@ pub enum StoppedEventReason {
   Pause,
   Entry,
   Goto,
+  #[serde(rename = "function breakpoint")]
   Function,
+  #[serde(rename = "data breakpoint")]
   Data,
+  #[serde(rename = "instruction breakpoint")]
   Instruction,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ThreadEventReason {
   Started,
   Exited,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ValueFormat {
@@ -639,9 +852,13 @@ pub struct ValueFormat {
   pub hex: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StackFrameFormat {
+  /// Display the value in hex.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hex: Option<bool>,
   /// Displays parameters for the stack frame.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub parameters: Option<bool>,
@@ -666,7 +883,7 @@ pub struct StackFrameFormat {
   pub include_all: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum EvaluateArgumentsContext {
@@ -675,29 +892,38 @@ pub enum EvaluateArgumentsContext {
   Repl,
   Hover,
   Clipboard,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum SteppingGranularity {
   Statement,
   Line,
   Instruction,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum DataBreakpointAccessType {
   Read,
   Write,
   ReadWrite,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DataBreakpoint {
@@ -714,12 +940,16 @@ pub struct DataBreakpoint {
   /// The debug adapter is expected to interpret the expression as needed.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub hit_condition: Option<String>,
+  /// The mode of this breakpoint. If defined, this must be one of the
+  /// `breakpointModes` the debug adapter advertised in its `Capabilities`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
 }
 
 /// Properties of a breakpoint passed to the setInstructionBreakpoints request
 ///
 /// Specfication: [InstructionBreakpoint](https://microsoft.github.io/debug-adapter-protocol/specification#Types_InstructionBreakpoint)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct InstructionBreakpoint {
@@ -743,20 +973,28 @@ pub struct InstructionBreakpoint {
   /// capability `supportsHitConditionalBreakpoints` is true.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub hit_condition: Option<String>,
+  /// The mode of this breakpoint. If defined, this must be one of the
+  /// `breakpointModes` the debug adapter advertised in its `Capabilities`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum VariablesArgumentsFilter {
   Indexed,
   Named,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
 /// Properties of a breakpoint location returned from the breakpointLocations request.
 
 /// Specfication: [BreakpointLocation](https://microsoft.github.io/debug-adapter-protocol/specification#Types_BreakpointLocation)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct BreakpointLocation {
@@ -781,7 +1019,7 @@ pub struct BreakpointLocation {
 /// icons for all of them
 ///
 /// Specification: [CompletionItemType](https://microsoft.github.io/debug-adapter-protocol/specification#Types_CompletionItemType)
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum CompletionItemType {
@@ -803,13 +1041,18 @@ pub enum CompletionItemType {
   Color,
   File,
   Reference,
+  #[serde(rename = "customcolor")]
   CustomColor,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
 /// `CompletionItems` are the suggestions returned from the `completions` request.
 ///
 /// Specification: [CompletionItem](https://microsoft.github.io/debug-adapter-protocol/specification#Types_CompletionItem)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct CompletionItem {
@@ -862,7 +1105,7 @@ pub struct CompletionItem {
 /// Represents a single disassembled instruction.
 ///
 /// Specification: [DisassembledInstruction](https://microsoft.github.io/debug-adapter-protocol/specification#Types_DisassembledInstruction)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct DisassembledInstruction {
@@ -901,7 +1144,7 @@ pub struct DisassembledInstruction {
   pub end_column: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum VariablePresentationHintKind {
@@ -931,13 +1174,14 @@ pub enum VariablePresentationHintKind {
   /// registered for the object. The `hasDataBreakpoint` attribute should
   /// generally be used instead.
   DataBreakpoint,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
 /// Set of attributes represented as an array of Strings. Before introducing
 /// additional values, try to use the listed values.
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum VariablePresentationHintAttributes {
@@ -957,11 +1201,12 @@ pub enum VariablePresentationHintAttributes {
   HasSideEffects,
   /// Indicates that the object has its value tracked by a data breakpoint.
   HasDataBreakpoint,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum VariablePresentationHintVisibility {
@@ -970,11 +1215,12 @@ pub enum VariablePresentationHintVisibility {
   Protected,
   Internal,
   Final,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 #[serde(rename_all = "camelCase")]
 pub struct VariablePresentationHint {
@@ -1037,7 +1283,7 @@ pub struct VariablePresentationHint {
 /// Detailed information about an exception that has occurred.
 ///
 /// Specification: [ExceptionDetails](https://microsoft.github.io/debug-adapter-protocol/specification#Types_ExceptionDetails)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct ExceptionDetails {
@@ -1058,6 +1304,8 @@ pub struct ExceptionDetails {
   #[serde(skip_serializing_if = "Option::is_none")]
   pub stack_trace: Option<String>,
   /// Details of the exception contained by this exception, if any.
+  // Few and short, so that generating the recursive type terminates quickly.
+  #[cfg_attr(feature = "integration_testing", dummy(faker = "(Faker, 0..3)"))]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub inner_exception: Option<Vec<ExceptionDetails>>,
 }
@@ -1067,7 +1315,7 @@ pub struct ExceptionDetails {
 /// The possible goto targets can be determined via the gotoTargets request.
 ///
 /// Specification: [GotoTarget](https://microsoft.github.io/debug-adapter-protocol/specification#Types_GotoTarget)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct GotoTarget {
@@ -1092,11 +1340,41 @@ pub struct GotoTarget {
   pub instruction_pointer_reference: Option<String>,
 }
 
+/// A `StepInTarget` can be used in the `stepIn` request and determines into
+/// which single target the `stepIn` request should step.
+///
+/// Specification: [StepInTarget](https://microsoft.github.io/debug-adapter-protocol/specification#Types_StepInTarget)
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "integration_testing", derive(Dummy))]
+pub struct StepInTarget {
+  /// Unique identifier for a step-in target.
+  pub id: i64,
+  /// The name of the step-in target (shown in the UI).
+  pub label: String,
+  /// The line of the step-in target.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub line: Option<i64>,
+  /// Start position of the range covered by the step in target. It is measured
+  /// in UTF-16 code units and the client capability `columnsStartAt1`
+  /// determines whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column: Option<i64>,
+  /// The end line of the range covered by the step-in target.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_line: Option<i64>,
+  /// End position of the range covered by the step in target. It is measured
+  /// in UTF-16 code units and the client capability `columnsStartAt1`
+  /// determines whether it is 0- or 1-based.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_column: Option<i64>,
+}
+
 /// A hint for how to present this scope in the UI. If this attribute is
 /// missing, the scope is shown with a generic UI.
 ///
 /// Specification: [Scope](https://microsoft.github.io/debug-adapter-protocol/specification#Types_Scope)
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum ScopePresentationhint {
@@ -1107,6 +1385,7 @@ pub enum ScopePresentationhint {
   /// Scope contains registers. Only a single `registers` scope
   /// should be returned from a `scopes` request.
   Registers,
+  /// A value that is not known to this version of the protocol.
   #[serde(untagged)]
   String(String),
 }
@@ -1115,7 +1394,7 @@ pub enum ScopePresentationhint {
 /// within a source.
 ///
 /// Specification: [Scope](https://microsoft.github.io/debug-adapter-protocol/specification#Types_Scope)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Scope {
@@ -1171,7 +1450,7 @@ pub struct Scope {
   pub end_column: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum StackFrameModuleid {
@@ -1179,19 +1458,23 @@ pub enum StackFrameModuleid {
   String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum StackFramePresentationhint {
   Normal,
   Label,
   Subtle,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
 /// A Stackframe contains the source location.
 ///
 /// Specification: [StackFrame](https://microsoft.github.io/debug-adapter-protocol/specification#Types_StackFrame)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct StackFrame {
@@ -1243,7 +1526,7 @@ pub struct StackFrame {
 /// A thread.
 ///
 /// Specification: [Thread](https://microsoft.github.io/debug-adapter-protocol/specification#Types_Thread)
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Thread {
@@ -1268,7 +1551,7 @@ pub struct Thread {
 ///
 /// The client can use this information to present the children in a paged UI and fetch them in
 /// chunks.
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub struct Variable {
@@ -1317,31 +1600,54 @@ pub struct Variable {
   /// `supportsMemoryReferences` is true.
   #[serde(skip_serializing_if = "Option::is_none")]
   pub memory_reference: Option<String>,
+  /// A reference that allows the client to request the location where the
+  /// variable is declared. This should be present only if the adapter is
+  /// likely to be able to resolve the location.
+  ///
+  /// This reference shares the same lifetime as the `variablesReference`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub declaration_location_reference: Option<i64>,
+  /// A reference that allows the client to request the location where the
+  /// variable's value is declared. For example, if the variable contains a
+  /// function pointer, the adapter may be able to look up the function's
+  /// location. This should be present only if the adapter is likely to be able
+  /// to resolve the location.
+  ///
+  /// This reference shares the same lifetime as the `variablesReference`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub value_location_reference: Option<i64>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum RunInTerminalRequestArgumentsKind {
   Integrated,
   External,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
 pub enum StartDebuggingRequestKind {
   Launch,
   Attach,
+  /// A value that is not known to this version of the protocol.
+  #[serde(untagged)]
+  #[cfg_attr(feature = "integration_testing", dummy(skip))]
+  String(String),
 }
 
 /// A structured message object. Used to return errors from requests.
 ///
 /// Specification: [Message](https://microsoft.github.io/debug-adapter-protocol/specification#Types_Message)
-#[derive(Serialize, Debug, Default, Clone)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "integration_testing", derive(Dummy))]
-#[cfg_attr(feature = "client", derive(Deserialize))]
 pub struct Message {
   /// Unique (within a debug adapter implementation) identifier for the message.
   /// The purpose of these error IDs is to help extension authors that have the
@@ -1398,4 +1704,132 @@ mod tests {
     let untagged_deser: InvalidatedAreas = serde_json::from_value(untagged_ser).unwrap();
     assert!(matches!(InvalidatedAreas::String(str), untagged_deser));
   }
+
+  #[test]
+  fn test_breakpoint_modes() {
+    let capabilities: Capabilities = serde_json::from_value(serde_json::json!({
+      "breakpointModes": [
+        {"mode": "hardware", "label": "Hardware", "appliesTo": ["source", "instruction"]},
+        {"mode": "software", "label": "Software", "appliesTo": ["source", "lineGroup"]},
+      ]
+    }))
+    .unwrap();
+    let modes = capabilities.breakpoint_modes.as_ref().unwrap();
+    assert_eq!(
+      modes[1].applies_to[1],
+      BreakpointModeApplicability::String("lineGroup".to_string())
+    );
+
+    let source = BreakpointModeApplicability::Source;
+    let instruction = BreakpointModeApplicability::Instruction;
+    assert!(capabilities.validate_breakpoint_mode(None, &source).is_ok());
+    assert!(capabilities
+      .validate_breakpoint_mode(Some("hardware"), &instruction)
+      .is_ok());
+    assert_eq!(
+      capabilities
+        .breakpoint_mode("software", &source)
+        .map(|mode| mode.label.as_str()),
+      Some("Software")
+    );
+    assert!(matches!(
+      capabilities.validate_breakpoint_mode(Some("software"), &instruction),
+      Err(BreakpointModeError::NotApplicable { .. })
+    ));
+    let err = capabilities
+      .validate_breakpoint_mode(Some("jtag"), &source)
+      .unwrap_err();
+    assert_eq!(err.to_string(), "Unknown breakpoint mode 'jtag'");
+    assert!(Capabilities::default()
+      .validate_breakpoint_mode(Some("hardware"), &source)
+      .is_err());
+
+    let breakpoint = SourceBreakpoint {
+      line: 3,
+      mode: Some("hardware".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      serde_json::to_value(breakpoint).unwrap(),
+      serde_json::json!({"line": 3, "mode": "hardware"})
+    );
+  }
+
+  fn assert_round_trips<T>(value: &str)
+  where
+    T: serde::de::DeserializeOwned + Serialize + std::fmt::Debug,
+  {
+    let parsed: T = serde_json::from_value(Value::String(value.to_string()))
+      .unwrap_or_else(|e| panic!("'{}' failed to parse: {}", value, e));
+    assert_eq!(
+      serde_json::to_value(&parsed).unwrap(),
+      value,
+      "{:?}",
+      parsed
+    );
+  }
+
+  #[test]
+  fn test_unknown_enum_values() {
+    let newer = "fromANewerSpec";
+    assert_round_trips::<ColumnDescriptorType>(newer);
+    assert_round_trips::<ChecksumAlgorithm>(newer);
+    assert_round_trips::<BreakpointModeApplicability>(newer);
+    assert_round_trips::<PresentationHint>(newer);
+    assert_round_trips::<ExceptionBreakMode>(newer);
+    assert_round_trips::<BreakpointEventReason>(newer);
+    assert_round_trips::<InvalidatedAreas>(newer);
+    assert_round_trips::<LoadedSourceEventReason>(newer);
+    assert_round_trips::<ModuleEventReason>(newer);
+    assert_round_trips::<OutputEventCategory>(newer);
+    assert_round_trips::<OutputEventGroup>(newer);
+    assert_round_trips::<ProcessEventStartMethod>(newer);
+    assert_round_trips::<StoppedEventReason>(newer);
+    assert_round_trips::<ThreadEventReason>(newer);
+    assert_round_trips::<EvaluateArgumentsContext>(newer);
+    assert_round_trips::<SteppingGranularity>(newer);
+    assert_round_trips::<DataBreakpointAccessType>(newer);
+    assert_round_trips::<VariablesArgumentsFilter>(newer);
+    assert_round_trips::<CompletionItemType>(newer);
+    assert_round_trips::<VariablePresentationHintKind>(newer);
+    assert_round_trips::<VariablePresentationHintAttributes>(newer);
+    assert_round_trips::<VariablePresentationHintVisibility>(newer);
+    assert_round_trips::<ScopePresentationhint>(newer);
+    assert_round_trips::<StackFramePresentationhint>(newer);
+    assert_round_trips::<RunInTerminalRequestArgumentsKind>(newer);
+    assert_round_trips::<StartDebuggingRequestKind>(newer);
+
+    // Known values still map to their variants.
+    assert_round_trips::<ColumnDescriptorType>("string");
+    assert_round_trips::<StoppedEventReason>("function breakpoint");
+    assert_round_trips::<CompletionItemType>("customcolor");
+    assert!(matches!(
+      serde_json::from_str::<StoppedEventReason>("\"data breakpoint\"").unwrap(),
+      StoppedEventReason::Data
+    ));
+    assert!(matches!(
+      serde_json::from_str::<ColumnDescriptorType>("\"string\"").unwrap(),
+      ColumnDescriptorType::String
+    ));
+    assert!(matches!(
+      serde_json::from_str::<SteppingGranularity>("\"line\"").unwrap(),
+      SteppingGranularity::Line
+    ));
+  }
+
+  #[test]
+  fn test_request_with_unknown_enum_value() {
+    let request: crate::requests::Request = serde_json::from_str(
+      r#"{"seq": 4, "type": "request", "command": "next",
+          "arguments": {"threadId": 1, "granularity": "basicBlock"}}"#,
+    )
+    .unwrap();
+    match request.command {
+      crate::requests::Command::Next(args) => assert!(matches!(
+        args.granularity,
+        Some(SteppingGranularity::String(ref value)) if value == "basicBlock"
+      )),
+      other => panic!("unexpected command {:?}", other),
+    }
+  }
 }
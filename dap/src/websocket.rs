@@ -0,0 +1,345 @@
+//! DAP over WebSocket.
+//!
+//! Each DAP message is carried in a single text frame, without the `Content-Length` header that
+//! is used on stream transports. [`WebSocketReader`] and [`WebSocketWriter`] translate between
+//! the two framings, so a [`Server`] can be run over a WebSocket connection unchanged.
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+use tungstenite::{
+  handshake::{HandshakeError, HandshakeRole},
+  http::Uri,
+  Message, WebSocket,
+};
+
+use crate::{
+  errors::{DeserializationError, ServerError},
+  server::Server,
+};
+
+/// How long a blocked read holds on to the connection before giving writers a chance to send.
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A WebSocket connection that carries DAP messages.
+///
+/// The connection can be cloned and shared between threads, e.g. to send events while another
+/// thread is waiting for the next message. Ping frames are answered automatically and a close
+/// frame from the peer ends the stream of messages.
+#[derive(Clone)]
+pub struct WebSocketConnection {
+  socket: Arc<Mutex<WebSocket<TcpStream>>>,
+}
+
+impl WebSocketConnection {
+  /// Perform the server side of the WebSocket handshake on an accepted TCP connection.
+  pub fn accept(stream: TcpStream) -> Result<Self, ServerError> {
+    let socket = tungstenite::accept(stream).map_err(handshake_error)?;
+    Self::from_socket(socket)
+  }
+
+  /// Connect to a WebSocket server at the given `ws://` URL.
+  pub fn connect(url: &str) -> Result<Self, ServerError> {
+    let uri: Uri = url.parse().map_err(|_| ServerError::InvalidTransport {
+      spec: url.to_string(),
+      reason: "invalid URL".to_string(),
+    })?;
+    let host = uri.host().ok_or_else(|| ServerError::InvalidTransport {
+      spec: url.to_string(),
+      reason: "missing host".to_string(),
+    })?;
+    let stream =
+      TcpStream::connect((host, uri.port_u16().unwrap_or(80))).map_err(ServerError::IoError)?;
+    let (socket, _) = tungstenite::client(url, stream).map_err(handshake_error)?;
+    Self::from_socket(socket)
+  }
+
+  /// Wrap an already established WebSocket.
+  pub fn from_socket(socket: WebSocket<TcpStream>) -> Result<Self, ServerError> {
+    // Reads must time out regularly, otherwise a reader waiting for the next message would
+    // block all writers.
+    socket
+      .get_ref()
+      .set_read_timeout(Some(READ_POLL_INTERVAL))
+      .map_err(ServerError::IoError)?;
+    Ok(Self {
+      socket: Arc::new(Mutex::new(socket)),
+    })
+  }
+
+  /// Serialize `message` and send it as a single text frame.
+  pub fn send<T: Serialize>(&self, message: &T) -> Result<(), ServerError> {
+    let text = serde_json::to_string(message).map_err(ServerError::SerializationError)?;
+    self.send_text(text)
+  }
+
+  /// Wait for the next message and deserialize it. Returns `None` once the connection is closed.
+  pub fn receive<T: DeserializeOwned>(&self) -> Result<Option<T>, ServerError> {
+    match self.receive_text()? {
+      Some(text) => serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| ServerError::ParseError(DeserializationError::SerdeError(e))),
+      None => Ok(None),
+    }
+  }
+
+  /// Start the closing handshake.
+  pub fn close(&self) -> Result<(), ServerError> {
+    match self.lock()?.close(None) {
+      Ok(()) | Err(tungstenite::Error::ConnectionClosed) => Ok(()),
+      Err(e) => Err(websocket_error(e)),
+    }
+  }
+
+  /// A reader that yields the incoming messages with `Content-Length` headers.
+  pub fn reader(&self) -> WebSocketReader {
+    WebSocketReader {
+      connection: self.clone(),
+      pending: Vec::new(),
+      position: 0,
+    }
+  }
+
+  /// A writer that expects messages with `Content-Length` headers and sends each of them as a
+  /// text frame.
+  pub fn writer(&self) -> WebSocketWriter {
+    WebSocketWriter {
+      connection: self.clone(),
+      buffer: Vec::new(),
+    }
+  }
+
+  fn send_text(&self, text: String) -> Result<(), ServerError> {
+    self
+      .lock()?
+      .send(Message::Text(text))
+      .map_err(websocket_error)
+  }
+
+  fn receive_text(&self) -> Result<Option<String>, ServerError> {
+    loop {
+      let mut socket = self.lock()?;
+      match socket.read() {
+        Ok(Message::Text(text)) => return Ok(Some(text)),
+        Ok(Message::Binary(data)) => {
+          return String::from_utf8(data).map(Some).map_err(|e| {
+            ServerError::ParseError(DeserializationError::DecodingError(e.utf8_error()))
+          })
+        }
+        // The pong is queued by tungstenite, make sure it goes out right away.
+        Ok(Message::Ping(_)) => socket.flush().map_err(websocket_error)?,
+        Ok(Message::Pong(_)) | Ok(Message::Frame(_)) => {}
+        Ok(Message::Close(_)) => {
+          // Sends the close frame that tungstenite queued in reply.
+          let _ = socket.flush();
+          return Ok(None);
+        }
+        Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+          return Ok(None)
+        }
+        Err(tungstenite::Error::Io(e))
+          if matches!(
+            e.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+          ) =>
+        {
+          drop(socket);
+          thread::sleep(Duration::from_millis(1));
+        }
+        Err(e) => return Err(websocket_error(e)),
+      }
+    }
+  }
+
+  fn lock(&self) -> Result<MutexGuard<'_, WebSocket<TcpStream>>, ServerError> {
+    self.socket.lock().map_err(|_| ServerError::OutputLockError)
+  }
+}
+
+impl Server<WebSocketReader, WebSocketWriter> {
+  /// Construct a new Server that communicates over a WebSocket connection.
+  pub fn from_websocket(connection: WebSocketConnection) -> Self {
+    Server::new(
+      io::BufReader::new(connection.reader()),
+      io::BufWriter::new(connection.writer()),
+    )
+  }
+}
+
+/// Presents the text frames of a [`WebSocketConnection`] as a `Content-Length` framed stream.
+pub struct WebSocketReader {
+  connection: WebSocketConnection,
+  pending: Vec<u8>,
+  position: usize,
+}
+
+impl Read for WebSocketReader {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if self.position == self.pending.len() {
+      match self.connection.receive_text().map_err(to_io_error)? {
+        Some(text) => {
+          self.pending = format!("Content-Length: {}\r\n\r\n{}", text.len(), text).into_bytes();
+          self.position = 0;
+        }
+        None => return Ok(0),
+      }
+    }
+    let size = buf.len().min(self.pending.len() - self.position);
+    buf[..size].copy_from_slice(&self.pending[self.position..self.position + size]);
+    self.position += size;
+    Ok(size)
+  }
+}
+
+/// Sends each `Content-Length` framed message written to it as a text frame of a
+/// [`WebSocketConnection`].
+pub struct WebSocketWriter {
+  connection: WebSocketConnection,
+  buffer: Vec<u8>,
+}
+
+impl WebSocketWriter {
+  /// Remove the next complete message from the buffer, if there is one.
+  fn take_message(&mut self) -> io::Result<Option<String>> {
+    // Skip the line break that follows the content of each message.
+    let start = self
+      .buffer
+      .iter()
+      .position(|b| !matches!(b, b'\r' | b'\n'))
+      .unwrap_or(self.buffer.len());
+    self.buffer.drain(..start);
+
+    let header_end = match self.buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+      Some(pos) => pos,
+      None => return Ok(None),
+    };
+    let length = std::str::from_utf8(&self.buffer[..header_end])
+      .ok()
+      .and_then(|header| header.strip_prefix("Content-Length:"))
+      .and_then(|length| length.trim().parse::<usize>().ok())
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid message header"))?;
+    let content_start = header_end + 4;
+    if self.buffer.len() < content_start + length {
+      return Ok(None);
+    }
+    let content = self.buffer[content_start..content_start + length].to_vec();
+    self.buffer.drain(..content_start + length);
+    String::from_utf8(content)
+      .map(Some)
+      .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+}
+
+impl Write for WebSocketWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.buffer.extend_from_slice(buf);
+    while let Some(message) = self.take_message()? {
+      self.connection.send_text(message).map_err(to_io_error)?;
+    }
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+fn handshake_error<R: HandshakeRole>(error: HandshakeError<R>) -> ServerError {
+  match error {
+    HandshakeError::Failure(e) => websocket_error(e),
+    HandshakeError::Interrupted(_) => ServerError::IoError(io::ErrorKind::WouldBlock.into()),
+  }
+}
+
+fn websocket_error(error: tungstenite::Error) -> ServerError {
+  match error {
+    tungstenite::Error::Io(e) => ServerError::IoError(e),
+    other => ServerError::WebSocketError(Box::new(other)),
+  }
+}
+
+fn to_io_error(error: ServerError) -> io::Error {
+  match error {
+    ServerError::IoError(e) => e,
+    other => io::Error::new(io::ErrorKind::Other, other),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::TcpListener;
+
+  use serde_json::{json, Value};
+
+  use super::*;
+  use crate::{events::Event, requests::Command};
+
+  fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    (listener, url)
+  }
+
+  #[test]
+  fn test_server_over_websocket() {
+    let (listener, url) = listen();
+    let adapter = thread::spawn(move || {
+      let (stream, _) = listener.accept().unwrap();
+      let mut server = Server::from_websocket(WebSocketConnection::accept(stream).unwrap());
+      let req = server.poll_request().unwrap().unwrap();
+      assert!(matches!(req.command, Command::Initialize(_)));
+      server.send_event(Event::Initialized).unwrap();
+      // The client closes the connection after receiving the event.
+      server.poll_request().unwrap().is_none()
+    });
+
+    let stream = TcpStream::connect(url.trim_start_matches("ws://")).unwrap();
+    let (mut client, _) = tungstenite::client(url.as_str(), stream).unwrap();
+    client.send(Message::Ping(b"ping".to_vec())).unwrap();
+    let request = json!({
+      "seq": 1,
+      "type": "request",
+      "command": "initialize",
+      "arguments": {"adapterID": "test"}
+    });
+    client.send(Message::Text(request.to_string())).unwrap();
+
+    assert_eq!(client.read().unwrap(), Message::Pong(b"ping".to_vec()));
+    let event: Value = match client.read().unwrap() {
+      Message::Text(text) => serde_json::from_str(&text).unwrap(),
+      other => panic!("Expected a text frame, got {:?}", other),
+    };
+    assert_eq!(event["type"], "event");
+    assert_eq!(event["event"], "initialized");
+
+    client.close(None).unwrap();
+    while client.read().is_ok() {}
+    assert!(adapter.join().unwrap());
+  }
+
+  #[test]
+  fn test_client_connection() {
+    let (listener, url) = listen();
+    let adapter = thread::spawn(move || {
+      let (stream, _) = listener.accept().unwrap();
+      let connection = WebSocketConnection::accept(stream).unwrap();
+      let request: Value = connection.receive().unwrap().unwrap();
+      connection
+        .send(&json!({"seq": 1, "type": "response", "request_seq": request["seq"]}))
+        .unwrap();
+      connection.receive::<Value>().unwrap().is_none()
+    });
+
+    let connection = WebSocketConnection::connect(&url).unwrap();
+    connection
+      .send(&json!({"seq": 7, "type": "request", "command": "threads"}))
+      .unwrap();
+    let response: Value = connection.receive().unwrap().unwrap();
+    assert_eq!(response["request_seq"], 7);
+    connection.close().unwrap();
+    assert!(adapter.join().unwrap());
+  }
+}
@@ -1,20 +1,114 @@
 use quote::{format_ident, quote};
 use rustfmt_wrapper::rustfmt;
 use std::{
-  collections::{HashMap, HashSet},
+  collections::HashSet,
   env,
   fs::File,
   io::Write,
   path::{Path, PathBuf},
 };
+use syn::{punctuated::Punctuated, Token};
 
 type DynResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+/// An enum of the dap crate whose variants are validated against the schema.
+struct Target {
+  /// The file in `dap/src` that defines the enum.
+  file: &'static str,
+  /// The name of the enum.
+  enum_name: &'static str,
+  /// Appended to the variant name to get the schema definition, e.g. `Stopped` -> `StoppedEvent`.
+  definition_suffix: &'static str,
+  /// Appended to the lowercase variant name in the test names.
+  test_suffix: &'static str,
+  /// The function in `src/lib.rs` that turns the enum into a message as it is sent on the wire.
+  to_value: &'static str,
+}
+
+const TARGETS: &[Target] = &[
+  Target {
+    file: "responses.rs",
+    enum_name: "ResponseBody",
+    definition_suffix: "Response",
+    test_suffix: "response",
+    to_value: "body_to_value",
+  },
+  Target {
+    file: "requests.rs",
+    enum_name: "Command",
+    definition_suffix: "Request",
+    test_suffix: "request",
+    to_value: "command_to_value",
+  },
+  Target {
+    file: "events.rs",
+    enum_name: "Event",
+    definition_suffix: "Event",
+    test_suffix: "event",
+    to_value: "event_to_value",
+  },
+  Target {
+    file: "reverse_requests.rs",
+    enum_name: "ReverseCommand",
+    definition_suffix: "Request",
+    test_suffix: "reverse_request",
+    to_value: "reverse_command_to_value",
+  },
+];
+
 fn parse_file(path: &Path) -> DynResult<syn::File> {
   let content = std::fs::read_to_string(path)?;
   syn::parse_file(&content).map_err(|e| e.into())
 }
 
+fn find_enum(ast: syn::File, name: &str) -> Option<syn::ItemEnum> {
+  ast.items.into_iter().find_map(|item| match item {
+    syn::Item::Enum(e) if e.ident == name => Some(e),
+    _ => None,
+  })
+}
+
+/// Collects the names of the types that derive or implement `Default`.
+fn collect_default_types(ast: &syn::File, names: &mut HashSet<String>) {
+  for item in &ast.items {
+    let (ident, attrs) = match item {
+      syn::Item::Struct(s) => (&s.ident, &s.attrs),
+      syn::Item::Enum(e) => (&e.ident, &e.attrs),
+      syn::Item::Impl(syn::ItemImpl {
+        trait_: Some((_, path, _)),
+        self_ty,
+        ..
+      }) if path.is_ident("Default") => {
+        if let syn::Type::Path(ty) = self_ty.as_ref() {
+          names.insert(ty.path.segments.last().unwrap().ident.to_string());
+        }
+        continue;
+      }
+      _ => continue,
+    };
+    let derives_default = attrs.iter().any(|attr| {
+      attr.path().is_ident("derive")
+        && attr
+          .parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+          .map(|paths| paths.iter().any(|path| path.is_ident("Default")))
+          .unwrap_or(false)
+    });
+    if derives_default {
+      names.insert(ident.to_string());
+    }
+  }
+}
+
+fn has_default(ty: &syn::Type, default_types: &HashSet<String>) -> bool {
+  match ty {
+    syn::Type::Path(ty) => {
+      let ident = ty.path.segments.last().unwrap().ident.to_string();
+      ident == "Option" || default_types.contains(&ident)
+    }
+    _ => false,
+  }
+}
+
 fn main() -> DynResult<()> {
   let out_dir = env::var_os("OUT_DIR").unwrap();
   let manifest_dir = env::var("CARGO_MANIFEST_DIR")?;
@@ -24,130 +118,81 @@ fn main() -> DynResult<()> {
   let mut f = File::create(dest_path)?;
   let dap_src = manifest_path.parent().unwrap().join("dap").join("src");
 
-  let ast = parse_file(&dap_src.join("responses.rs"))?;
-  let resp_to_body_ty = HashMap::from([("Initialize".to_string(), "Capabilities")]);
-
-  let skipped_responses: HashSet<_> = [
-    // These responses are skipped because the schema for Source (which they contain) is
-    // self-referential and we can't load that the way we're doing it now (by manually dereferencing
-    // the $ref's in the schema).
-    // This is something that jsonschema should explicitly develop support for.
-    "LoadedSources",
-    "Scopes",
+  let skipped_variants: HashSet<_> = [
+    // Added in a later version of the specification than the schema these tests are run against.
+    "Locations",
   ]
   .iter()
   .map(|s| s.to_string())
   .collect();
 
-  for item in ast.items {
-    if let syn::Item::Enum(e) = item {
-      if e.ident != "ResponseBody" {
-        continue;
-      }
-      for variant in e.variants {
-        if skipped_responses.contains(&variant.ident.to_string()) {
-          continue;
-        }
-        let test_name = format_ident!(
-          "validate_{}_response",
-          variant.ident.to_string().to_lowercase()
-        );
-
-        let mut init_part = quote! {};
-        if let syn::Fields::Unnamed(fields) = variant.fields.clone() {
-          for field in fields.unnamed {
-            if let syn::Type::Path(ty) = field.ty {
-              let fieldname = &ty.path.segments[0].ident;
-              init_part = quote! { (#fieldname::default()) };
-              break;
-            }
-          }
-        }
+  let skipped_fakes: HashSet<_> = [
+    // The schema allows either launch or attach arguments, as a `oneOf`. Both are objects with
+    // optional properties only, so any arguments beyond the defaults match both and fail.
+    ("Command", "Restart"),
+  ]
+  .iter()
+  .map(|(e, v)| (e.to_string(), v.to_string()))
+  .collect();
 
-        let ident = format_ident!("{}", variant.ident.to_string());
-        let schema_ident = format!("{}Response", variant.ident);
+  let mut default_types = HashSet::new();
+  for entry in std::fs::read_dir(&dap_src)? {
+    let path = entry?.path();
+    if path.extension().is_some_and(|ext| ext == "rs") {
+      println!("cargo:rerun-if-changed={}", path.display());
+      collect_default_types(&parse_file(&path)?, &mut default_types);
+    }
+  }
 
-        let test_fn = quote! {
-          #[test]
-          fn #test_name() {
-            let schema = get_schema(#schema_ident);
-            let compiled = JSONSchema::options()
-              .with_document("/".to_string(), schema.clone())
-              .compile(&schema)
-              .unwrap();
-            let resp = Response {
-              request_seq: 1,
-              success: true,
-              message: None,
-              body: Some(ResponseBody::#ident #init_part),
-              error: None,
-            };
-            let instance = resp_to_value(&resp);
-            let result = compiled.validate(&instance);
-            if let Err(errors) = result {
-              for error in errors {
-                eprintln!("Validation error: {}", error);
-                eprintln!("Instance path: {}", error.instance_path);
-              }
-            }
-            assert!(compiled.is_valid(&instance));
-          }
-        };
-        writeln!(f, "{}", rustfmt(test_fn).unwrap())?;
+  for target in TARGETS {
+    let e = find_enum(parse_file(&dap_src.join(target.file))?, target.enum_name)
+      .ok_or_else(|| format!("{} not found in {}", target.enum_name, target.file))?;
+    let enum_ident = format_ident!("{}", target.enum_name);
+    let to_value = format_ident!("{}", target.to_value);
 
-        let test_name = format_ident!(
-          "validate_fake_{}_response",
-          variant.ident.to_string().to_lowercase()
-        );
-        let resp_ty = if resp_to_body_ty.contains_key(&variant.ident.to_string()) {
-          format_ident!(
-            "{}",
-            resp_to_body_ty.get(&variant.ident.to_string()).unwrap()
-          )
-        } else {
-          format_ident!("{}Response", variant.ident)
-        };
+    for variant in e.variants {
+      if skipped_variants.contains(&variant.ident.to_string()) {
+        continue;
+      }
+      let ident = &variant.ident;
+      let lowercase = ident.to_string().to_lowercase();
+      let definition = format!("{}{}", ident, target.definition_suffix);
+      let payload_ty = match &variant.fields {
+        syn::Fields::Unnamed(fields) => fields.unnamed.first().map(|field| field.ty.clone()),
+        _ => None,
+      };
 
-        let mut init_part = quote! {};
-        let mut create_body = quote! {};
-        if let syn::Fields::Unnamed(fields) = variant.fields {
-          for field in fields.unnamed {
-            if let syn::Type::Path(_) = field.ty {
-              init_part = quote! { (body) };
-              create_body = quote! {
-                let rng = &mut StdRng::from_seed(RNG_SEED);
-                let body: #resp_ty = Faker.fake_with_rng(rng);
-              };
-              break;
-            }
-          }
-        }
+      // Payloads without a `Default` (because they have required fields without a sensible
+      // default) are only validated with faked values.
+      let (default_payload, fake_payload, init_part) = match payload_ty {
+        Some(ty) => (
+          has_default(&ty, &default_types).then(|| quote! { let payload = <#ty>::default(); }),
+          quote! {
+            let rng = &mut StdRng::from_seed(RNG_SEED);
+            let payload: #ty = Faker.fake_with_rng(rng);
+          },
+          quote! { (payload) },
+        ),
+        None => (Some(quote! {}), quote! {}, quote! {}),
+      };
 
+      let skip_fake = skipped_fakes.contains(&(target.enum_name.to_string(), ident.to_string()));
+      let tests = [
+        ("validate", default_payload),
+        ("validate_fake", Some(fake_payload).filter(|_| !skip_fake)),
+      ];
+      for (prefix, create_payload) in tests {
+        let create_payload = match create_payload {
+          Some(create_payload) => create_payload,
+          None => continue,
+        };
+        let test_name = format_ident!("{}_{}_{}", prefix, lowercase, target.test_suffix);
         let test_fn = quote! {
           #[test]
           fn #test_name() {
-            let schema = get_schema(#schema_ident);
-            let compiled = JSONSchema::options()
-              .with_document("/".to_string(), schema.clone())
-              .compile(&schema)
-              .unwrap();
-            #create_body
-            let resp = Response {
-              request_seq: 1,
-              success: true,
-              message: None,
-              body: Some(ResponseBody::#ident #init_part),
-              error: None,
-            };
-            let instance = resp_to_value(&resp);
-            let result = compiled.validate(&instance);
-            if let Err(errors) = result {
-              for error in errors {
-                eprintln!("Validation error: {}", error);
-                eprintln!("Instance path: {}", error.instance_path);
-              }
-            }
-            assert!(compiled.is_valid(&instance));
+            #create_payload
+            let instance = #to_value(#enum_ident::#ident #init_part);
+            validate(#definition, &instance);
           }
         };
         writeln!(f, "{}", rustfmt(test_fn).unwrap())?;
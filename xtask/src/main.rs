@@ -0,0 +1,90 @@
+//! Development tasks, run with `cargo xtask <task>`.
+//!
+//! ```text
+//! cargo xtask schema-check [--schema <file>]
+//! ```
+//!
+//! The protocol types in `dap/src` are written by hand. `schema-check` reports the parts of the
+//! schema in `dap/schema` that they don't cover. To update to a new version of the specification,
+//! replace the schema file and add what the check reports.
+mod schema_check;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use serde_json::Value;
+
+const USAGE: &str = "\
+usage: cargo xtask schema-check [--schema <file>]
+
+  --schema <file>  the DAP JSON schema (default: the only schema in dap/schema)";
+
+fn workspace_root() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .parent()
+    .expect("xtask is a workspace member")
+    .to_path_buf()
+}
+
+/// The schema bundled with the crate. Its file name starts with the commit of the specification
+/// repository it was fetched from, so there must be exactly one.
+fn default_schema(root: &Path) -> Result<PathBuf, String> {
+  let dir = root.join("dap").join("schema");
+  let schemas: Vec<PathBuf> = fs::read_dir(&dir)
+    .map_err(|e| format!("could not read {}: {}", dir.display(), e))?
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    .collect();
+  match schemas.as_slice() {
+    [schema] => Ok(schema.clone()),
+    [] => Err(format!("no schema in {}", dir.display())),
+    _ => Err(format!(
+      "more than one schema in {}, pick one with --schema",
+      dir.display()
+    )),
+  }
+}
+
+fn schema_check(args: &[String]) -> Result<(), String> {
+  let root = workspace_root();
+  let schema = match args {
+    [] => default_schema(&root)?,
+    [flag, schema] if flag == "--schema" => PathBuf::from(schema),
+    _ => return Err(USAGE.to_string()),
+  };
+
+  let contents = fs::read_to_string(&schema)
+    .map_err(|e| format!("could not read {}: {}", schema.display(), e))?;
+  let parsed: Value = serde_json::from_str(&contents)
+    .map_err(|e| format!("{} is not valid JSON: {}", schema.display(), e))?;
+  let types = schema_check::Types::load(&root.join("dap").join("src"))?;
+  let problems = schema_check::check(&parsed, &types)?;
+  for problem in &problems {
+    println!("{}", problem);
+  }
+  if !problems.is_empty() {
+    return Err(format!(
+      "{} parts of {} are not covered by the types in dap/src",
+      problems.len(),
+      schema.display()
+    ));
+  }
+  println!("the types in dap/src cover {}", schema.display());
+  Ok(())
+}
+
+fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let result = match args.split_first() {
+    Some((task, rest)) if task == "schema-check" => schema_check(rest),
+    _ => {
+      eprintln!("{}", USAGE);
+      process::exit(2);
+    }
+  };
+  if let Err(e) = result {
+    eprintln!("error: {}", e);
+    process::exit(1);
+  }
+}
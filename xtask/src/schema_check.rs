@@ -0,0 +1,529 @@
+//! Checks that the protocol types in `dap/src` cover `debugAdapterProtocol.json`.
+//!
+//! The types are written by hand. Starting from the messages of the schema, every property is
+//! followed to the Rust type of the field that models it, and the check reports:
+//!
+//! * requests, responses and events without a variant in `Command`, `ResponseBody`, `Event`,
+//!   `ReverseCommand` or `ReverseResponseBody`,
+//! * properties without a field, taking `rename`, `rename_all` and `flatten` into account,
+//! * values of enumerations without a variant.
+//!
+//! The types may model more than the schema, e.g. additions of a newer version of the
+//! specification.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+/// The serde attributes of an item, field or variant that affect its JSON name.
+#[derive(Debug, Default)]
+struct SerdeAttrs {
+  rename: Option<String>,
+  rename_all: Option<String>,
+  flatten: bool,
+  skip: bool,
+  untagged: bool,
+}
+
+impl SerdeAttrs {
+  fn parse(attrs: &[syn::Attribute]) -> Self {
+    let mut parsed = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+      // Attributes that can't be parsed don't affect names, so they are ignored.
+      let _ = attr.parse_nested_meta(|meta| {
+        let value = if meta.input.peek(syn::Token![=]) {
+          match meta.value()?.parse::<syn::Lit>()? {
+            syn::Lit::Str(value) => Some(value.value()),
+            _ => None,
+          }
+        } else {
+          None
+        };
+        let key = meta.path.get_ident().map(ToString::to_string);
+        match key.as_deref() {
+          Some("rename") => parsed.rename = value,
+          Some("rename_all") => parsed.rename_all = value,
+          Some("flatten") => parsed.flatten = true,
+          Some("skip") => parsed.skip = true,
+          Some("untagged") => parsed.untagged = true,
+          _ => {}
+        }
+        Ok(())
+      });
+    }
+    parsed
+  }
+}
+
+#[derive(Debug)]
+enum Item {
+  Struct {
+    /// The JSON name of each field and the name of the type it holds.
+    fields: BTreeMap<String, String>,
+    /// Types whose fields are flattened into this one.
+    flattened: Vec<String>,
+  },
+  Enum {
+    /// The JSON name of each variant and the name of the type it carries, if any.
+    variants: BTreeMap<String, Option<String>>,
+  },
+}
+
+/// The structs and enums of the dap crate, as serde sees them.
+#[derive(Debug, Default)]
+pub struct Types {
+  items: HashMap<String, Item>,
+}
+
+impl Types {
+  /// Collect the types of all modules in `dir`.
+  pub fn load(dir: &Path) -> Result<Self, String> {
+    let mut types = Types::default();
+    let entries =
+      fs::read_dir(dir).map_err(|e| format!("could not read {}: {}", dir.display(), e))?;
+    for entry in entries {
+      let path = entry.map_err(|e| e.to_string())?.path();
+      if path.extension().is_some_and(|ext| ext == "rs") {
+        let source = fs::read_to_string(&path)
+          .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        types
+          .add(&source)
+          .map_err(|e| format!("could not parse {}: {}", path.display(), e))?;
+      }
+    }
+    Ok(types)
+  }
+
+  fn add(&mut self, source: &str) -> Result<(), syn::Error> {
+    for item in syn::parse_file(source)?.items {
+      match item {
+        syn::Item::Struct(item) => {
+          let container = SerdeAttrs::parse(&item.attrs);
+          let mut fields = BTreeMap::new();
+          let mut flattened = Vec::new();
+          for field in &item.fields {
+            let (Some(ident), Some(ty)) = (&field.ident, type_name(&field.ty)) else {
+              continue;
+            };
+            let attrs = SerdeAttrs::parse(&field.attrs);
+            if attrs.skip {
+              continue;
+            }
+            if attrs.flatten {
+              flattened.push(ty);
+              continue;
+            }
+            let name = ident.to_string();
+            let json_name = attrs.rename.unwrap_or_else(|| {
+              rename(
+                name.trim_start_matches("r#"),
+                container.rename_all.as_deref(),
+              )
+            });
+            fields.insert(json_name, ty);
+          }
+          self
+            .items
+            .insert(item.ident.to_string(), Item::Struct { fields, flattened });
+        }
+        syn::Item::Enum(item) => {
+          let container = SerdeAttrs::parse(&item.attrs);
+          let mut variants = BTreeMap::new();
+          for variant in &item.variants {
+            let attrs = SerdeAttrs::parse(&variant.attrs);
+            // Catch-all variants for values this crate doesn't know.
+            if attrs.untagged || attrs.skip {
+              continue;
+            }
+            let json_name = attrs.rename.unwrap_or_else(|| {
+              rename(&variant.ident.to_string(), container.rename_all.as_deref())
+            });
+            let payload = match &variant.fields {
+              syn::Fields::Unnamed(fields) => fields.unnamed.first().and_then(|f| type_name(&f.ty)),
+              _ => None,
+            };
+            variants.insert(json_name, payload);
+          }
+          self
+            .items
+            .insert(item.ident.to_string(), Item::Enum { variants });
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  /// The fields of a struct including the flattened ones, or `None` if `name` is not a struct.
+  fn fields(&self, name: &str) -> Option<BTreeMap<String, String>> {
+    match self.items.get(name)? {
+      Item::Struct { fields, flattened } => {
+        let mut all = fields.clone();
+        for ty in flattened {
+          all.extend(self.fields(ty).unwrap_or_default());
+        }
+        Some(all)
+      }
+      Item::Enum { .. } => None,
+    }
+  }
+
+  fn variants(&self, name: &str) -> Option<&BTreeMap<String, Option<String>>> {
+    match self.items.get(name)? {
+      Item::Enum { variants } => Some(variants),
+      Item::Struct { .. } => None,
+    }
+  }
+}
+
+/// The JSON name serde gives a field or variant.
+fn rename(name: &str, rename_all: Option<&str>) -> String {
+  match rename_all {
+    Some("lowercase") => name.to_lowercase(),
+    Some("camelCase") if name.contains('_') || name.starts_with(char::is_lowercase) => {
+      let mut words = name.split('_');
+      let first = words.next().unwrap_or_default().to_string();
+      first + &words.map(capitalize).collect::<String>()
+    }
+    Some("camelCase") => lower_first(name),
+    _ => name.to_string(),
+  }
+}
+
+/// The name of the type a field holds, looking through `Option`, `Vec`, `Box` and the values of
+/// maps.
+fn type_name(ty: &syn::Type) -> Option<String> {
+  let syn::Type::Path(path) = ty else {
+    return None;
+  };
+  let segment = path.path.segments.last()?;
+  let name = segment.ident.to_string();
+  if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+    if matches!(
+      name.as_str(),
+      "Option" | "Vec" | "Box" | "HashMap" | "BTreeMap"
+    ) {
+      let inner = arguments
+        .args
+        .iter()
+        .rev()
+        .find_map(|argument| match argument {
+          syn::GenericArgument::Type(ty) => Some(ty),
+          _ => None,
+        })?;
+      return type_name(inner);
+    }
+  }
+  Some(name)
+}
+
+struct Checker<'a> {
+  definitions: &'a serde_json::Map<String, Value>,
+  types: &'a Types,
+  /// Pairs of definitions and Rust types that were already compared.
+  visited: BTreeSet<(String, String)>,
+  problems: Vec<String>,
+}
+
+/// Compare the schema with the types. Returns a description of every gap.
+pub fn check(schema: &Value, types: &Types) -> Result<Vec<String>, String> {
+  let definitions = schema["definitions"]
+    .as_object()
+    .ok_or("the schema has no definitions")?;
+  let mut checker = Checker {
+    definitions,
+    types,
+    visited: BTreeSet::new(),
+    problems: Vec::new(),
+  };
+  checker.messages()?;
+  checker.problems.sort();
+  Ok(checker.problems)
+}
+
+impl<'a> Checker<'a> {
+  fn messages(&mut self) -> Result<(), String> {
+    for (name, definition) in self.definitions {
+      let properties = &extension(definition)["properties"];
+      // Reverse requests are not marked consistently in the schema, so requests and responses
+      // may be modeled by either enum.
+      match base_of(definition) {
+        Some("Request") => {
+          let command = single_enum_value(&properties["command"])
+            .ok_or_else(|| format!("{} has no command", name))?;
+          let targets = ["Command", "ReverseCommand"];
+          self.message(name, &targets, command, properties, "arguments");
+        }
+        Some("Response") if name != "ErrorResponse" => {
+          let command = lower_first(name.trim_end_matches("Response"));
+          let targets = ["ResponseBody", "ReverseResponseBody"];
+          self.message(name, &targets, &command, properties, "body");
+        }
+        Some("Event") => {
+          let event = single_enum_value(&properties["event"])
+            .ok_or_else(|| format!("{} has no event", name))?;
+          self.message(name, &["Event"], event, properties, "body");
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  /// Check that one of `targets` has a variant for a message, and compare its payload with the
+  /// `arguments` or `body` property of the message.
+  fn message(
+    &mut self,
+    definition: &str,
+    targets: &[&str],
+    json_name: &str,
+    properties: &Value,
+    payload_property: &str,
+  ) {
+    let variant = targets
+      .iter()
+      .find_map(|target| self.types.variants(target)?.get(json_name));
+    let payload = &properties[payload_property];
+    let has_properties = match ref_name(payload) {
+      Some(name) => self
+        .definitions
+        .get(name)
+        .is_some_and(|definition| self.inherit(definition)["properties"].is_object()),
+      None => payload["properties"].is_object(),
+    };
+    match variant {
+      None => self.problems.push(format!(
+        "{}: no variant of `{}`",
+        definition,
+        targets.join("` or `")
+      )),
+      Some(Some(ty)) => {
+        let path = format!("{}.{}", definition, payload_property);
+        self.compare(payload, &ty.clone(), &path)
+      }
+      Some(None) if has_properties => self
+        .problems
+        .push(format!("{}: the variant has no payload", definition)),
+      Some(None) => {}
+    }
+  }
+
+  /// Compare a schema with the Rust type that models it.
+  fn compare(&mut self, schema: &Value, ty: &str, path: &str) {
+    if let Some(name) = ref_name(schema) {
+      if !self.visited.insert((name.to_string(), ty.to_string())) {
+        return;
+      }
+      let definition = match self.definitions.get(name) {
+        Some(definition) => self.inherit(definition),
+        None => {
+          self
+            .problems
+            .push(format!("{}: unknown $ref '{}'", path, name));
+          return;
+        }
+      };
+      return self.compare(&definition, ty, name);
+    }
+    if schema["type"] == "array" {
+      return self.compare(&schema["items"], ty, path);
+    }
+
+    let values = schema["enum"].as_array().or(schema["_enum"].as_array());
+    if let (Some(values), Some(variants)) = (values, self.types.variants(ty)) {
+      for value in values.iter().filter_map(Value::as_str) {
+        if !variants.contains_key(value) {
+          self
+            .problems
+            .push(format!("{}: no variant of `{}` for '{}'", path, ty, value));
+        }
+      }
+      return;
+    }
+
+    // Types that aren't structs, such as `Value` or `String`, accept anything.
+    let (Some(properties), Some(fields)) =
+      (schema["properties"].as_object(), self.types.fields(ty))
+    else {
+      return;
+    };
+    for (property, property_schema) in properties {
+      match fields.get(property) {
+        Some(field_ty) => {
+          self.compare(property_schema, field_ty, &format!("{}.{}", path, property))
+        }
+        None => self
+          .problems
+          .push(format!("{}.{}: no field in `{}`", path, property, ty)),
+      }
+    }
+  }
+
+  /// Merge a definition that extends another one, such as `StackFrameFormat` extending
+  /// `ValueFormat`, into a single object definition.
+  fn inherit(&self, definition: &Value) -> Value {
+    let base = match base_of(definition).and_then(|base| self.definitions.get(base)) {
+      Some(base) => self.inherit(base),
+      None => return definition.clone(),
+    };
+    let mut merged = base;
+    let properties = &extension(definition)["properties"];
+    match (merged["properties"].as_object_mut(), properties.as_object()) {
+      (Some(base), Some(more)) => base.extend(more.clone()),
+      (None, Some(_)) => merged["properties"] = properties.clone(),
+      _ => {}
+    }
+    merged
+  }
+}
+
+/// The name of the definition a message extends: `Request`, `Response` or `Event`.
+fn base_of(definition: &Value) -> Option<&str> {
+  ref_name(&definition["allOf"][0])
+}
+
+/// The part of an `allOf` definition that adds to its base.
+fn extension(definition: &Value) -> &Value {
+  &definition["allOf"][1]
+}
+
+fn ref_name(schema: &Value) -> Option<&str> {
+  schema["$ref"]
+    .as_str()
+    .and_then(|r| r.strip_prefix("#/definitions/"))
+}
+
+fn single_enum_value(schema: &Value) -> Option<&str> {
+  schema["enum"][0].as_str()
+}
+
+fn capitalize(name: &str) -> String {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_uppercase().chain(chars).collect(),
+    None => String::new(),
+  }
+}
+
+fn lower_first(name: &str) -> String {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(first) => first.to_lowercase().chain(chars).collect(),
+    None => String::new(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_names() {
+    assert_eq!(rename("client_id", Some("camelCase")), "clientId");
+    assert_eq!(
+      rename("lines_start_at1", Some("camelCase")),
+      "linesStartAt1"
+    );
+    assert_eq!(
+      rename("UnixTimestampUTC", Some("camelCase")),
+      "unixTimestampUTC"
+    );
+    assert_eq!(rename("Attach", Some("lowercase")), "attach");
+  }
+
+  #[test]
+  fn test_check() {
+    let schema = json!({
+      "definitions": {
+        "Request": {}, "Response": {}, "Event": {},
+        "PauseRequest": {"allOf": [{"$ref": "#/definitions/Request"}, {
+          "properties": {
+            "command": {"type": "string", "enum": ["pause"]},
+            "arguments": {"$ref": "#/definitions/PauseArguments"}
+          }
+        }]},
+        "PauseArguments": {"type": "object", "properties": {
+          "threadId": {"type": "integer"},
+          "granularity": {"$ref": "#/definitions/SteppingGranularity"}
+        }},
+        "PauseResponse": {"allOf": [{"$ref": "#/definitions/Response"}, {}]},
+        "StoppedEvent": {"allOf": [{"$ref": "#/definitions/Event"}, {
+          "properties": {
+            "event": {"type": "string", "enum": ["stopped"]},
+            "body": {"type": "object", "properties": {
+              "reason": {"type": "string", "_enum": ["step", "function breakpoint"]},
+              "threadId": {"type": "integer"},
+              "hitBreakpointIds": {"type": "array", "items": {"type": "integer"}}
+            }}
+          }
+        }]},
+        "SteppingGranularity": {"type": "string", "enum": ["statement", "line"]}
+      }
+    });
+    let mut types = Types::default();
+    types
+      .add(
+        r#"
+        #[serde(tag = "command", content = "arguments", rename_all = "camelCase")]
+        pub enum Command {
+          Pause(PauseArguments),
+        }
+        #[serde(rename_all = "camelCase")]
+        pub struct PauseArguments {
+          pub thread_id: i64,
+          pub granularity: Option<SteppingGranularity>,
+        }
+        #[serde(rename_all = "camelCase")]
+        pub enum SteppingGranularity {
+          Statement,
+          #[serde(untagged)]
+          Other(String),
+        }
+        pub enum ResponseBody {}
+        pub enum ReverseResponseBody {}
+        #[serde(tag = "event", content = "body", rename_all = "camelCase")]
+        pub enum Event {
+          Stopped(StoppedEventBody),
+        }
+        #[serde(rename_all = "camelCase")]
+        pub struct StoppedEventBody {
+          pub reason: StoppedEventReason,
+          #[serde(rename = "threadId")]
+          pub thread: Option<i64>,
+        }
+        #[serde(rename_all = "camelCase")]
+        pub enum StoppedEventReason {
+          Step,
+          #[serde(rename = "function breakpoint")]
+          Function,
+        }
+        "#,
+      )
+      .unwrap();
+
+    assert_eq!(
+      check(&schema, &types).unwrap(),
+      vec![
+        "PauseResponse: no variant of `ResponseBody` or `ReverseResponseBody`",
+        "SteppingGranularity: no variant of `SteppingGranularity` for 'line'",
+        "StoppedEvent.body.hitBreakpointIds: no field in `StoppedEventBody`",
+      ]
+    );
+  }
+
+  /// The check `cargo xtask schema-check` runs, so the test suite fails when types and schema
+  /// drift apart.
+  #[test]
+  fn test_dap_types_cover_schema() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let schema = crate::default_schema(&root).unwrap();
+    let schema: Value = serde_json::from_str(&fs::read_to_string(schema).unwrap()).unwrap();
+    let types = Types::load(&root.join("dap").join("src")).unwrap();
+    assert_eq!(check(&schema, &types).unwrap(), Vec::<String>::new());
+  }
+}